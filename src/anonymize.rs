@@ -0,0 +1,187 @@
+//! Scrubbing internal identifiers from exported data, for `--anonymize`.
+//!
+//! Agent ids become `agent-1`, `agent-2`, ... in the order they're first met,
+//! session ids become a short hash (stable, so two exports of one session
+//! still line up), and paths lose the project root. Paths outside the project
+//! keep only their file name. Events also drop their todo lists, which are
+//! free text.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::coverage::{CoverageFormatter, CoverageReport};
+use crate::ingest::AgentToolCall;
+
+/// Stand-in for the directories of a path outside the project.
+const EXTERNAL: &str = "<external>";
+
+/// `session-` and the first 12 hex digits of the id's SHA-256.
+pub fn session_id(id: &str) -> String {
+    let digest = Sha256::digest(id.as_bytes());
+    let hex: String = digest.iter().take(6).map(|b| format!("{b:02x}")).collect();
+    format!("session-{hex}")
+}
+
+/// `path` relative to `root`; absolute paths elsewhere keep only their file name.
+pub fn relative_path(path: &Path, root: &Path) -> PathBuf {
+    match path.strip_prefix(root) {
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) if path.is_absolute() => {
+            Path::new(EXTERNAL).join(path.file_name().unwrap_or_default())
+        }
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Renames agents consistently across everything it scrubs.
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    root: PathBuf,
+    agents: HashMap<String, String>,
+}
+
+impl Anonymizer {
+    pub fn new(project_root: &Path) -> Self {
+        Self { root: project_root.to_path_buf(), agents: HashMap::new() }
+    }
+
+    /// The stand-in for `id`, numbered in first-seen order.
+    pub fn agent(&mut self, id: &str) -> String {
+        let next = self.agents.len() + 1;
+        self.agents.entry(id.to_string()).or_insert_with(|| format!("agent-{next}")).clone()
+    }
+
+    pub fn event(&mut self, event: &mut AgentToolCall) {
+        event.agent_id = self.agent(&event.agent_id);
+        if let Some(ref path) = event.file_path {
+            event.file_path = Some(relative_path(path, &self.root));
+        }
+        if let Some(ref glob) = event.path_glob {
+            event.path_glob = Some(self.path_string(glob));
+        }
+        event.description = self.description(&event.description);
+        event.todos.clear();
+    }
+
+    /// `description` with each absolute path in it (a word, possibly quoted)
+    /// scrubbed like a file path.
+    fn description(&self, description: &str) -> String {
+        description
+            .split(' ')
+            .map(|word| {
+                let quoted = word.len() > 1 && word.starts_with('"') && word.ends_with('"');
+                let inner = if quoted { &word[1..word.len() - 1] } else { word };
+                if !Path::new(inner).is_absolute() {
+                    return word.to_string();
+                }
+                let scrubbed = self.path_string(inner);
+                if quoted { format!("\"{scrubbed}\"") } else { scrubbed }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn report(&self, report: &mut CoverageReport) {
+        report.session_id = report.session_id.as_deref().map(session_id);
+        for file in &mut report.files {
+            file.path = self.path_string(&file.path);
+        }
+        for gap in &mut report.symbols {
+            gap.path = self.path_string(&gap.path);
+        }
+    }
+
+    fn path_string(&self, path: &str) -> String {
+        relative_path(Path::new(path), &self.root).to_string_lossy().to_string()
+    }
+}
+
+/// Formats an anonymized copy of the report with another formatter.
+pub struct AnonymizedFormatter {
+    pub inner: Box<dyn CoverageFormatter>,
+    pub project_root: PathBuf,
+}
+
+impl CoverageFormatter for AnonymizedFormatter {
+    fn format(&self, report: &CoverageReport) -> String {
+        let mut report = report.clone();
+        Anonymizer::new(&self.project_root).report(&mut report);
+        self.inner.format(&report)
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use super::*;
+    use crate::coverage::TextFormatter;
+    use crate::tracking::ReadDepth;
+
+    #[test]
+    fn agents_are_numbered_and_paths_lose_the_root() {
+        let mut anonymizer = Anonymizer::new(Path::new("/test/project"));
+        let mut events = [
+            tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody),
+            tool_call("Read", "/home/me/notes.txt", ReadDepth::FullBody),
+            tool_call("Read", "src/b.rs", ReadDepth::FullBody),
+        ];
+        for (event, agent) in events.iter_mut().zip(["0d6c1e0a", "3f2a7b19", "0d6c1e0a"]) {
+            event.agent_id = agent.into();
+            anonymizer.event(event);
+        }
+
+        let agents: Vec<_> = events.iter().map(|e| e.agent_id.as_str()).collect();
+        assert_eq!(agents, ["agent-1", "agent-2", "agent-1"]);
+        let paths: Vec<_> = events.iter().map(|e| e.file_path.clone().unwrap()).collect();
+        assert_eq!(paths, [PathBuf::from("src/a.rs"), PathBuf::from("<external>/notes.txt"), PathBuf::from("src/b.rs")]);
+    }
+
+    #[test]
+    fn emitted_events_keep_no_absolute_paths() {
+        let mut anonymizer = Anonymizer::new(Path::new("/test/project"));
+        let mut overview = tool_call("get_symbols_overview", "/test/project/src/a.rs", ReadDepth::NameOnly);
+        overview.description = "Overview /test/project/src/a.rs".into();
+        let mut glob = tool_call("Glob", "/home/me/other", ReadDepth::NameOnly);
+        glob.description = "Glob /home/me/other/**/*.rs".into();
+        glob.path_glob = Some("/home/me/other/**/*.rs".into());
+        let mut search = tool_call("Grep", "/test/project/src", ReadDepth::Overview);
+        search.description = "Search \"/test/project/src/a.rs\"".into();
+        search.path_glob = Some("/test/project/src/**/*.rs".into());
+        let mut plan = tool_call("TodoWrite", "", ReadDepth::Unseen);
+        plan.file_path = None;
+        plan.todos = vec!["Fix /home/me/secret/plan.rs".into()];
+
+        for mut event in [overview, glob, search, plan] {
+            anonymizer.event(&mut event);
+            let json = serde_json::to_string(&event).unwrap();
+            assert!(!json.contains("/test/project") && !json.contains("/home/me"), "{json}");
+        }
+
+        assert_eq!(anonymizer.description("Overview /test/project/src/a.rs"), "Overview src/a.rs");
+        assert_eq!(anonymizer.description("Search \"/test/project/a.rs\""), "Search \"a.rs\"");
+        assert_eq!(anonymizer.description("Read src/a.rs"), "Read src/a.rs");
+    }
+
+    #[test]
+    fn reports_hash_the_session_id() {
+        let tree = project(vec![file("src/a.rs", vec![sym("src/a.rs::alpha", "alpha")])]);
+        let mut report = CoverageReport::from_project(&tree, &crate::tracking::ContextLedger::new());
+        report.session_id = Some("0d6c1e0a-5b7e-4c1f-9a51-0c7d2f3e8b11".into());
+
+        let formatter = AnonymizedFormatter {
+            inner: Box::new(TextFormatter::default()),
+            project_root: PathBuf::from("/test/project"),
+        };
+        let output = formatter.format(&report);
+        assert!(!output.contains("0d6c1e0a"), "{output}");
+        assert!(output.contains(&format!("(session: {})", session_id("0d6c1e0a-5b7e-4c1f-9a51-0c7d2f3e8b11"))));
+        assert!(output.contains("src/a.rs"));
+    }
+}
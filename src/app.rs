@@ -1,14 +1,26 @@
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Local, Utc};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use serde::{Deserialize, Serialize};
 
-use crate::coverage::count_symbols;
-use crate::symbols::{ProjectTree, SymbolNode};
+use crate::coverage::{count_symbols_for_agent, ranked_symbol_gaps, stale_report, unseen_tokens, CountMode};
+use crate::symbols::index::{FileIndex, IndexedSymbol, SymbolIndex};
+use crate::symbols::{visit_symbols, walk_symbols, FileSymbols, ProjectTree, SymbolCategory, SymbolNode, SymbolVisitor};
 use crate::tracking::ReadDepth;
 use crate::tracking::ContextLedger;
 use crate::ingest::AgentToolCall;
+use crate::keymap::{Action, KeyMap};
+use crate::replay::Replay;
+
+/// How often the event loop samples overall coverage for the stats sparkline.
+pub const COVERAGE_SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+/// Samples kept for the sparkline: two minutes at the sample interval.
+pub const COVERAGE_HISTORY_LEN: usize = 60;
 
 /// How files are sorted in the tree view.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +29,60 @@ pub enum SortMode {
     ByCoverage,
 }
 
+/// How the agent filter restricts the tree view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentFilterMode {
+    /// Show coverage earned by the selected agent only.
+    CoveredBy,
+    /// Show only symbols the selected agent has not seen yet.
+    NotCoveredBy,
+}
+
+/// How the TUI arranges its panels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutMode {
+    /// Stacked on narrow terminals, split otherwise.
+    #[default]
+    Auto,
+    /// Tree and stats side by side, activity below.
+    Split,
+    /// Full-width tree with one panel (stats or activity) below it.
+    Stacked,
+}
+
+impl std::str::FromStr for LayoutMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "split" => Ok(Self::Split),
+            "stacked" => Ok(Self::Stacked),
+            _ => Err(format!("expected auto, split or stacked, got `{s}`")),
+        }
+    }
+}
+
+/// Where the project's symbols come from, shown in the TUI header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SymbolSource {
+    #[default]
+    TreeSitter,
+    Serena,
+    /// Serena, with tree-sitter for files missing from its cache.
+    SerenaWithFallback,
+}
+
+impl SymbolSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::TreeSitter => "tree-sitter",
+            Self::Serena => "serena",
+            Self::SerenaWithFallback => "serena+tree-sitter",
+        }
+    }
+}
+
 /// Four-state coverage classification for files.
 /// Variant order gives the desired sort: Partially → AllSeen → Fully → Not Covered.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -39,10 +105,58 @@ pub struct TreeRow {
     pub has_children: bool,
     pub line_range: String,
     pub token_count: usize,
+    pub line_count: usize,
     pub read_depth: ReadDepth,
     pub coverage_status: Option<FileCoverageStatus>,
     pub file_coverage_seen: usize,
     pub file_coverage_total: usize,
+    pub is_pinned: bool,
+    pub has_note: bool,
+    /// File rows: the file changed on disk after the agent last read it.
+    pub modified_since_read: bool,
+    /// An agent's todo list names this file or symbol.
+    pub is_planned: bool,
+    /// The symbol counts as seen only because of an edit or a search.
+    pub is_inferred: bool,
+}
+
+impl TreeRow {
+    /// The row's size annotation: `~N tok` for symbols and `N lines` for
+    /// files, the other way around with `show_lines`.
+    pub fn size_label(&self, show_lines: bool) -> String {
+        if show_lines != self.is_file {
+            format!("{} lines", self.line_count)
+        } else {
+            format!("~{} tok", self.token_count)
+        }
+    }
+}
+
+/// What a watched session has gathered, parked while another one is shown.
+#[derive(Debug, Clone, Default)]
+pub struct SessionState {
+    pub session_id: String,
+    pub ledger: ContextLedger,
+    pub agents_seen: Vec<String>,
+    pub activity: Vec<AgentToolCall>,
+    pub session_start: Option<DateTime<Utc>>,
+    pub coverage_history: VecDeque<u32>,
+    pub warnings: Vec<String>,
+    pub orphan_reads: std::collections::BTreeMap<PathBuf, usize>,
+}
+
+/// Symbols an edited file still has that no agent has read.
+#[derive(Debug, Clone)]
+pub struct UnseenAlert {
+    pub file_path: PathBuf,
+    pub symbols: Vec<UnseenSymbol>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnseenSymbol {
+    pub label: String,
+    pub name: String,
+    pub line_range: std::ops::Range<usize>,
 }
 
 /// Which panel is focused.
@@ -58,11 +172,18 @@ pub struct App {
     pub project_root: PathBuf,
     pub ledger: ContextLedger,
     pub should_quit: bool,
+    // Ask for confirmation before `q` quits; `quit_pending` while asking.
+    pub confirm_quit: bool,
+    pub quit_pending: bool,
 
     // Tree view state.
     pub tree_rows: Vec<TreeRow>,
     pub selected_index: usize,
     pub collapsed: std::collections::HashSet<String>,
+    // Files with more symbols than this are only flattened once expanded by
+    // hand (0 for no limit); `expanded_large` holds the ones that were.
+    pub large_file_symbols: usize,
+    pub expanded_large: std::collections::HashSet<String>,
 
     // Activity feed.
     pub activity: Vec<AgentToolCall>,
@@ -72,22 +193,96 @@ pub struct App {
 
     // Agent filter: if Some, only show coverage from this agent.
     pub agent_filter: Option<String>,
+    pub agent_filter_mode: AgentFilterMode,
+
+    // Agent picker popup cursor while open: 0 is "all agents", i is agents_seen[i - 1].
+    pub agent_picker: Option<usize>,
 
     // Focus.
     pub focus: FocusPanel,
 
+    // Panel arrangement.
+    pub layout: LayoutMode,
+
     // Sort mode for tree view.
     pub sort_mode: SortMode,
 
+    // Merge chains of single-child containers into one row (`a::b::c`).
+    pub compact_chains: bool,
+
+    // Annotate rows with line counts instead of estimated tokens.
+    pub show_lines: bool,
+
+    // Show the activity feed panel; hidden, its room goes to the tree and stats.
+    pub show_activity: bool,
+
+    // List every symbol in one flat list ranked by coverage gap, ignoring files.
+    pub flat_view: bool,
+
+    // Cluster a file's impl blocks for the same type under one row.
+    pub group_impls: bool,
+
+    // Which symbols count toward coverage numbers.
+    pub count_mode: CountMode,
+
+    // Per-file coverage counts, reused until the ledger changes.
+    coverage_cache: CoverageCache,
+
+    // Lookup tables for marking targeted tool calls; rebuilt by `tree_changed`.
+    symbol_index: SymbolIndex,
+
+    // Files pinned to the top of the tree.
+    pub pinned: std::collections::HashSet<String>,
+
+    // Transient message shown in the status bar until it expires.
+    pub flash: Option<(String, Instant)>,
+
+    // Detail popup for the selected symbol.
+    pub show_detail: bool,
+
+    // Alert raised when an edited file still has unseen symbols.
+    pub unseen_alert: Option<UnseenAlert>,
+
+    // Path-matching problems hit while applying events (fuzzy or ambiguous matches).
+    pub warnings: Vec<String>,
+    // Paths agents touched that match no file in the tree, with event counts.
+    pub orphan_reads: std::collections::BTreeMap<PathBuf, usize>,
+    pub show_warnings: bool,
+
     // Search.
     pub search_mode: bool,
     pub search_query: String,
 
+    // Review note being typed: (symbol id, text so far).
+    pub note_input: Option<(String, String)>,
+
+    // Overall seen percentage, oldest first, sampled by `sample_coverage`.
+    pub coverage_history: VecDeque<u32>,
+    pub last_coverage_sample: Option<Instant>,
+
+    // Session events still to be played back with `--replay`.
+    pub replay: Option<Replay>,
+
+    // Every watched session in picker order; empty when only one is watched.
+    pub sessions: Vec<String>,
+    // The watched sessions not on screen, by id.
+    pub parked_sessions: std::collections::HashMap<String, SessionState>,
+    // Session picker popup cursor while open, an index into `sessions`.
+    pub session_picker: Option<usize>,
+
     // Session info for display.
     pub session_id: Option<String>,
+    pub symbol_source: SymbolSource,
+    // Earliest event timestamp seen, used for the session age.
+    pub session_start: Option<DateTime<Utc>>,
+    // Wall clock for the status bar; set by the event loop, None hides the clock.
+    pub now: Option<DateTime<Local>>,
 
     // Optional event log writer.
     pub event_log: Option<BufWriter<File>>,
+
+    // Key bindings for normal mode.
+    pub keymap: KeyMap,
 }
 
 impl App {
@@ -98,32 +293,93 @@ impl App {
             .iter()
             .map(|f| f.file_path.to_string_lossy().to_string())
             .collect();
+        let symbol_index = SymbolIndex::new(&project_tree);
 
         let mut app = Self {
             project_tree,
             project_root,
             ledger: ContextLedger::new(),
             should_quit: false,
+            confirm_quit: true,
+            quit_pending: false,
             tree_rows: Vec::new(),
             selected_index: 0,
             collapsed,
+            large_file_symbols: 0,
+            expanded_large: std::collections::HashSet::new(),
             activity: Vec::new(),
             agents_seen: Vec::new(),
             agent_filter: None,
+            agent_filter_mode: AgentFilterMode::CoveredBy,
+            agent_picker: None,
             focus: FocusPanel::Tree,
+            layout: LayoutMode::Auto,
             sort_mode: SortMode::Alphabetical,
+            compact_chains: false,
+            show_lines: false,
+            show_activity: true,
+            flat_view: false,
+            group_impls: false,
+            count_mode: CountMode::ALL,
+            coverage_cache: CoverageCache::default(),
+            symbol_index,
+            pinned: std::collections::HashSet::new(),
+            flash: None,
+            show_detail: false,
+            unseen_alert: None,
+            warnings: Vec::new(),
+            orphan_reads: std::collections::BTreeMap::new(),
+            show_warnings: false,
             search_mode: false,
             search_query: String::new(),
+            note_input: None,
+            coverage_history: VecDeque::with_capacity(COVERAGE_HISTORY_LEN),
+            last_coverage_sample: None,
+            replay: None,
+            sessions: Vec::new(),
+            parked_sessions: std::collections::HashMap::new(),
+            session_picker: None,
             session_id: None,
+            symbol_source: SymbolSource::default(),
+            session_start: None,
+            now: None,
             event_log,
+            keymap: KeyMap::default(),
         };
         app.rebuild_tree_rows();
         app
     }
 
+    /// Rebuild what's derived from `project_tree`: the symbol index and cached
+    /// per-file coverage. Call after replacing files in it; ledger changes
+    /// invalidate the coverage cache on their own.
+    pub fn tree_changed(&mut self) {
+        self.coverage_cache = CoverageCache::default();
+        self.symbol_index = SymbolIndex::new(&self.project_tree);
+    }
+
     /// Rebuild the flattened tree rows from the project tree + collapsed state.
+    /// The cursor stays on the same row id when it's still shown, and is
+    /// clamped to the new rows otherwise.
     pub fn rebuild_tree_rows(&mut self) {
+        let selected = self.tree_rows.get(self.selected_index).map(|r| r.symbol_id.clone());
+        self.tree_rows = self.build_tree_rows();
+        match selected.and_then(|id| self.tree_rows.iter().position(|r| r.symbol_id == id)) {
+            Some(idx) => self.selected_index = idx,
+            None => self.selected_index = self.selected_index.min(self.tree_rows.len().saturating_sub(1)),
+        }
+    }
+
+    fn build_tree_rows(&mut self) -> Vec<TreeRow> {
         let mut rows = Vec::new();
+        let view = AgentView {
+            agent: self.agent_filter.as_deref(),
+            mode: self.agent_filter_mode,
+        };
+
+        if self.flat_view {
+            return self.flat_rows(view);
+        }
 
         // Build iteration order: sorted by coverage status if ByCoverage mode is active.
         let file_indices: Vec<usize> = if self.sort_mode == SortMode::ByCoverage {
@@ -133,28 +389,39 @@ impl App {
                 .iter()
                 .enumerate()
                 .map(|(i, f)| {
-                    let (total, seen, full) = count_symbols(&f.symbols, &self.ledger);
+                    let counts = self.coverage_cache.counts(f, &self.ledger, view.agent, self.count_mode);
                     (
-                        coverage_status_from_counts(total, seen, full),
+                        file_coverage_status(f, counts, &self.ledger),
                         f.file_path.as_path(),
                         i,
                     )
                 })
                 .collect();
-            indices.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+            indices.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
             indices.into_iter().map(|(_, _, i)| i).collect()
         } else {
             (0..self.project_tree.files.len()).collect()
         };
+        // Pinned files go first; the sort above is kept within each group.
+        let (mut file_indices, rest): (Vec<usize>, Vec<usize>) = file_indices
+            .into_iter()
+            .partition(|&i| self.pinned.contains(&*self.project_tree.files[i].file_path.to_string_lossy()));
+        file_indices.extend(rest);
 
         for &idx in &file_indices {
             let file = &self.project_tree.files[idx];
+            if view.hides_covered() && !file.symbols.iter().any(|s| view.includes(s, &self.ledger)) {
+                continue;
+            }
             let file_path = file.file_path.to_string_lossy().to_string();
             let file_id = file_path.clone();
-            let is_expanded = !self.collapsed.contains(&file_id);
+            let symbol_count = if self.large_file_symbols > 0 { file.total_symbols() } else { 0 };
+            let truncated = symbol_count > self.large_file_symbols && !self.expanded_large.contains(&file_id);
+            let is_expanded = !truncated && !self.collapsed.contains(&file_id);
+            let is_pinned = self.pinned.contains(&file_id);
 
-            let (total, seen, full) = count_symbols(&file.symbols, &self.ledger);
-            let status = coverage_status_from_counts(total, seen, full);
+            let (total, seen, full) = self.coverage_cache.counts(file, &self.ledger, view.agent, self.count_mode);
+            let status = file_coverage_status(file, (total, seen, full), &self.ledger);
             let file_read_depth = if status != FileCoverageStatus::NotCovered {
                 ReadDepth::NameOnly // Use NameOnly to indicate "has coverage"
             } else {
@@ -169,22 +436,73 @@ impl App {
                 is_file: true,
                 is_expanded,
                 has_children: !file.symbols.is_empty(),
-                line_range: format!("{} lines", file.total_lines),
-                token_count: 0,
+                line_range: if truncated {
+                    format!("{symbol_count} symbols (truncated)")
+                } else {
+                    String::new()
+                },
+                token_count: file.symbols.iter().map(|s| s.total_tokens()).sum(),
+                line_count: file.total_lines,
                 read_depth: file_read_depth,
                 coverage_status: Some(status),
                 file_coverage_seen: seen,
                 file_coverage_total: total,
+                is_pinned,
+                has_note: false,
+                modified_since_read: file
+                    .modified
+                    .is_some_and(|mtime| self.ledger.modified_since_read(&file.file_path, mtime)),
+                is_planned: self.ledger.is_planned(&file_id),
+                is_inferred: false,
             });
 
             if is_expanded {
-                for sym in &file.symbols {
-                    flatten_symbol(sym, 1, &self.collapsed, &self.ledger, &mut rows);
+                let ctx = FlattenCtx {
+                    collapsed: &self.collapsed,
+                    ledger: &self.ledger,
+                    view,
+                    compact_chains: self.compact_chains,
+                };
+                if self.group_impls {
+                    flatten_with_impl_groups(&file_id, &file.symbols, &ctx, self.count_mode, &mut rows);
+                } else {
+                    for sym in &file.symbols {
+                        flatten_symbol(sym, 1, &ctx, &mut rows);
+                    }
                 }
             }
         }
 
-        self.tree_rows = rows;
+        rows
+    }
+
+    /// One row per symbol, biggest coverage gap first, with the file in the line column.
+    fn flat_rows(&self, view: AgentView) -> Vec<TreeRow> {
+        ranked_symbol_gaps(&self.project_tree, &self.ledger, view.agent)
+            .into_iter()
+            .filter(|gap| !(view.hides_covered() && gap.depth.is_seen()))
+            .map(|gap| TreeRow {
+                is_planned: self.ledger.is_planned(&gap.symbol_id),
+                is_inferred: self.ledger.is_inferred(&gap.symbol_id),
+                symbol_id: gap.symbol_id,
+                display_name: gap.name,
+                label: gap.label,
+                depth: 0,
+                is_file: false,
+                is_expanded: false,
+                has_children: false,
+                line_range: format!("{}:{}-{}", gap.path, gap.line_range.start, gap.line_range.end),
+                token_count: gap.tokens,
+                line_count: gap.line_range.end - gap.line_range.start + 1,
+                read_depth: gap.depth,
+                coverage_status: None,
+                file_coverage_seen: 0,
+                file_coverage_total: 0,
+                is_pinned: false,
+                has_note: gap.note.is_some(),
+                modified_since_read: false,
+            })
+            .collect()
     }
 
     pub fn handle_key(&mut self, key: KeyEvent) {
@@ -192,34 +510,120 @@ impl App {
             self.handle_search_key(key);
             return;
         }
+        if self.note_input.is_some() {
+            self.handle_note_key(key);
+            return;
+        }
+        if self.agent_picker.is_some() {
+            self.handle_agent_picker_key(key);
+            return;
+        }
+        if self.session_picker.is_some() {
+            self.handle_session_picker_key(key);
+            return;
+        }
 
-        match key.code {
-            KeyCode::Char('q') => self.should_quit = true,
-            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.should_quit = true;
-            }
-            KeyCode::Char('j') | KeyCode::Down => self.move_selection(1),
-            KeyCode::Char('k') | KeyCode::Up => self.move_selection(-1),
-            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => self.toggle_expand(),
-            KeyCode::Char('h') | KeyCode::Left => self.collapse_current(),
-            KeyCode::Char('G') => self.select_last(),
-            KeyCode::Char('g') => self.select_first(),
-            KeyCode::Char('/') => {
+        // Ctrl-C always quits, whatever the key map says.
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.should_quit = true;
+            return;
+        }
+        if self.quit_pending {
+            self.quit_pending = false;
+            self.should_quit = matches!(key.code, KeyCode::Char('y' | 'Y' | 'q') | KeyCode::Enter);
+            return;
+        }
+
+        if key.code == KeyCode::Esc {
+            if self.show_detail {
+                self.show_detail = false;
+                return;
+            }
+            if self.show_warnings {
+                self.show_warnings = false;
+                return;
+            }
+            if self.unseen_alert.take().is_some() {
+                return;
+            }
+        }
+
+        let Some(action) = self.keymap.action_for(&key) else {
+            return;
+        };
+        match action {
+            Action::Quit if self.confirm_quit => self.quit_pending = true,
+            Action::Quit | Action::ForceQuit => self.should_quit = true,
+            Action::Down => self.move_selection(1),
+            Action::Up => self.move_selection(-1),
+            Action::Expand => self.toggle_expand(),
+            Action::Collapse => self.collapse_current(),
+            Action::Bottom => self.select_last(),
+            Action::Top => self.select_first(),
+            Action::Search => {
                 self.search_mode = true;
                 self.search_query.clear();
             }
-            KeyCode::Char('s') => {
+            Action::Sort => {
                 self.sort_mode = match self.sort_mode {
                     SortMode::Alphabetical => SortMode::ByCoverage,
                     SortMode::ByCoverage => SortMode::Alphabetical,
                 };
                 self.rebuild_tree_rows();
             }
-            KeyCode::Char('a') => self.cycle_agent_filter(),
-            KeyCode::Tab => self.cycle_focus(),
-            KeyCode::PageDown => self.move_selection(20),
-            KeyCode::PageUp => self.move_selection(-20),
-            _ => {}
+            Action::CycleAgent => self.cycle_agent_filter(),
+            Action::AgentPicker => {
+                let current = self
+                    .agent_filter
+                    .as_ref()
+                    .and_then(|id| self.agents_seen.iter().position(|a| a == id))
+                    .map_or(0, |i| i + 1);
+                self.agent_picker = Some(current);
+            }
+            Action::SessionPicker => {
+                if self.sessions.len() < 2 {
+                    self.flash_message("Only one session is watched; pass --sessions to watch more".into());
+                } else {
+                    let current = self.sessions.iter().position(|id| Some(id) == self.session_id.as_ref());
+                    self.session_picker = Some(current.unwrap_or(0));
+                }
+            }
+            Action::ToggleAgentMode => self.toggle_agent_filter_mode(),
+            Action::Focus => self.cycle_focus(),
+            Action::PageDown => self.move_selection(20),
+            Action::PageUp => self.move_selection(-20),
+            Action::HalfPageDown => self.move_selection(10),
+            Action::HalfPageUp => self.move_selection(-10),
+            Action::Details => self.show_detail = !self.show_detail,
+            Action::Warnings => self.show_warnings = !self.show_warnings,
+            Action::CompactChains => {
+                self.compact_chains = !self.compact_chains;
+                self.rebuild_tree_rows();
+            }
+            Action::ToggleSizeUnit => self.show_lines = !self.show_lines,
+            Action::ToggleActivity => {
+                self.show_activity = !self.show_activity;
+                if !self.show_activity && self.focus == FocusPanel::Activity {
+                    self.focus = FocusPanel::Tree;
+                }
+            }
+            Action::Pin => self.toggle_pin(),
+            Action::GroupImpls => {
+                self.group_impls = !self.group_impls;
+                self.rebuild_tree_rows();
+            }
+            Action::Note => self.start_note(),
+            Action::SameName => self.jump_to_same_name(),
+            Action::ExportView => self.export_view(),
+            Action::StaleReport => self.export_stale_report(),
+            Action::FlatView => {
+                self.flat_view = !self.flat_view;
+                self.rebuild_tree_rows();
+            }
+            // Replay controls do nothing without a replay loaded.
+            Action::ReplayPause => self.replay.iter_mut().for_each(Replay::toggle_pause),
+            Action::ReplayFaster => self.replay.iter_mut().for_each(Replay::faster),
+            Action::ReplaySlower => self.replay.iter_mut().for_each(Replay::slower),
         }
     }
 
@@ -251,6 +655,322 @@ impl App {
         }
     }
 
+    fn handle_agent_picker_key(&mut self, key: KeyEvent) {
+        let Some(cursor) = self.agent_picker else {
+            return;
+        };
+        let last = self.agents_seen.len();
+        match key.code {
+            KeyCode::Esc => self.agent_picker = None,
+            KeyCode::Enter => {
+                self.agent_picker = None;
+                self.agent_filter = cursor.checked_sub(1).and_then(|i| self.agents_seen.get(i)).cloned();
+                self.rebuild_tree_rows();
+            }
+            _ => match self.keymap.action_for(&key) {
+                Some(Action::Down) => self.agent_picker = Some((cursor + 1).min(last)),
+                Some(Action::Up) => self.agent_picker = Some(cursor.saturating_sub(1)),
+                Some(Action::Top) => self.agent_picker = Some(0),
+                Some(Action::Bottom) => self.agent_picker = Some(last),
+                Some(Action::AgentPicker | Action::Quit) => self.agent_picker = None,
+                _ => {}
+            },
+        }
+    }
+
+    fn handle_session_picker_key(&mut self, key: KeyEvent) {
+        let Some(cursor) = self.session_picker else {
+            return;
+        };
+        let last = self.sessions.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc => self.session_picker = None,
+            KeyCode::Enter => {
+                self.session_picker = None;
+                if let Some(id) = self.sessions.get(cursor).cloned() {
+                    self.switch_session(&id);
+                }
+            }
+            _ => match self.keymap.action_for(&key) {
+                Some(Action::Down) => self.session_picker = Some((cursor + 1).min(last)),
+                Some(Action::Up) => self.session_picker = Some(cursor.saturating_sub(1)),
+                Some(Action::Top) => self.session_picker = Some(0),
+                Some(Action::Bottom) => self.session_picker = Some(last),
+                Some(Action::SessionPicker | Action::Quit) => self.session_picker = None,
+                _ => {}
+            },
+        }
+    }
+
+    /// Watch `ids` as well as the session on screen, each with its own
+    /// ledger and activity. Ids other than the current one start parked.
+    pub fn watch_sessions(&mut self, ids: Vec<String>) {
+        for id in &ids {
+            if Some(id) != self.session_id.as_ref() && !self.parked_sessions.contains_key(id) {
+                let state = SessionState { session_id: id.clone(), ..SessionState::default() };
+                self.parked_sessions.insert(id.clone(), state);
+            }
+        }
+        self.sessions = ids;
+    }
+
+    /// The ledger of a watched session, whether it's on screen or parked.
+    pub fn session_ledger(&self, session_id: &str) -> Option<&ContextLedger> {
+        if self.session_id.as_deref() == Some(session_id) {
+            Some(&self.ledger)
+        } else {
+            self.parked_sessions.get(session_id).map(|s| &s.ledger)
+        }
+    }
+
+    /// The tree, and every session's ledger, shown first and then the parked
+    /// ones, so a change to the tree can reach sessions that aren't on screen.
+    pub fn ledgers_mut(&mut self) -> (&ProjectTree, impl Iterator<Item = &mut ContextLedger>) {
+        let parked = self.parked_sessions.values_mut().map(|s| &mut s.ledger);
+        (&self.project_tree, std::iter::once(&mut self.ledger).chain(parked))
+    }
+
+    /// Apply an event from `session_id`: to the screen if that session is
+    /// shown (or not watched separately), otherwise to its parked state.
+    pub fn process_session_event(&mut self, session_id: &str, event: AgentToolCall) {
+        let Some(mut state) = self.parked_sessions.remove(session_id) else {
+            self.process_agent_event(event);
+            return;
+        };
+        self.swap_session(&mut state);
+        self.record_agent_event(event, false);
+        self.swap_session(&mut state);
+        self.parked_sessions.insert(session_id.to_string(), state);
+    }
+
+    /// Put a parked session on screen and park the one that was shown. The
+    /// agent filter is cleared, since agent ids differ between sessions.
+    pub fn switch_session(&mut self, session_id: &str) {
+        let Some(mut state) = self.parked_sessions.remove(session_id) else {
+            return;
+        };
+        self.swap_session(&mut state);
+        self.parked_sessions.insert(state.session_id.clone(), state);
+        self.agent_filter = None;
+        self.coverage_cache = CoverageCache::default();
+        self.rebuild_tree_rows();
+        self.flash_message(format!("Showing session {session_id}"));
+    }
+
+    /// Trade what's on screen for `state`. Notes belong to the project, so
+    /// they stay put.
+    fn swap_session(&mut self, state: &mut SessionState) {
+        std::mem::swap(&mut self.ledger, &mut state.ledger);
+        std::mem::swap(&mut self.ledger.notes, &mut state.ledger.notes);
+        std::mem::swap(&mut self.agents_seen, &mut state.agents_seen);
+        std::mem::swap(&mut self.activity, &mut state.activity);
+        std::mem::swap(&mut self.session_start, &mut state.session_start);
+        std::mem::swap(&mut self.coverage_history, &mut state.coverage_history);
+        std::mem::swap(&mut self.warnings, &mut state.warnings);
+        std::mem::swap(&mut self.orphan_reads, &mut state.orphan_reads);
+        let shown = self.session_id.take().unwrap_or_default();
+        self.session_id = Some(std::mem::replace(&mut state.session_id, shown));
+    }
+
+    /// Open the note input for the selected symbol, prefilled with its current note.
+    fn start_note(&mut self) {
+        let Some(row) = self.tree_rows.get(self.selected_index) else {
+            return;
+        };
+        if self.selected_symbol().is_none() {
+            self.flash_message("Notes attach to symbols; select one inside the file".into());
+            return;
+        }
+        let text = self.ledger.notes.get(&row.symbol_id).unwrap_or_default().to_string();
+        self.note_input = Some((row.symbol_id.clone(), text));
+    }
+
+    fn handle_note_key(&mut self, key: KeyEvent) {
+        let Some((symbol_id, mut text)) = self.note_input.take() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                self.ledger.notes.set(&symbol_id, &text);
+                if let Err(e) = self.ledger.notes.save(&self.project_root) {
+                    self.flash_message(format!("Failed to save notes: {e}"));
+                }
+                self.rebuild_tree_rows();
+                return;
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        self.note_input = Some((symbol_id, text));
+    }
+
+    /// Time since the earliest event in the session, if both ends are known.
+    pub fn session_age(&self) -> Option<chrono::Duration> {
+        let start = self.session_start?;
+        let now = self.now?;
+        Some(now.with_timezone(&Utc) - start)
+    }
+
+    /// After a source file changes, raise an alert listing its symbols that are
+    /// still unseen, or clear the alert if the agent has seen them all.
+    pub fn note_file_changed(&mut self, rel_path: &Path) {
+        let Some(file) = self.project_tree.files.iter().find(|f| f.file_path == rel_path) else {
+            return;
+        };
+        let mut symbols = Vec::new();
+        visit_symbols(&file.symbols, |sym| {
+            if self.ledger.depth_of(&sym.id) == ReadDepth::Unseen {
+                symbols.push(UnseenSymbol {
+                    label: sym.label.clone(),
+                    name: sym.name.clone(),
+                    line_range: sym.line_range.clone(),
+                });
+            }
+        });
+
+        self.unseen_alert = if symbols.is_empty() {
+            None
+        } else {
+            Some(UnseenAlert {
+                file_path: rel_path.to_path_buf(),
+                symbols,
+            })
+        };
+    }
+
+    /// Pin or unpin the file of the selected row.
+    fn toggle_pin(&mut self) {
+        let end = (self.selected_index + 1).min(self.tree_rows.len());
+        let file_id = self.tree_rows[..end]
+            .iter()
+            .rev()
+            .find(|r| r.is_file)
+            .map(|r| r.symbol_id.clone())
+            .or_else(|| self.selected_symbol().map(|s| s.file_path.to_string_lossy().to_string()));
+        let Some(file_id) = file_id else {
+            return;
+        };
+        if !self.pinned.remove(&file_id) {
+            self.pinned.insert(file_id.clone());
+        }
+        self.rebuild_tree_rows();
+        // Keep the cursor on the file that just moved.
+        if let Some(idx) = self.tree_rows.iter().position(|r| r.is_file && r.symbol_id == file_id) {
+            self.selected_index = idx;
+        }
+    }
+
+    fn pinned_statuses(&self) -> Vec<(String, FileCoverageStatus)> {
+        self.project_tree
+            .files
+            .iter()
+            .filter(|f| self.pinned.contains(&*f.file_path.to_string_lossy()))
+            .map(|f| {
+                let counts = count_symbols_for_agent(&f.symbols, &self.ledger, None, self.count_mode);
+                (f.file_path.to_string_lossy().to_string(), file_coverage_status(f, counts, &self.ledger))
+            })
+            .collect()
+    }
+
+    /// One line summarizing coverage, printed after the TUI exits, e.g.
+    /// `Session 34e212cf: 42% seen, 18% full across 312 symbols`.
+    pub fn exit_summary(&self) -> String {
+        let (total, seen, full) = self.coverage_totals();
+        let pct = |n: usize| (n * 100).checked_div(total).unwrap_or(0);
+        let label = match &self.session_id {
+            Some(id) => format!("Session {}", id.chars().take(8).collect::<String>()),
+            None => "Coverage".to_string(),
+        };
+        format!("{label}: {}% seen, {}% full across {total} symbols", pct(seen), pct(full))
+    }
+
+    /// (total, seen, full) symbol counts across the project, for all agents.
+    fn coverage_totals(&self) -> (usize, usize, usize) {
+        self.project_tree.files.iter().fold((0, 0, 0), |(t, s, f), file| {
+            let (ft, fs, ff) = count_symbols_for_agent(&file.symbols, &self.ledger, None, self.count_mode);
+            (t + ft, s + fs, f + ff)
+        })
+    }
+
+    /// Record the overall seen percentage if `COVERAGE_SAMPLE_INTERVAL` has
+    /// passed since the last sample, dropping the oldest beyond `COVERAGE_HISTORY_LEN`.
+    pub fn sample_coverage(&mut self, now: Instant) {
+        if self
+            .last_coverage_sample
+            .is_some_and(|last| now.duration_since(last) < COVERAGE_SAMPLE_INTERVAL)
+        {
+            return;
+        }
+        self.last_coverage_sample = Some(now);
+        if self.coverage_history.len() == COVERAGE_HISTORY_LEN {
+            self.coverage_history.pop_front();
+        }
+        self.coverage_history.push_back(self.seen_percent());
+    }
+
+    /// Percentage of symbols seen at any depth, across the project and all agents.
+    pub fn seen_percent(&self) -> u32 {
+        let (total, seen, _) = self.coverage_totals();
+        (seen * 100).checked_div(total).unwrap_or(0) as u32
+    }
+
+    /// Apply the replayed events that have come due by `now`.
+    pub fn advance_replay(&mut self, now: Instant) {
+        let due = match self.replay.as_mut() {
+            Some(replay) => replay.advance(now),
+            None => return,
+        };
+        for event in due {
+            self.process_agent_event(event);
+        }
+    }
+
+    /// Estimated tokens of code no agent has seen yet, across the project.
+    pub fn unseen_tokens(&self) -> usize {
+        self.project_tree.files.iter().map(|file| unseen_tokens(&file.symbols, &self.ledger)).sum()
+    }
+
+    /// Show `text` in the status bar for a few seconds.
+    pub fn flash_message(&mut self, text: String) {
+        self.flash = Some((text, Instant::now() + Duration::from_secs(5)));
+    }
+
+    /// Add warnings raised outside event processing (e.g. by a Serena cache
+    /// load) to the warnings list, and flash how many there were.
+    pub fn add_warnings(&mut self, warnings: Vec<String>) {
+        if warnings.is_empty() {
+            return;
+        }
+        let count = warnings.len();
+        self.warnings.extend(warnings);
+        let plural = if count == 1 { "" } else { "s" };
+        self.flash_message(format!("{count} warning{plural}, press W to list them"));
+    }
+
+    /// The status-bar flash message, if it hasn't expired.
+    pub fn active_flash(&self) -> Option<&str> {
+        self.flash
+            .as_ref()
+            .filter(|(_, until)| Instant::now() < *until)
+            .map(|(text, _)| text.as_str())
+    }
+
+    /// The symbol under the cursor, or None for file rows and an empty tree.
+    pub fn selected_symbol(&self) -> Option<&SymbolNode> {
+        let row = self.tree_rows.get(self.selected_index)?;
+        if row.is_file {
+            return None;
+        }
+        self.project_tree
+            .files
+            .iter()
+            .find_map(|f| find_symbol(&f.symbols, &row.symbol_id))
+    }
+
     fn move_selection(&mut self, delta: i32) {
         if self.tree_rows.is_empty() {
             return;
@@ -273,10 +993,16 @@ impl App {
         if let Some(row) = self.tree_rows.get(self.selected_index) {
             if row.has_children {
                 let id = row.symbol_id.clone();
-                if self.collapsed.contains(&id) {
-                    self.collapsed.remove(&id);
-                } else {
+                // A large file's row can be folded while absent from `collapsed`.
+                let expanded = if row.is_file { row.is_expanded } else { !self.collapsed.contains(&id) };
+                if expanded {
+                    self.expanded_large.remove(&id);
                     self.collapsed.insert(id);
+                } else {
+                    if row.is_file {
+                        self.expanded_large.insert(id.clone());
+                    }
+                    self.collapsed.remove(&id);
                 }
                 self.rebuild_tree_rows();
             }
@@ -286,7 +1012,9 @@ impl App {
     fn collapse_current(&mut self) {
         if let Some(row) = self.tree_rows.get(self.selected_index) {
             let id = row.symbol_id.clone();
-            if row.has_children && !self.collapsed.contains(&id) {
+            let expanded = if row.is_file { row.is_expanded } else { !self.collapsed.contains(&id) };
+            if row.has_children && expanded {
+                self.expanded_large.remove(&id);
                 self.collapsed.insert(id);
                 self.rebuild_tree_rows();
             }
@@ -317,11 +1045,19 @@ impl App {
         self.rebuild_tree_rows();
     }
 
+    fn toggle_agent_filter_mode(&mut self) {
+        self.agent_filter_mode = match self.agent_filter_mode {
+            AgentFilterMode::CoveredBy => AgentFilterMode::NotCoveredBy,
+            AgentFilterMode::NotCoveredBy => AgentFilterMode::CoveredBy,
+        };
+        self.rebuild_tree_rows();
+    }
+
     fn cycle_focus(&mut self) {
         self.focus = match self.focus {
             FocusPanel::Tree => FocusPanel::Stats,
-            FocusPanel::Stats => FocusPanel::Activity,
-            FocusPanel::Activity => FocusPanel::Tree,
+            FocusPanel::Stats if self.show_activity => FocusPanel::Activity,
+            FocusPanel::Stats | FocusPanel::Activity => FocusPanel::Tree,
         };
     }
 
@@ -345,41 +1081,246 @@ impl App {
         }
     }
 
-    /// Process an agent tool call event and update the ledger.
-    pub fn process_agent_event(&mut self, event: AgentToolCall) {
-        // Track unique agents.
-        if !self.agents_seen.contains(&event.agent_id) {
-            self.agents_seen.push(event.agent_id.clone());
+    /// Move to the next symbol anywhere in the project with the selected symbol's
+    /// name, wrapping around. Matches by name only; there's no resolution.
+    fn jump_to_same_name(&mut self) {
+        let Some(sym) = self.selected_symbol() else {
+            return;
+        };
+        let (name, current) = (sym.name.clone(), sym.id.clone());
+        let mut matches = Vec::new();
+        for file in &self.project_tree.files {
+            visit_symbols(&file.symbols, |sym| {
+                if sym.name == name {
+                    matches.push(sym.id.clone());
+                }
+            });
+        }
+        if matches.len() < 2 {
+            self.flash_message(format!("No other symbols named {name}"));
+            return;
         }
+        let pos = matches.iter().position(|id| *id == current).unwrap_or(0);
+        let next = (pos + 1) % matches.len();
+        if self.reveal_symbol(&matches[next]) {
+            self.flash_message(format!("{name}: {}/{}", next + 1, matches.len()));
+        } else {
+            self.flash_message(format!("{name}: match {} is hidden by the current view", next + 1));
+        }
+    }
 
-        if let Some(ref file_path) = event.file_path {
-            // Normalize the tool call path: strip the project root to get a relative path.
-            let tool_rel = normalize_tool_path(file_path, &self.project_root);
+    /// Where a session's ledger is cached between runs:
+    /// `.ambits/ledger-<session>.json` under the project root.
+    pub fn ledger_cache_path(&self, session_id: &str) -> PathBuf {
+        ledger_cache_path(&self.project_root, session_id)
+    }
 
-            for file in &self.project_tree.files {
-                if file.file_path == tool_rel {
-                    if event.target_symbol.is_some() || event.target_lines.is_some() {
-                        mark_targeted_symbols(&file.symbols, &event, &mut self.ledger);
-                    } else {
-                        mark_file_symbols(&file.symbols, &event, &mut self.ledger);
-                    }
-                }
-            }
+    /// Fill the shown and parked sessions' ledgers from their caches, where
+    /// they have one, and say whether any did. Symbols whose content changed
+    /// since they were read come back Stale. Log events up to each ledger's
+    /// `applied_until` are already in it.
+    pub fn load_ledger_cache(&mut self) -> color_eyre::Result<bool> {
+        let mut loaded = false;
+        if let Some(session) = self.session_id.clone() {
+            loaded |= load_cached_ledger(&self.project_tree, &self.ledger_cache_path(&session), &mut self.ledger)?;
         }
-        // Write to event log if configured.
-        if let Some(ref mut writer) = self.event_log {
-            let path_str = event
-                .file_path
-                .as_ref()
-                .map(|p| p.display().to_string())
-                .unwrap_or_else(|| "-".to_string());
-            let target = if let Some(ref sym) = event.target_symbol {
-                sym.clone()
-            } else if let Some(ref lines) = event.target_lines {
-                format!("L{}-{}", lines.start, lines.end)
-            } else {
-                "-".to_string()
-            };
+        for (session, state) in &mut self.parked_sessions {
+            let path = ledger_cache_path(&self.project_root, session);
+            loaded |= load_cached_ledger(&self.project_tree, &path, &mut state.ledger)?;
+        }
+        if loaded {
+            self.rebuild_tree_rows();
+        }
+        Ok(loaded)
+    }
+
+    /// Write the shown and parked sessions' ledgers to their caches; does
+    /// nothing without a session.
+    pub fn save_ledger_cache(&self) -> color_eyre::Result<()> {
+        if let Some(ref session) = self.session_id {
+            self.ledger.save_to_path(&self.ledger_cache_path(session))?;
+        }
+        for (session, state) in &self.parked_sessions {
+            state.ledger.save_to_path(&self.ledger_cache_path(session))?;
+        }
+        Ok(())
+    }
+
+    /// Write the rows currently in the tree view to a timestamped file in the
+    /// project root, and say where in the status bar.
+    fn export_view(&mut self) {
+        let name = format!("ambits-view-{}.txt", Local::now().format("%Y%m%d-%H%M%S"));
+        match std::fs::write(self.project_root.join(&name), self.view_text()) {
+            Ok(()) => self.flash_message(format!("Wrote {name}")),
+            Err(e) => self.flash_message(format!("Failed to write {name}: {e}")),
+        }
+    }
+
+    /// Write the stale symbols to `ambits-stale-<timestamp>.txt` in the
+    /// project root, or just say so when there are none.
+    fn export_stale_report(&mut self) {
+        let Some(report) = stale_report(&self.project_tree, &self.ledger) else {
+            self.flash_message("No stale symbols".into());
+            return;
+        };
+        let name = format!("ambits-stale-{}.txt", Local::now().format("%Y%m%d-%H%M%S"));
+        match std::fs::write(self.project_root.join(&name), report) {
+            Ok(()) => self.flash_message(format!("Wrote {name}")),
+            Err(e) => self.flash_message(format!("Failed to write {name}: {e}")),
+        }
+    }
+
+    /// The tree view as text: a header naming the view settings, then one line
+    /// per visible row, indented by depth, with the same annotations as on screen.
+    pub fn view_text(&self) -> String {
+        let mut settings = vec![match self.sort_mode {
+            SortMode::Alphabetical => "sorted by name".to_string(),
+            SortMode::ByCoverage => "sorted by coverage".to_string(),
+        }];
+        if let Some(agent) = &self.agent_filter {
+            settings.push(match self.agent_filter_mode {
+                AgentFilterMode::CoveredBy => format!("covered by {agent}"),
+                AgentFilterMode::NotCoveredBy => format!("not covered by {agent}"),
+            });
+        }
+        if self.flat_view {
+            settings.push("flat".into());
+        }
+        if self.compact_chains {
+            settings.push("compact chains".into());
+        }
+        if self.group_impls {
+            settings.push("impls grouped".into());
+        }
+        if self.show_lines {
+            settings.push("line counts".into());
+        }
+
+        let mut out = format!("# {} ({})\n", self.project_root.display(), settings.join(", "));
+        if let Some(session) = &self.session_id {
+            out.push_str(&format!("# session {session}\n"));
+        }
+        for row in &self.tree_rows {
+            let indent = "  ".repeat(row.depth);
+            let counts = if row.file_coverage_total > 0 {
+                format!("  {}/{}", row.file_coverage_seen, row.file_coverage_total)
+            } else {
+                String::new()
+            };
+            let size = row.size_label(self.show_lines);
+            if row.is_file {
+                out.push_str(&format!("{indent}{}{counts}  ({})\n", row.display_name, file_annotation(row, &size)));
+            } else {
+                let depth = format!("[{}]", row.read_depth);
+                out.push_str(&format!(
+                    "{indent}{depth:<12}{} {}{counts}  [{}] {size}\n",
+                    row.label, row.display_name, row.line_range
+                ));
+            }
+        }
+        out
+    }
+
+    /// Expand the file and parents of `symbol_id` and put the cursor on its row.
+    /// Returns false when the current view has no row for it.
+    fn reveal_symbol(&mut self, symbol_id: &str) -> bool {
+        fn ancestors(symbols: &[SymbolNode], id: &str, path: &mut Vec<String>) -> bool {
+            for sym in symbols {
+                if sym.id == id {
+                    return true;
+                }
+                path.push(sym.id.clone());
+                if ancestors(&sym.children, id, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+
+        for file in &self.project_tree.files {
+            let mut path = Vec::new();
+            if ancestors(&file.symbols, symbol_id, &mut path) {
+                let file_id = file.file_path.to_string_lossy().to_string();
+                self.collapsed.remove(&file_id);
+                self.expanded_large.insert(file_id);
+                for id in path {
+                    self.collapsed.remove(&id);
+                }
+                break;
+            }
+        }
+        self.rebuild_tree_rows();
+        match self.tree_rows.iter().position(|r| !r.is_file && r.symbol_id == symbol_id) {
+            Some(idx) => {
+                self.selected_index = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Process an agent tool call event and update the ledger.
+    pub fn process_agent_event(&mut self, event: AgentToolCall) {
+        self.record_agent_event(event, true);
+        self.rebuild_tree_rows();
+    }
+
+    /// `process_agent_event` without rebuilding the tree rows. Events of a
+    /// session that isn't `shown` don't flash pinned files' coverage.
+    fn record_agent_event(&mut self, event: AgentToolCall, shown: bool) {
+        // Track unique agents.
+        if !self.agents_seen.contains(&event.agent_id) {
+            self.agents_seen.push(event.agent_id.clone());
+        }
+
+        if let Ok(ts) = DateTime::parse_from_rfc3339(&event.timestamp_str) {
+            let ts = ts.with_timezone(&Utc);
+            if self.session_start.is_none_or(|start| ts < start) {
+                self.session_start = Some(ts);
+            }
+            self.ledger.mark_applied(ts);
+        }
+
+        let pinned_before = if shown { self.pinned_statuses() } else { Vec::new() };
+        let applied = apply_event(&self.project_tree, &self.symbol_index, &self.project_root, &event, &mut self.ledger);
+        if let Some(path) = applied.orphan {
+            *self.orphan_reads.entry(path).or_default() += 1;
+        }
+        if let Some(warning) = applied.warning {
+            if let Some(ref mut writer) = self.event_log {
+                let _ = writeln!(writer, "[{}] warning: {}", event.timestamp_str, warning);
+            }
+            self.warnings.push(warning);
+            if self.warnings.len() > 200 {
+                self.warnings.drain(0..100);
+            }
+        }
+        if shown {
+            for ((path, before), (_, after)) in pinned_before.iter().zip(self.pinned_statuses()) {
+                if *before != FileCoverageStatus::FullyCovered && after == FileCoverageStatus::FullyCovered {
+                    self.flash_message(format!("Pinned file {path} is fully covered"));
+                }
+            }
+        }
+        // Write to event log if configured.
+        if let Some(ref mut writer) = self.event_log {
+            let path_str = event
+                .file_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let target = if let Some(ref sym) = event.target_symbol {
+                sym.clone()
+            } else if let Some(ref lines) = event.target_lines {
+                if lines.end == crate::ingest::END_OF_FILE {
+                    format!("L{}-end", lines.start)
+                } else {
+                    format!("L{}-{}", lines.start, lines.end)
+                }
+            } else {
+                "-".to_string()
+            };
             let _ = writeln!(
                 writer,
                 "[{}] agent={} tool={} depth={:?} path={} target={} desc=\"{}\"",
@@ -401,43 +1342,242 @@ impl App {
                 self.activity.drain(0..100);
             }
         }
-        self.rebuild_tree_rows();
     }
 }
 
-fn flatten_symbol(
-    sym: &SymbolNode,
-    depth: usize,
-    collapsed: &std::collections::HashSet<String>,
-    ledger: &ContextLedger,
+/// What a file row shows in parentheses: its size, then any note such as
+/// the truncation marker.
+pub fn file_annotation(row: &TreeRow, size: &str) -> String {
+    if row.line_range.is_empty() {
+        size.to_string()
+    } else {
+        format!("{size}, {}", row.line_range)
+    }
+}
+
+/// The agent filter resolved for a single tree rebuild.
+#[derive(Clone, Copy)]
+struct AgentView<'a> {
+    agent: Option<&'a str>,
+    mode: AgentFilterMode,
+}
+
+impl AgentView<'_> {
+    fn hides_covered(&self) -> bool {
+        self.agent.is_some() && self.mode == AgentFilterMode::NotCoveredBy
+    }
+
+    /// Depth to display: the agent's own depth when filtering by coverage,
+    /// otherwise the overall depth so other agents' reads stay visible.
+    fn depth(&self, ledger: &ContextLedger, symbol_id: &str) -> ReadDepth {
+        match (self.agent, self.mode) {
+            (Some(agent), AgentFilterMode::CoveredBy) => ledger.depth_for_agent(symbol_id, agent),
+            _ => ledger.depth_of(symbol_id),
+        }
+    }
+
+    /// Whether the symbol, or any of its descendants, passes the filter.
+    fn includes(&self, sym: &SymbolNode, ledger: &ContextLedger) -> bool {
+        match self.agent {
+            Some(agent) if self.hides_covered() => {
+                !ledger.depth_for_agent(&sym.id, agent).is_seen()
+                    || sym.children.iter().any(|c| self.includes(c, ledger))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// File path, agent filter and count mode a cached count was computed for.
+type CoverageKey = (PathBuf, Option<String>, CountMode);
+
+/// Per-file `(total, seen, full)` counts, valid for a single ledger version.
+#[derive(Debug, Default)]
+struct CoverageCache {
+    ledger_version: u64,
+    counts: std::collections::HashMap<CoverageKey, (usize, usize, usize)>,
+}
+
+impl CoverageCache {
+    fn counts(
+        &mut self,
+        file: &FileSymbols,
+        ledger: &ContextLedger,
+        agent: Option<&str>,
+        mode: CountMode,
+    ) -> (usize, usize, usize) {
+        if self.ledger_version != ledger.version() {
+            self.counts.clear();
+            self.ledger_version = ledger.version();
+        }
+        *self
+            .counts
+            .entry((file.file_path.clone(), agent.map(str::to_string), mode))
+            .or_insert_with(|| count_symbols_for_agent(&file.symbols, ledger, agent, mode))
+    }
+}
+
+/// Shared state for flattening one file's symbols into rows.
+struct FlattenCtx<'a> {
+    collapsed: &'a std::collections::HashSet<String>,
+    ledger: &'a ContextLedger,
+    view: AgentView<'a>,
+    compact_chains: bool,
+}
+
+fn flatten_symbol(sym: &SymbolNode, depth: usize, ctx: &FlattenCtx, rows: &mut Vec<TreeRow>) {
+    walk_symbols(std::slice::from_ref(sym), depth, &mut Flatten { ctx, rows });
+}
+
+/// Visitor pushing one row per shown symbol; collapsed and filtered-out
+/// symbols prune their subtree, and compact chains skip to the chain's end.
+struct Flatten<'c, 'r> {
+    ctx: &'c FlattenCtx<'c>,
+    rows: &'r mut Vec<TreeRow>,
+}
+
+impl<'a> SymbolVisitor<'a> for Flatten<'_, '_> {
+    fn visit(&mut self, sym: &'a SymbolNode, depth: usize) -> &'a [SymbolNode] {
+        let ctx = self.ctx;
+        if !ctx.view.includes(sym, ctx.ledger) {
+            return &[];
+        }
+
+        // With compact chains on, walk down while the node's only child is itself a
+        // container; the row then stands for the innermost node of the chain.
+        let mut node = sym;
+        let mut display_name = sym.name.clone();
+        if ctx.compact_chains {
+            while let [only] = node.children.as_slice() {
+                if only.children.is_empty() || ctx.collapsed.contains(&node.id) {
+                    break;
+                }
+                node = only;
+                display_name = format!("{display_name}::{}", node.name);
+            }
+        }
+
+        let is_expanded = !ctx.collapsed.contains(&node.id);
+        let read_depth = ctx.view.depth(ctx.ledger, &node.id);
+
+        self.rows.push(TreeRow {
+            symbol_id: node.id.clone(),
+            display_name,
+            label: node.label.clone(),
+            depth,
+            is_file: false,
+            is_expanded,
+            has_children: !node.children.is_empty(),
+            line_range: format!("L{}-{}", node.line_range.start, node.line_range.end),
+            token_count: node.estimated_tokens,
+            line_count: node.line_range.end - node.line_range.start + 1,
+            read_depth,
+            coverage_status: None,
+            file_coverage_seen: 0,
+            file_coverage_total: 0,
+            is_pinned: false,
+            has_note: ctx.ledger.notes.get(&node.id).is_some(),
+            modified_since_read: false,
+            is_planned: ctx.ledger.is_planned(&node.id),
+            is_inferred: read_depth.is_seen() && ctx.ledger.is_inferred(&node.id),
+        });
+
+        if is_expanded {
+            &node.children
+        } else {
+            &[]
+        }
+    }
+}
+
+/// The type an impl block is for, without generics: `Foo` for both
+/// `impl Foo<T>` and `impl Display for Foo`. None for anything but impls.
+fn impl_target(sym: &SymbolNode) -> Option<&str> {
+    if sym.category != SymbolCategory::Implementation {
+        return None;
+    }
+    let target = sym.name.rsplit_once(" for ").map_or(sym.name.as_str(), |(_, t)| t);
+    Some(target.split('<').next().unwrap_or(target).trim())
+}
+
+/// Flatten a file's top-level symbols, putting impl blocks that share a target
+/// type under one group row (at the first impl's position) with method coverage.
+fn flatten_with_impl_groups(
+    file_id: &str,
+    symbols: &[SymbolNode],
+    ctx: &FlattenCtx,
+    mode: CountMode,
     rows: &mut Vec<TreeRow>,
 ) {
-    let is_expanded = !collapsed.contains(&sym.id);
-    let read_depth = ledger.depth_of(&sym.id);
-
-    rows.push(TreeRow {
-        symbol_id: sym.id.clone(),
-        display_name: sym.name.clone(),
-        label: sym.label.clone(),
-        depth,
-        is_file: false,
-        is_expanded,
-        has_children: !sym.children.is_empty(),
-        line_range: format!("L{}-{}", sym.line_range.start, sym.line_range.end),
-        token_count: sym.estimated_tokens,
-        read_depth,
-        coverage_status: None,
-        file_coverage_seen: 0,
-        file_coverage_total: 0,
-    });
+    let mut groups: Vec<(&str, Vec<&SymbolNode>)> = Vec::new();
+    for sym in symbols {
+        if let Some(target) = impl_target(sym) {
+            match groups.iter_mut().find(|(t, _)| *t == target) {
+                Some((_, impls)) => impls.push(sym),
+                None => groups.push((target, vec![sym])),
+            }
+        }
+    }
+
+    let mut emitted = std::collections::HashSet::new();
+    for sym in symbols {
+        let group = impl_target(sym)
+            .and_then(|target| groups.iter().find(|(t, impls)| *t == target && impls.len() > 1));
+        let Some((target, impls)) = group else {
+            flatten_symbol(sym, 1, ctx, rows);
+            continue;
+        };
+        if !emitted.insert(*target) || !impls.iter().any(|s| ctx.view.includes(s, ctx.ledger)) {
+            continue;
+        }
 
-    if is_expanded {
-        for child in &sym.children {
-            flatten_symbol(child, depth + 1, collapsed, ledger, rows);
+        let (mut total, mut seen) = (0, 0);
+        for imp in impls {
+            let (t, s, _) = count_symbols_for_agent(&imp.children, ctx.ledger, ctx.view.agent, mode);
+            total += t;
+            seen += s;
+        }
+        let id = format!("{file_id}::impls:{target}");
+        let is_expanded = !ctx.collapsed.contains(&id);
+        rows.push(TreeRow {
+            symbol_id: id,
+            display_name: target.to_string(),
+            label: "impls".into(),
+            depth: 1,
+            is_file: false,
+            is_expanded,
+            has_children: true,
+            line_range: format!("{} impls", impls.len()),
+            token_count: impls.iter().map(|s| s.estimated_tokens).sum(),
+            line_count: impls.iter().map(|s| s.line_range.end - s.line_range.start + 1).sum(),
+            read_depth: if seen > 0 { ReadDepth::NameOnly } else { ReadDepth::Unseen },
+            coverage_status: None,
+            file_coverage_seen: seen,
+            file_coverage_total: total,
+            is_pinned: false,
+            has_note: false,
+            modified_since_read: false,
+            is_planned: false,
+            is_inferred: false,
+        });
+        if is_expanded {
+            for imp in impls {
+                flatten_symbol(imp, 2, ctx, rows);
+            }
         }
     }
 }
 
+fn find_symbol<'a>(symbols: &'a [SymbolNode], id: &str) -> Option<&'a SymbolNode> {
+    symbols.iter().find_map(|s| {
+        if s.id == id {
+            Some(s)
+        } else {
+            find_symbol(&s.children, id)
+        }
+    })
+}
+
 /// Convert a tool call file path (usually absolute) to a relative path matching
 /// the project tree's convention. Strips the project root prefix if present.
 pub fn normalize_tool_path(tool_path: &Path, project_root: &Path) -> PathBuf {
@@ -451,23 +1591,271 @@ pub fn normalize_tool_path(tool_path: &Path, project_root: &Path) -> PathBuf {
     }
 }
 
-pub fn mark_file_symbols(
-    symbols: &[SymbolNode],
+/// What `apply_event` noticed besides the ledger updates.
+#[derive(Debug, Default, PartialEq)]
+pub struct Applied {
+    /// A fuzzy or ambiguous path match worth telling the user about.
+    pub warning: Option<String>,
+    /// The event's path, relative to the project root, when it matched no
+    /// file in the tree at all.
+    pub orphan: Option<PathBuf>,
+}
+
+fn ledger_cache_path(project_root: &Path, session_id: &str) -> PathBuf {
+    project_root.join(".ambits").join(format!("ledger-{session_id}.json"))
+}
+
+/// Fill `ledger` from the cache at `path`, if there is one, marking symbols
+/// of `tree` whose content changed since they were read Stale.
+fn load_cached_ledger(tree: &ProjectTree, path: &Path, ledger: &mut ContextLedger) -> color_eyre::Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    ledger.load_from_path(path)?;
+    for file in &tree.files {
+        visit_symbols(&file.symbols, |sym| ledger.mark_stale_if_changed(&sym.id, sym.content_hash));
+    }
+    Ok(true)
+}
+
+/// Record a tool call in the ledger against every project file it touched.
+/// When the path matches no file exactly, falls back to `fuzzy_file_match`,
+/// and reports a fuzzy or ambiguous match, or no match at all.
+pub fn apply_event(
+    project_tree: &ProjectTree,
+    index: &SymbolIndex,
+    project_root: &Path,
     event: &AgentToolCall,
     ledger: &mut ContextLedger,
-) {
-    for sym in symbols {
-        ledger.record(
+) -> Applied {
+    let mut files = files_for_event(project_tree, project_root, event);
+    let mut warning = None;
+    let mut orphan = None;
+    if let (true, None, Some(path)) = (files.is_empty(), &event.path_glob, &event.file_path) {
+        match fuzzy_file_match(project_tree, project_root, path) {
+            FuzzyMatch::One(file) => {
+                warning = Some(format!(
+                    "Fuzzy path match: {} -> {}",
+                    path.display(),
+                    file.file_path.display()
+                ));
+                files.push(file);
+            }
+            FuzzyMatch::Ambiguous(candidates) => {
+                let names: Vec<String> = candidates.iter().map(|f| f.file_path.display().to_string()).collect();
+                warning = Some(format!("Ambiguous path {}: could be {}", path.display(), names.join(", ")));
+            }
+            FuzzyMatch::NoMatch => {
+                // A search of a directory that holds project files isn't a missing file.
+                let rel = normalize_tool_path(path, project_root);
+                if !project_tree.files.iter().any(|f| f.file_path.starts_with(&rel)) {
+                    orphan = Some(rel);
+                }
+            }
+        }
+    }
+
+    if let Ok(at) = DateTime::parse_from_rfc3339(&event.timestamp_str) {
+        for file in &files {
+            ledger.record_file_read(&file.file_path, at.with_timezone(&Utc));
+        }
+    }
+    for file in &files {
+        ledger.record_file_depth(&file.file_path, event.read_depth);
+    }
+
+    let mark = |ledger: &mut ContextLedger| {
+        for file in &files {
+            match index.file(&file.file_path) {
+                Some(file_index) => mark_indexed_symbols(file_index, event, ledger),
+                // Not indexed (the tree changed without a rebuild): walk it instead.
+                None if event.target_symbol.is_some() || event.target_lines.is_some() => {
+                    mark_targeted_symbols(&file.symbols, event, ledger)
+                }
+                None => mark_file_symbols(&file.symbols, event, ledger),
+            }
+        }
+    };
+    // Writing over a file in the tree replaces its existing symbols, so it edits them.
+    if event.is_edit() || event.is_write() {
+        // Find the edited symbols on a scratch ledger before the edit counts as a read.
+        let mut touched = ContextLedger::new();
+        mark(&mut touched);
+        for id in touched.entries.keys() {
+            ledger.record_edit(id);
+        }
+    }
+    mark(ledger);
+    if event.is_plan() {
+        ledger.record_plan(&event.agent_id, crate::tracking::intent::planned_targets(project_tree, &event.todos));
+    }
+    Applied { warning, orphan }
+}
+
+/// Result of matching a tool path against project files approximately.
+#[derive(Debug)]
+pub enum FuzzyMatch<'a> {
+    One(&'a FileSymbols),
+    Ambiguous(Vec<&'a FileSymbols>),
+    NoMatch,
+}
+
+/// Match a tool path that didn't equal any file path exactly, ignoring case,
+/// `\` vs `/` and `.` components. A file matches when its path ends with the
+/// whole tool path (relative to the root if absolute), and several matches are
+/// ambiguous. Paths that leave the project (`..`, or absolute paths outside the
+/// root) and in-root files missing from the tree never match.
+pub fn fuzzy_file_match<'a>(project_tree: &'a ProjectTree, project_root: &Path, tool_path: &Path) -> FuzzyMatch<'a> {
+    fn components(path: &str) -> Vec<String> {
+        path.split(['/', '\\'])
+            .filter(|c| !c.is_empty() && *c != ".")
+            .map(str::to_lowercase)
+            .collect()
+    }
+
+    let tool_str = tool_path.to_string_lossy();
+    let mut wanted = components(&tool_str);
+    let is_absolute = tool_path.is_absolute() || tool_str.starts_with('\\') || tool_str.get(1..2) == Some(":");
+    if is_absolute {
+        let root = components(&project_root.to_string_lossy());
+        if wanted.len() <= root.len() || wanted[..root.len()] != root[..] {
+            return FuzzyMatch::NoMatch;
+        }
+        wanted.drain(..root.len());
+    }
+    if wanted.is_empty() {
+        return FuzzyMatch::NoMatch;
+    }
+
+    let candidates: Vec<&FileSymbols> = project_tree
+        .files
+        .iter()
+        .filter(|file| components(&file.file_path.to_string_lossy()).ends_with(&wanted))
+        .collect();
+    match candidates.len() {
+        0 => FuzzyMatch::NoMatch,
+        1 => FuzzyMatch::One(candidates[0]),
+        _ => FuzzyMatch::Ambiguous(candidates),
+    }
+}
+
+/// Find the project files a tool call refers to: the file at its path, or,
+/// for scoped searches, every file under its path matching `path_glob`.
+pub fn files_for_event<'a>(
+    project_tree: &'a ProjectTree,
+    project_root: &Path,
+    event: &AgentToolCall,
+) -> Vec<&'a FileSymbols> {
+    // Normalize the tool call path: strip the project root to get a relative path.
+    let base = event
+        .file_path
+        .as_ref()
+        .map(|p| normalize_tool_path(p, project_root));
+
+    let matcher = event.path_glob.as_ref().and_then(|glob| {
+        globset::GlobBuilder::new(glob)
+            .literal_separator(true)
+            .build()
+            .ok()
+            .map(|g| g.compile_matcher())
+    });
+
+    project_tree
+        .files
+        .iter()
+        .filter(|file| {
+            if base.as_deref() == Some(file.file_path.as_path()) {
+                return true;
+            }
+            let Some(ref matcher) = matcher else {
+                return false;
+            };
+            let rel = match base {
+                Some(ref dir) => match file.file_path.strip_prefix(dir) {
+                    Ok(rel) => rel,
+                    Err(_) => return false,
+                },
+                None => file.file_path.as_path(),
+            };
+            matcher.is_match(rel)
+        })
+        .collect()
+}
+
+/// Mark the symbols a tool call covers using the file's index: every symbol
+/// for untargeted calls, otherwise each matched symbol with its descendants.
+/// Same result as `mark_targeted_symbols` / `mark_file_symbols` on the tree.
+pub fn mark_indexed_symbols(index: &FileIndex, event: &AgentToolCall, ledger: &mut ContextLedger) {
+    let record = |sym: &IndexedSymbol, ledger: &mut ContextLedger| {
+        ledger.record_with(
             sym.id.clone(),
             event.read_depth,
             sym.content_hash,
             event.agent_id.clone(),
             sym.estimated_tokens,
+            event.source,
         );
-        mark_file_symbols(&sym.children, event, ledger);
+    };
+    if event.target_symbol.is_none() && event.target_lines.is_none() {
+        let within_limit = |sym: &&IndexedSymbol| event.symbol_depth_limit.is_none_or(|max| sym.depth <= max);
+        for sym in index.symbols.iter().filter(within_limit) {
+            record(sym, ledger);
+        }
+        return;
+    }
+
+    let mut hits = Vec::new();
+    if let Some(ref target) = event.target_symbol {
+        hits.extend(
+            index
+                .name_candidates(target)
+                .into_iter()
+                .filter(|&i| name_matches_target(&index.symbols[i].id, &index.symbols[i].name, target)),
+        );
+    }
+    if let Some(ref lines) = event.target_lines {
+        hits.extend(index.overlapping(lines));
+        hits.extend(index.module_targeted(lines));
+    }
+
+    // Subtrees in pre-order are nested or disjoint, so skipping hits inside
+    // the last marked subtree records each symbol once.
+    hits.sort_unstable();
+    let mut marked_until = 0;
+    for start in hits {
+        if start < marked_until {
+            continue;
+        }
+        marked_until = index.symbols[start].subtree_end;
+        for sym in &index.symbols[start..marked_until] {
+            record(sym, ledger);
+        }
     }
 }
 
+/// Mark `symbols` and their descendants, down to the event's `symbol_depth_limit`.
+pub fn mark_file_symbols<'a>(
+    symbols: &'a [SymbolNode],
+    event: &AgentToolCall,
+    ledger: &mut ContextLedger,
+) {
+    walk_symbols(symbols, 0, &mut |sym: &'a SymbolNode, depth| {
+        ledger.record_with(
+            sym.id.clone(),
+            event.read_depth,
+            sym.content_hash,
+            event.agent_id.clone(),
+            sym.estimated_tokens,
+            event.source,
+        );
+        if event.symbol_depth_limit.is_some_and(|max| depth >= max) {
+            &[]
+        } else {
+            sym.children.as_slice()
+        }
+    });
+}
+
 /// Mark only the symbols that match the tool call's targeting info.
 pub fn mark_targeted_symbols(
     symbols: &[SymbolNode],
@@ -475,14 +1863,19 @@ pub fn mark_targeted_symbols(
     ledger: &mut ContextLedger,
 ) {
     for sym in symbols {
-        let matches = symbol_matches_target(sym, event);
+        let matches = if sym.is_module_symbol() {
+            module_lines_targeted(symbols, sym, event)
+        } else {
+            symbol_matches_target(sym, event)
+        };
         if matches {
-            ledger.record(
+            ledger.record_with(
                 sym.id.clone(),
                 event.read_depth,
                 sym.content_hash,
                 event.agent_id.clone(),
                 sym.estimated_tokens,
+                event.source,
             );
             // If we matched a parent (e.g. an impl block), also mark children
             mark_file_symbols(&sym.children, event, ledger);
@@ -493,19 +1886,35 @@ pub fn mark_targeted_symbols(
     }
 }
 
+/// Whether a line-targeted tool call touches module-level code: lines in the
+/// module pseudo-symbol's span that none of its siblings cover.
+fn module_lines_targeted(siblings: &[SymbolNode], module: &SymbolNode, event: &AgentToolCall) -> bool {
+    let Some(ref target) = event.target_lines else {
+        return false;
+    };
+    crate::symbols::uncovered_lines(siblings, module.line_range.end)
+        .into_iter()
+        .any(|line| line >= module.line_range.start && target.contains(&line))
+}
+
+/// Whether a symbol with this id and name is the one a `target_symbol` names.
+fn name_matches_target(id: &str, name: &str, target_name: &str) -> bool {
+    // Match if the symbol's id ends with the target name path.
+    // SymbolId format is "file_path::name_path", e.g. "src/app.rs::impl App/handle_key"
+    // target_name is a Serena name_path like "App/handle_key" or just "handle_key"
+    if let Some(name_part) = id.split("::").last() {
+        if name_part == target_name || name_part.ends_with(&format!("/{target_name}")) {
+            return true;
+        }
+    }
+    // Also check plain name match for simple names
+    name == target_name
+}
+
 /// Check if a symbol matches the tool call's target_symbol or target_lines.
 pub fn symbol_matches_target(sym: &SymbolNode, event: &AgentToolCall) -> bool {
     if let Some(ref target_name) = event.target_symbol {
-        // Match if the symbol's id ends with the target name path.
-        // SymbolId format is "file_path::name_path", e.g. "src/app.rs::impl App/handle_key"
-        // target_name is a Serena name_path like "App/handle_key" or just "handle_key"
-        if let Some(name_part) = sym.id.split("::").last() {
-            if name_part == target_name || name_part.ends_with(&format!("/{target_name}")) {
-                return true;
-            }
-        }
-        // Also check plain name match for simple names
-        if sym.name == *target_name {
+        if name_matches_target(&sym.id, &sym.name, target_name) {
             return true;
         }
     }
@@ -518,6 +1927,20 @@ pub fn symbol_matches_target(sym: &SymbolNode, event: &AgentToolCall) -> bool {
     false
 }
 
+/// A file's coverage status from its `(total, seen, full)` symbol counts. A
+/// file without symbols to count is as covered as its deepest read.
+fn file_coverage_status(file: &FileSymbols, counts: (usize, usize, usize), ledger: &ContextLedger) -> FileCoverageStatus {
+    let (total, seen, full) = counts;
+    if total > 0 || !file.symbols.is_empty() {
+        return coverage_status_from_counts(total, seen, full);
+    }
+    match ledger.file_depth(&file.file_path) {
+        ReadDepth::FullBody => FileCoverageStatus::FullyCovered,
+        depth if depth.is_seen() => FileCoverageStatus::AllSeen,
+        _ => FileCoverageStatus::NotCovered,
+    }
+}
+
 /// Classify a file's coverage as fully covered, all seen, partially covered, or not covered.
 /// "Fully covered" means every symbol has been read at FullBody depth.
 /// "All seen" means every symbol has been seen (depth > Unseen) but not all at FullBody.
@@ -541,14 +1964,14 @@ fn coverage_status_from_counts(total: usize, seen: usize, full: usize) -> FileCo
 
 #[cfg(test)]
 #[path = "../tests/helpers/mod.rs"]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::duplicate_mod)]
 mod helpers;
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use super::helpers::*;
-    use crate::symbols::FileSymbols;
+    use crate::coverage::count_symbols;
     use std::path::Path;
 
     #[test]
@@ -676,6 +2099,18 @@ mod tests {
         assert_eq!(app.ledger.depth_of("mock/f.rs::beta"), ReadDepth::FullBody);
     }
 
+    #[test]
+    fn reading_a_file_without_symbols_covers_it() {
+        let mut app = test_app(vec![file("mock/index.rs", vec![]), file("mock/unread.rs", vec![])]);
+        app.collapsed.clear();
+        app.process_agent_event(tool_call("Read", "/test/project/mock/index.rs", ReadDepth::FullBody));
+
+        let status = |app: &App, path: &str| app.tree_rows.iter().find(|r| r.symbol_id == path).unwrap().coverage_status;
+        assert_eq!(status(&app, "mock/index.rs"), Some(FileCoverageStatus::FullyCovered));
+        assert_eq!(status(&app, "mock/unread.rs"), Some(FileCoverageStatus::NotCovered));
+        assert_eq!(app.ledger.total_seen(), 0);
+    }
+
     #[test]
     fn process_agent_event_targeted() {
         let syms = vec![sym("mock/f.rs::alpha", "alpha"), sym("mock/f.rs::beta", "beta")];
@@ -740,4 +2175,935 @@ mod tests {
         // PartiallyCovered (mock/a.rs) sorts before NotCovered (mock/b.rs).
         assert_eq!(file_rows, vec!["mock/a.rs", "mock/b.rs"]);
     }
+
+    #[test]
+    fn not_covered_by_filter_hides_agent_reads() {
+        let syms = vec![
+            sym("mock/a.rs::x", "x"),
+            sym("mock/a.rs::y", "y"),
+            sym("mock/a.rs::z", "z"),
+        ];
+        let mut app = test_app(vec![file("mock/a.rs", syms), file("mock/b.rs", vec![sym("mock/b.rs::w", "w")])]);
+        app.collapsed.clear();
+        app.ledger.record("mock/a.rs::x".into(), ReadDepth::FullBody, [0; 32], "agent-a".into(), 10);
+        app.ledger.record("mock/a.rs::y".into(), ReadDepth::FullBody, [0; 32], "agent-b".into(), 10);
+        app.ledger.record("mock/b.rs::w".into(), ReadDepth::FullBody, [0; 32], "agent-b".into(), 10);
+        app.agent_filter = Some("agent-b".into());
+        app.agent_filter_mode = AgentFilterMode::NotCoveredBy;
+        app.rebuild_tree_rows();
+
+        let names: Vec<&str> = app.tree_rows.iter().map(|r| r.display_name.as_str()).collect();
+        // agent-b read y and all of b.rs; x (read by agent-a) and z (read by nobody) remain.
+        assert_eq!(names, vec!["mock/a.rs", "x", "z"]);
+        // Rows keep the overall depth so agent-a's read is still visible.
+        assert_eq!(app.tree_rows[1].read_depth, ReadDepth::FullBody);
+        assert_eq!(app.tree_rows[2].read_depth, ReadDepth::Unseen);
+    }
+
+    #[test]
+    fn covered_by_filter_uses_agent_depth() {
+        let mut app = test_app(vec![file("mock/a.rs", vec![sym("mock/a.rs::x", "x")])]);
+        app.collapsed.clear();
+        app.ledger.record("mock/a.rs::x".into(), ReadDepth::FullBody, [0; 32], "agent-a".into(), 10);
+        app.agent_filter = Some("agent-b".into());
+        app.rebuild_tree_rows();
+
+        assert_eq!(app.tree_rows[1].read_depth, ReadDepth::Unseen);
+        assert_eq!(app.tree_rows[0].file_coverage_seen, 0);
+    }
+
+    #[test]
+    fn scoped_glob_marks_matching_files() {
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::a", "a")]),
+            file("src/sub/b.rs", vec![sym("src/sub/b.rs::b", "b")]),
+            file("tests/c.rs", vec![sym("tests/c.rs::c", "c")]),
+            file("src/d.py", vec![sym("src/d.py::d", "d")]),
+        ]);
+
+        let mut event = tool_call("Grep", "/test/project/src", ReadDepth::Overview);
+        event.path_glob = Some("**/*.rs".into());
+        app.process_agent_event(event);
+
+        assert_eq!(app.ledger.depth_of("src/a.rs::a"), ReadDepth::Overview);
+        assert_eq!(app.ledger.depth_of("src/sub/b.rs::b"), ReadDepth::Overview);
+        assert_eq!(app.ledger.depth_of("tests/c.rs::c"), ReadDepth::Unseen);
+        assert_eq!(app.ledger.depth_of("src/d.py::d"), ReadDepth::Unseen);
+    }
+
+    #[test]
+    fn unfiltered_directory_search_marks_nothing() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::a", "a")])]);
+        app.process_agent_event(tool_call("Grep", "/test/project/src", ReadDepth::Overview));
+
+        assert_eq!(app.ledger.depth_of("src/a.rs::a"), ReadDepth::Unseen);
+        assert!(app.orphan_reads.is_empty());
+    }
+
+    #[test]
+    fn root_glob_without_path() {
+        let tree = project(vec![
+            file("src/a.rs", vec![sym("src/a.rs::a", "a")]),
+            file("src/sub/b.rs", vec![sym("src/sub/b.rs::b", "b")]),
+        ]);
+        let mut event = tool_call("Glob", "", ReadDepth::NameOnly);
+        event.file_path = None;
+        event.path_glob = Some("src/*.rs".into());
+
+        let matched: Vec<_> = files_for_event(&tree, Path::new("/test/project"), &event)
+            .iter()
+            .map(|f| f.file_path.clone())
+            .collect();
+        // `*` does not cross directory separators.
+        assert_eq!(matched, vec![PathBuf::from("src/a.rs")]);
+    }
+
+    #[test]
+    fn overview_marks_top_level_names_only() {
+        let mut app = test_app(vec![file(
+            "src/a.rs",
+            vec![sym_with_children("src/a.rs::impl A", "impl A", vec![sym("src/a.rs::impl A/f", "f")]), sym("src/a.rs::g", "g")],
+        )]);
+        let mut overview = tool_call("get_symbols_overview", "src/a.rs", ReadDepth::NameOnly);
+        overview.symbol_depth_limit = Some(0);
+        app.process_agent_event(overview.clone());
+        assert_eq!(app.ledger.depth_of("src/a.rs::impl A"), ReadDepth::NameOnly);
+        assert_eq!(app.ledger.depth_of("src/a.rs::g"), ReadDepth::NameOnly);
+        assert_eq!(app.ledger.depth_of("src/a.rs::impl A/f"), ReadDepth::Unseen);
+
+        overview.symbol_depth_limit = Some(1);
+        app.process_agent_event(overview);
+        assert_eq!(app.ledger.depth_of("src/a.rs::impl A/f"), ReadDepth::NameOnly);
+    }
+
+    #[test]
+    fn indexed_marking_matches_tree_walk() {
+        let mut imp = sym_with_lines("src/a.rs::impl App", "impl App", 10, 40);
+        imp.children = vec![
+            sym_with_lines("src/a.rs::impl App/new", "new", 11, 20),
+            sym_with_lines("src/a.rs::impl App/handle_key", "handle_key", 21, 39),
+        ];
+        let symbols = vec![
+            sym_with_lines("src/a.rs::new", "new", 1, 5),
+            imp,
+            sym_with_lines("src/a.rs::handle_key", "handle_key", 45, 50),
+        ];
+        let index = FileIndex::new(&symbols);
+
+        let path = "/test/project/src/a.rs";
+        let mut events = vec![tool_call("Read", path, ReadDepth::FullBody)];
+        for target in ["new", "App/handle_key", "impl App", "missing"] {
+            events.push(tool_call_targeted("find_symbol", path, ReadDepth::FullBody, target));
+        }
+        let mut overview = tool_call("get_symbols_overview", path, ReadDepth::NameOnly);
+        overview.symbol_depth_limit = Some(0);
+        events.push(overview);
+        for lines in [3..4, 15..25, 41..44, 0..100, 45..46] {
+            let mut event = tool_call("Read", path, ReadDepth::Overview);
+            event.target_lines = Some(lines);
+            events.push(event);
+        }
+        for event in &events {
+            let (mut walked, mut indexed) = (ContextLedger::new(), ContextLedger::new());
+            if event.target_symbol.is_some() || event.target_lines.is_some() {
+                mark_targeted_symbols(&symbols, event, &mut walked);
+            } else {
+                mark_file_symbols(&symbols, event, &mut walked);
+            }
+            mark_indexed_symbols(&index, event, &mut indexed);
+
+            let depths = |l: &ContextLedger| {
+                let mut v: Vec<(String, ReadDepth)> = l.entries.values().map(|e| (e.symbol_id.clone(), e.depth)).collect();
+                v.sort();
+                v
+            };
+            assert_eq!(depths(&walked), depths(&indexed), "{:?} {:?}", event.target_symbol, event.target_lines);
+        }
+    }
+
+    #[test]
+    fn apply_event_tracks_whether_edits_were_read_first() {
+        let tree = project(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")])]);
+        let index = SymbolIndex::new(&tree);
+        let root = Path::new("/test/project");
+        let mut ledger = ContextLedger::new();
+        let path = "/test/project/src/a.rs";
+        apply_event(&tree, &index, root, &tool_call_targeted("Read", path, ReadDepth::FullBody, "f"), &mut ledger);
+        apply_event(&tree, &index, root, &tool_call("Edit", path, ReadDepth::FullBody), &mut ledger);
+        // A second edit doesn't count the first one as a read.
+        apply_event(&tree, &index, root, &tool_call("Edit", path, ReadDepth::FullBody), &mut ledger);
+
+        assert_eq!(ledger.edited.get("src/a.rs::f"), Some(&true));
+        assert_eq!(ledger.edited.get("src/a.rs::g"), Some(&false));
+        assert_eq!(ledger.depth_of("src/a.rs::g"), ReadDepth::FullBody);
+    }
+
+    #[test]
+    fn write_over_unread_file_is_edited_before_read() {
+        let tree = project(vec![
+            file("src/a.rs", vec![sym("src/a.rs::f", "f")]),
+            file("src/b.rs", vec![sym("src/b.rs::g", "g")]),
+        ]);
+        let index = SymbolIndex::new(&tree);
+        let root = Path::new("/test/project");
+        let mut ledger = ContextLedger::new();
+        apply_event(&tree, &index, root, &tool_call("Read", "/test/project/src/b.rs", ReadDepth::FullBody), &mut ledger);
+        apply_event(&tree, &index, root, &tool_call("Write", "/test/project/src/a.rs", ReadDepth::FullBody), &mut ledger);
+        apply_event(&tree, &index, root, &tool_call("Write", "/test/project/src/b.rs", ReadDepth::FullBody), &mut ledger);
+        // A write of a file that isn't in the tree yet edits nothing.
+        apply_event(&tree, &index, root, &tool_call("Write", "/test/project/src/new.rs", ReadDepth::FullBody), &mut ledger);
+
+        // What `--check` reports.
+        assert_eq!(crate::rules::edited_before_read(&ledger), ["src/a.rs::f"]);
+    }
+
+    #[test]
+    fn fuzzy_path_match_recovers_near_misses() {
+        let tree = project(vec![
+            file("src/app.rs", vec![]),
+            file("src/parser/mod.rs", vec![]),
+            file("src/ui/mod.rs", vec![]),
+        ]);
+        let root = Path::new("/test/project");
+        let matched = |p: &str| match fuzzy_file_match(&tree, root, Path::new(p)) {
+            FuzzyMatch::One(f) => Some(f.file_path.to_string_lossy().to_string()),
+            _ => None,
+        };
+        assert_eq!(matched("./src/app.rs").as_deref(), Some("src/app.rs"));
+        assert_eq!(matched("SRC/App.rs").as_deref(), Some("src/app.rs"));
+        assert_eq!(matched("src\\ui\\mod.rs").as_deref(), Some("src/ui/mod.rs"));
+        assert_eq!(matched("/Test/Project/src/parser/mod.rs").as_deref(), Some("src/parser/mod.rs"));
+        assert_eq!(matched("parser/mod.rs").as_deref(), Some("src/parser/mod.rs"));
+        // The whole tool path has to match, not just its tail.
+        assert!(matched("crate/parser/mod.rs").is_none());
+        // Files outside the project root never match.
+        assert!(matched("/elsewhere/src/app.rs").is_none());
+        assert!(matched("../other/src/app.rs").is_none());
+        // Nor do in-root files the tree leaves out.
+        assert!(matched("target/debug/build/app.rs").is_none());
+        assert!(matched("/test/project/vendor/src/app.rs").is_none());
+        // ...and such reads stay listed as orphans rather than crediting src/app.rs.
+        let index = SymbolIndex::new(&tree);
+        let mut ledger = ContextLedger::new();
+        let applied = apply_event(&tree, &index, root, &tool_call("Read", "/test/project/target/app.rs", ReadDepth::FullBody), &mut ledger);
+        assert_eq!((applied.warning, applied.orphan.as_deref()), (None, Some(Path::new("target/app.rs"))));
+        assert!(matches!(
+            fuzzy_file_match(&tree, root, Path::new("mod.rs")),
+            FuzzyMatch::Ambiguous(ref c) if c.len() == 2
+        ));
+    }
+
+    #[test]
+    fn process_agent_event_uses_fuzzy_match_and_warns() {
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::f", "f")]),
+            file("src/x/mod.rs", vec![sym("src/x/mod.rs::g", "g")]),
+            file("src/y/mod.rs", vec![sym("src/y/mod.rs::h", "h")]),
+        ]);
+        app.process_agent_event(tool_call("Read", "./src/A.rs", ReadDepth::FullBody));
+        assert_eq!(app.ledger.depth_of("src/a.rs::f"), ReadDepth::FullBody);
+        assert_eq!(app.warnings, vec!["Fuzzy path match: ./src/A.rs -> src/a.rs"]);
+
+        app.process_agent_event(tool_call("Read", "mod.rs", ReadDepth::FullBody));
+        assert_eq!(app.ledger.depth_of("src/x/mod.rs::g"), ReadDepth::Unseen);
+        assert!(app.warnings[1].starts_with("Ambiguous path mod.rs"));
+
+        // Exact matches don't warn.
+        app.process_agent_event(tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody));
+        assert_eq!(app.warnings.len(), 2);
+    }
+
+    #[test]
+    fn quit_asks_first_unless_forced_or_disabled() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")])]);
+        let press = |app: &mut App, c| app.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+        press(&mut app, 'q');
+        assert!(app.quit_pending && !app.should_quit);
+        // Any other key cancels without acting on it.
+        press(&mut app, 'j');
+        assert!(!app.quit_pending && !app.should_quit);
+        assert_eq!(app.selected_index, 0);
+        press(&mut app, 'q');
+        press(&mut app, 'y');
+        assert!(app.should_quit);
+
+        let mut app = test_app(vec![]);
+        app.handle_key(KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::SHIFT));
+        assert!(app.should_quit);
+
+        let mut app = test_app(vec![]);
+        app.confirm_quit = false;
+        press(&mut app, 'q');
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn exit_summary_reports_percentages() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")])]);
+        app.process_agent_event(tool_call_targeted("Read", "/test/project/src/a.rs", ReadDepth::FullBody, "f"));
+        assert_eq!(app.exit_summary(), "Coverage: 50% seen, 50% full across 2 symbols");
+        app.session_id = Some("34e212cf-a176-4059-ba12-eca94b56e43b".into());
+        assert!(app.exit_summary().starts_with("Session 34e212cf: "));
+    }
+
+    #[test]
+    fn sample_coverage_keeps_a_bounded_history() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")])]);
+        let start = Instant::now();
+        app.sample_coverage(start);
+        app.process_agent_event(tool_call_targeted("Read", "/test/project/src/a.rs", ReadDepth::FullBody, "f"));
+        // Too soon for another sample.
+        app.sample_coverage(start + Duration::from_millis(250));
+        assert_eq!(app.coverage_history, [0]);
+        app.sample_coverage(start + COVERAGE_SAMPLE_INTERVAL);
+        assert_eq!(app.coverage_history, [0, 50]);
+
+        for i in 2..=COVERAGE_HISTORY_LEN as u32 + 5 {
+            app.sample_coverage(start + COVERAGE_SAMPLE_INTERVAL * i);
+        }
+        assert_eq!(app.coverage_history.len(), COVERAGE_HISTORY_LEN);
+        assert!(app.coverage_history.iter().all(|&pct| pct == 50));
+    }
+
+    #[test]
+    fn handle_key_uses_configured_keymap() {
+        use crate::config::KeyBinding;
+
+        let mut app = test_app(vec![file("src/a.rs", vec![]), file("src/b.rs", vec![])]);
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("down".to_string(), KeyBinding::One("ctrl-n".into()));
+        app.keymap = KeyMap::from_config(&overrides).unwrap();
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert_eq!(app.selected_index, 0);
+        app.handle_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL));
+        assert_eq!(app.selected_index, 1);
+
+        // Ctrl-C quits regardless of bindings.
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn detail_toggle_and_selected_symbol() {
+        let mut app = test_app(vec![file(
+            "src/a.rs",
+            vec![sym_with_children("src/a.rs::Outer", "Outer", vec![sym("src/a.rs::Outer/inner", "inner")])],
+        )]);
+        app.collapsed.clear();
+        app.rebuild_tree_rows();
+
+        // File row has no symbol.
+        assert!(app.selected_symbol().is_none());
+        app.selected_index = 2;
+        assert_eq!(app.selected_symbol().unwrap().name, "inner");
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+        assert!(app.show_detail);
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(!app.show_detail);
+    }
+
+    #[test]
+    fn ledger_cache_round_trips_and_marks_changed_symbols_stale() {
+        use crate::symbols::merkle::content_hash;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")])]);
+        app.project_root = tmp.path().to_path_buf();
+        app.save_ledger_cache().unwrap();
+        assert!(!tmp.path().join(".ambits").exists());
+        app.session_id = Some("s1".into());
+        app.watch_sessions(vec!["s1".into(), "s2".into()]);
+        app.ledger.record("src/a.rs::f".into(), ReadDepth::FullBody, content_hash("f"), "ag".into(), 10);
+        app.ledger.record("src/a.rs::g".into(), ReadDepth::FullBody, content_hash("old g"), "ag".into(), 10);
+        let mut event = tool_call("Read", "src/a.rs", ReadDepth::FullBody);
+        event.timestamp_str = "2025-01-01T10:00:00Z".into();
+        app.process_session_event("s2", event);
+        app.save_ledger_cache().unwrap();
+        assert!(tmp.path().join(".ambits/ledger-s1.json").exists());
+        assert!(tmp.path().join(".ambits/ledger-s2.json").exists());
+
+        let mut reopened = test_app(app.project_tree.files.clone());
+        reopened.project_root = tmp.path().to_path_buf();
+        reopened.session_id = Some("s1".into());
+        reopened.watch_sessions(vec!["s1".into(), "s2".into()]);
+        assert!(reopened.load_ledger_cache().unwrap());
+        assert_eq!(reopened.ledger.depth_of("src/a.rs::f"), ReadDepth::FullBody);
+        assert_eq!(reopened.ledger.depth_of("src/a.rs::g"), ReadDepth::Stale);
+        let parked = reopened.session_ledger("s2").unwrap();
+        assert_eq!(parked.depth_of("src/a.rs::g"), ReadDepth::FullBody);
+        assert!(parked.has_applied("2025-01-01T10:00:00Z"));
+
+        reopened.session_id = Some("s3".into());
+        reopened.parked_sessions.clear();
+        assert!(!reopened.load_ledger_cache().unwrap());
+    }
+
+    #[test]
+    fn note_input_saves_note_and_marks_row() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f")])]);
+        app.project_root = tmp.path().to_path_buf();
+        let press = |app: &mut App, code| app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+
+        // File rows can't take notes.
+        press(&mut app, KeyCode::Char('n'));
+        assert!(app.note_input.is_none());
+
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('n'));
+        for c in "skipped errs".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Backspace);
+        // Normal-mode keys are typed into the note, not dispatched.
+        assert_eq!(app.selected_index, 1);
+        press(&mut app, KeyCode::Enter);
+
+        assert!(app.note_input.is_none());
+        assert_eq!(app.ledger.notes.get("src/a.rs::f"), Some("skipped err"));
+        assert!(app.tree_rows[1].has_note);
+        let saved = crate::tracking::notes::Notes::load(tmp.path()).unwrap();
+        assert_eq!(saved.get("src/a.rs::f"), Some("skipped err"));
+
+        // Esc discards an edit.
+        press(&mut app, KeyCode::Char('n'));
+        press(&mut app, KeyCode::Char('x'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.ledger.notes.get("src/a.rs::f"), Some("skipped err"));
+    }
+
+    #[test]
+    fn group_impls_clusters_blocks_by_target_type() {
+        let imp = |id: &str, name: &str, methods: Vec<SymbolNode>| {
+            let mut s = sym_with_children(id, name, methods);
+            s.category = SymbolCategory::Implementation;
+            s.label = "impl".into();
+            s
+        };
+        let symbols = vec![
+            sym("src/a.rs::Foo", "Foo"),
+            imp("src/a.rs::impl Foo", "Foo<T>", vec![sym("src/a.rs::impl Foo/new", "new")]),
+            imp("src/a.rs::impl Bar", "Bar", vec![sym("src/a.rs::impl Bar/run", "run")]),
+            imp(
+                "src/a.rs::impl Display for Foo",
+                "Display for Foo",
+                vec![sym("src/a.rs::impl Display for Foo/fmt", "fmt"), sym("src/a.rs::impl Display for Foo/x", "x")],
+            ),
+        ];
+        let mut app = test_app(vec![file("src/a.rs", symbols)]);
+        app.collapsed.clear();
+        app.ledger.record("src/a.rs::impl Display for Foo/fmt".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        app.handle_key(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE));
+
+        let rows: Vec<_> = app.tree_rows.iter().map(|r| (r.depth, r.display_name.as_str())).collect();
+        assert_eq!(
+            rows,
+            vec![
+                (0, "src/a.rs"),
+                (1, "Foo"),
+                (1, "Foo"),
+                (2, "Foo<T>"),
+                (3, "new"),
+                (2, "Display for Foo"),
+                (3, "fmt"),
+                (3, "x"),
+                // A type with a single impl stays as it was.
+                (1, "Bar"),
+                (2, "run"),
+            ]
+        );
+        let group = &app.tree_rows[2];
+        assert_eq!((group.label.as_str(), group.line_range.as_str()), ("impls", "2 impls"));
+        assert_eq!((group.file_coverage_seen, group.file_coverage_total), (1, 3));
+
+        // Group rows collapse like any other.
+        app.selected_index = 2;
+        app.handle_key(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE));
+        assert_eq!(app.tree_rows.len(), 5);
+    }
+
+    #[test]
+    fn coverage_cache_follows_ledger_and_tree_changes() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")])]);
+        assert_eq!(app.tree_rows[0].file_coverage_seen, 0);
+
+        app.ledger.record("src/a.rs::f".into(), ReadDepth::FullBody, [0; 32], "main".into(), 10);
+        app.rebuild_tree_rows();
+        assert_eq!(app.tree_rows[0].file_coverage_seen, 1);
+
+        // Replacing a file's symbols leaves the ledger alone, so the cache must be dropped.
+        app.project_tree.files[0].symbols.pop();
+        app.tree_changed();
+        app.rebuild_tree_rows();
+        assert_eq!(app.tree_rows[0].file_coverage_total, 1);
+    }
+
+    #[test]
+    fn star_cycles_through_symbols_with_the_same_name() {
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::new", "new"), sym("src/a.rs::other", "other")]),
+            file(
+                "src/b.rs",
+                vec![sym_with_children("src/b.rs::Foo", "Foo", vec![sym("src/b.rs::Foo::new", "new")])],
+            ),
+        ]);
+        app.collapsed.insert("src/b.rs::Foo".into());
+        app.collapsed.remove("src/a.rs");
+        app.rebuild_tree_rows();
+        app.selected_index = app.tree_rows.iter().position(|r| r.symbol_id == "src/a.rs::new").unwrap();
+
+        let star = KeyEvent::new(KeyCode::Char('*'), KeyModifiers::SHIFT);
+        app.handle_key(star);
+        // The collapsed file and parent were expanded to reach the match.
+        assert_eq!(app.selected_symbol().unwrap().id, "src/b.rs::Foo::new");
+        assert_eq!(app.active_flash(), Some("new: 2/2"));
+
+        app.handle_key(star);
+        assert_eq!(app.selected_symbol().unwrap().id, "src/a.rs::new");
+
+        app.selected_index = app.tree_rows.iter().position(|r| r.symbol_id == "src/a.rs::other").unwrap();
+        app.handle_key(star);
+        assert_eq!(app.selected_symbol().unwrap().id, "src/a.rs::other");
+        assert_eq!(app.active_flash(), Some("No other symbols named other"));
+    }
+
+    #[test]
+    fn x_exports_the_visible_rows() {
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::alpha", "alpha"), sym("src/a.rs::beta", "beta")]),
+            file("src/b.rs", vec![sym("src/b.rs::gamma", "gamma")]),
+        ]);
+        app.process_agent_event(tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody));
+        app.collapsed.remove("src/a.rs");
+        app.rebuild_tree_rows();
+
+        let text = app.view_text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "# /test/project (sorted by name)");
+        assert!(lines[1].starts_with("src/a.rs  2/2"), "{text}");
+        assert!(lines[2].starts_with("  [full]      fn alpha"), "{text}");
+        // The collapsed file's symbols aren't on screen, so they aren't exported.
+        assert!(lines[4].starts_with("src/b.rs  0/1"), "{text}");
+        assert!(!text.contains("gamma"));
+
+        let tmp = tempfile::tempdir().unwrap();
+        app.project_root = tmp.path().to_path_buf();
+        app.handle_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        let written: Vec<_> = std::fs::read_dir(tmp.path()).unwrap().flatten().collect();
+        assert_eq!(written.len(), 1);
+        let name = written[0].file_name().to_string_lossy().to_string();
+        assert!(name.starts_with("ambits-view-") && name.ends_with(".txt"));
+        assert_eq!(app.active_flash(), Some(format!("Wrote {name}").as_str()));
+        assert!(std::fs::read_to_string(written[0].path()).unwrap().contains("alpha"));
+    }
+
+    #[test]
+    fn selection_follows_its_row_across_rebuilds() {
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::a", "a")]),
+            file("src/b.rs", vec![sym("src/b.rs::b", "b")]),
+        ]);
+        app.collapsed.clear();
+        app.rebuild_tree_rows();
+        app.selected_index = 3;
+        assert_eq!(app.selected_symbol().unwrap().name, "b");
+
+        // Sorting by coverage moves the fully read file to the top.
+        app.process_agent_event(tool_call("Read", "/test/project/src/b.rs", ReadDepth::FullBody));
+        app.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE));
+        assert_eq!(app.tree_rows[0].display_name, "src/b.rs");
+        assert_eq!(app.selected_index, 1);
+        assert_eq!(app.selected_symbol().unwrap().name, "b");
+
+        // A row that's gone leaves the cursor clamped to what's left.
+        app.selected_index = 3;
+        app.collapsed.insert("src/a.rs".into());
+        app.rebuild_tree_rows();
+        assert_eq!(app.tree_rows.len(), 3);
+        assert_eq!(app.selected_index, 2);
+    }
+
+    #[test]
+    fn t_switches_row_sizes_between_tokens_and_lines() {
+        let mut alpha = sym_with_lines("src/a.rs::alpha", "alpha", 3, 9);
+        alpha.estimated_tokens = 42;
+        let mut app = test_app(vec![file("src/a.rs", vec![alpha])]);
+        app.collapsed.clear();
+        app.rebuild_tree_rows();
+        let sizes = |app: &App| -> Vec<String> { app.tree_rows.iter().map(|r| r.size_label(app.show_lines)).collect() };
+        assert_eq!(sizes(&app), ["100 lines", "~42 tok"]);
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE));
+        assert_eq!(sizes(&app), ["~42 tok", "7 lines"]);
+        assert!(app.view_text().contains("fn alpha  [L3-9] 7 lines"), "{}", app.view_text());
+    }
+
+    #[test]
+    fn hiding_the_activity_feed_takes_it_out_of_the_focus_cycle() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::alpha", "alpha")])]);
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        app.handle_key(tab);
+        app.handle_key(tab);
+        assert_eq!(app.focus, FocusPanel::Activity);
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('F'), KeyModifiers::SHIFT));
+        assert!(!app.show_activity);
+        assert_eq!(app.focus, FocusPanel::Tree);
+        app.handle_key(tab);
+        app.handle_key(tab);
+        assert_eq!(app.focus, FocusPanel::Tree);
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('F'), KeyModifiers::SHIFT));
+        assert!(app.show_activity);
+    }
+
+    #[test]
+    fn large_files_stay_folded_until_expanded_by_hand() {
+        let mut app = test_app(vec![
+            file("src/big.rs", (0..3).map(|i| sym(&format!("src/big.rs::f{i}"), &format!("f{i}"))).collect()),
+            file("src/small.rs", vec![sym("src/small.rs::g", "g")]),
+        ]);
+        app.large_file_symbols = 2;
+        // Not in `collapsed` (e.g. a file added while running), but over the cap.
+        app.collapsed.clear();
+        app.rebuild_tree_rows();
+        let shown: Vec<&str> = app.tree_rows.iter().map(|r| r.display_name.as_str()).collect();
+        assert_eq!(shown, ["src/big.rs", "src/small.rs", "g"]);
+        assert_eq!(app.tree_rows[0].line_range, "3 symbols (truncated)");
+        assert!(!app.tree_rows[0].is_expanded);
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE));
+        assert_eq!(app.tree_rows.len(), 6);
+        assert_eq!(app.tree_rows[0].line_range, "");
+        app.handle_key(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE));
+        assert_eq!(app.tree_rows.len(), 3);
+        assert!(app.tree_rows[0].line_range.ends_with("(truncated)"));
+    }
+
+    #[test]
+    fn shift_x_writes_the_stale_report() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym_with_lines("src/a.rs::alpha", "alpha", 2, 8)])]);
+        app.process_agent_event(tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody));
+        let tmp = tempfile::tempdir().unwrap();
+        app.project_root = tmp.path().to_path_buf();
+        let shift_x = KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT);
+
+        app.handle_key(shift_x);
+        assert_eq!(app.active_flash(), Some("No stale symbols"));
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 0);
+
+        app.ledger.mark_stale_if_changed("src/a.rs::alpha", [1; 32]);
+        app.handle_key(shift_x);
+        let written: Vec<_> = std::fs::read_dir(tmp.path()).unwrap().flatten().collect();
+        assert_eq!(written.len(), 1);
+        assert!(written[0].file_name().to_string_lossy().starts_with("ambits-stale-"));
+        let report = std::fs::read_to_string(written[0].path()).unwrap();
+        assert!(report.contains("- src/a.rs:L2-8 fn alpha"), "{report}");
+    }
+
+    #[test]
+    fn todo_lists_mark_planned_rows_without_covering_them() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::alpha", "alpha"), sym("src/a.rs::beta", "beta")])]);
+        let mut plan = tool_call("TodoWrite", "unused", ReadDepth::Unseen);
+        plan.file_path = None;
+        plan.todos = vec!["Rework `alpha`".into()];
+        app.collapsed.clear();
+        app.process_agent_event(plan.clone());
+
+        let planned: Vec<_> = app.tree_rows.iter().filter(|r| r.is_planned).map(|r| r.symbol_id.as_str()).collect();
+        assert_eq!(planned, ["src/a.rs::alpha"]);
+        assert_eq!(app.ledger.total_seen(), 0);
+
+        plan.todos = Vec::new();
+        app.process_agent_event(plan);
+        assert!(!app.tree_rows.iter().any(|r| r.is_planned));
+    }
+
+    #[test]
+    fn targeted_read_marks_module_symbol_only_for_glue_lines() {
+        use crate::parser::{LanguageParser, ParseOptions};
+        let parser = crate::parser::rust::RustParser::with_options(ParseOptions {
+            module_symbols: true,
+            ..Default::default()
+        });
+        let source = "use std::fmt;\n\nfn a() {\n}\n\nconst X: u32 = 1;\nfn b() {}\n";
+        let parsed = parser.parse_file(Path::new("src/a.rs"), source).unwrap();
+        let mut app = test_app(vec![parsed]);
+        let module_id = "src/a.rs::(module)";
+
+        let mut read = tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody);
+        read.target_lines = Some(3..5);
+        app.process_agent_event(read.clone());
+        assert!(app.ledger.depth_of("src/a.rs::a").is_seen());
+        assert_eq!(app.ledger.depth_of(module_id), ReadDepth::Unseen);
+
+        read.target_lines = Some(1..2);
+        app.process_agent_event(read);
+        assert_eq!(app.ledger.depth_of(module_id), ReadDepth::FullBody);
+        assert_eq!(app.ledger.depth_of("src/a.rs::b"), ReadDepth::Unseen);
+    }
+
+    #[test]
+    fn file_row_flags_modification_after_last_read() {
+        let at = |s: &str| std::time::SystemTime::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let mut a = file("src/a.rs", vec![sym("src/a.rs::f", "f")]);
+        a.modified = Some(at("2025-01-01T00:10:00Z"));
+        let mut b = file("src/b.rs", vec![sym("src/b.rs::g", "g")]);
+        b.modified = Some(at("2024-12-31T23:00:00Z"));
+        let mut app = test_app(vec![a, b, file("src/c.rs", vec![sym("src/c.rs::h", "h")])]);
+
+        // Read at 2025-01-01T00:00:00Z.
+        app.process_agent_event(tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody));
+        app.process_agent_event(tool_call("Read", "/test/project/src/b.rs", ReadDepth::FullBody));
+        let flagged: Vec<&str> = app
+            .tree_rows
+            .iter()
+            .filter(|r| r.modified_since_read)
+            .map(|r| r.symbol_id.as_str())
+            .collect();
+        assert_eq!(flagged, ["src/a.rs"]);
+    }
+
+    #[test]
+    fn watched_sessions_keep_separate_coverage_and_switch() {
+        let mut app = test_app(vec![file("mock/f.rs", vec![sym("mock/f.rs::alpha", "alpha")]), file("mock/g.rs", vec![sym("mock/g.rs::beta", "beta")])]);
+        app.session_id = Some("s1".into());
+        app.watch_sessions(vec!["s1".into(), "s2".into()]);
+        app.ledger.notes.set("mock/f.rs::alpha", "check errors");
+
+        app.process_session_event("s1", tool_call("Read", "/test/project/mock/f.rs", ReadDepth::FullBody));
+        app.pinned.insert("mock/g.rs".into());
+        let mut other = tool_call("Read", "/test/project/mock/g.rs", ReadDepth::FullBody);
+        other.agent_id = "worker".into();
+        app.process_session_event("s2", other);
+        let mut missing = tool_call("Read", "/test/project/mock/gone.rs", ReadDepth::FullBody);
+        missing.agent_id = "worker".into();
+        app.process_session_event("s2", missing);
+        // The parked session's pinned file and missing read stay out of view.
+        assert_eq!(app.active_flash(), None);
+        assert!(app.orphan_reads.is_empty());
+
+        assert_eq!(app.ledger.depth_of("mock/f.rs::alpha"), ReadDepth::FullBody);
+        assert_eq!(app.ledger.depth_of("mock/g.rs::beta"), ReadDepth::Unseen);
+        assert_eq!(app.session_ledger("s2").unwrap().depth_of("mock/g.rs::beta"), ReadDepth::FullBody);
+        assert_eq!(app.activity.len(), 1);
+
+        app.agent_filter = Some("main".into());
+        let press = |app: &mut App, code| app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        app.handle_key(KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT));
+        assert_eq!(app.session_picker, Some(0));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.session_picker, None);
+        assert_eq!(app.session_id.as_deref(), Some("s2"));
+        assert_eq!(app.agent_filter, None);
+        assert_eq!(app.agents_seen, ["worker"]);
+        assert_eq!(app.ledger.depth_of("mock/g.rs::beta"), ReadDepth::FullBody);
+        assert_eq!(app.ledger.depth_of("mock/f.rs::alpha"), ReadDepth::Unseen);
+        assert_eq!(app.ledger.notes.get("mock/f.rs::alpha"), Some("check errors"));
+        assert_eq!(app.session_ledger("s1").unwrap().depth_of("mock/f.rs::alpha"), ReadDepth::FullBody);
+        assert_eq!(app.orphan_reads.keys().collect::<Vec<_>>(), [Path::new("mock/gone.rs")]);
+    }
+
+    #[test]
+    fn ledgers_mut_reaches_parked_sessions() {
+        let mut app = test_app(vec![file("mock/f.rs", vec![sym("mock/f.rs::alpha", "alpha")])]);
+        app.session_id = Some("s1".into());
+        app.watch_sessions(vec!["s1".into(), "s2".into()]);
+        app.process_session_event("s1", tool_call("Read", "/test/project/mock/f.rs", ReadDepth::FullBody));
+        app.process_session_event("s2", tool_call("Read", "/test/project/mock/f.rs", ReadDepth::FullBody));
+
+        let mut changed = sym("mock/f.rs::alpha", "alpha");
+        changed.content_hash = crate::symbols::merkle::content_hash("fn alpha() { 2 }");
+        changed.merkle_hash = changed.content_hash;
+        let new_tree = project(vec![file("mock/f.rs", vec![changed])]);
+        let (old_tree, ledgers) = app.ledgers_mut();
+        assert_eq!(ledgers.map(|l| l.reconcile(old_tree, &new_tree).changed).collect::<Vec<_>>(), [1, 1]);
+        assert_eq!(app.ledger.depth_of("mock/f.rs::alpha"), ReadDepth::Stale);
+        assert_eq!(app.session_ledger("s2").unwrap().depth_of("mock/f.rs::alpha"), ReadDepth::Stale);
+    }
+
+    #[test]
+    fn add_warnings_lists_all_and_flashes_the_count() {
+        let mut app = test_app(vec![file("src/a.rs", vec![])]);
+        app.add_warnings(Vec::new());
+        assert_eq!(app.active_flash(), None);
+
+        app.add_warnings(vec!["first".into(), "second".into()]);
+        assert_eq!(app.warnings, ["first", "second"]);
+        assert_eq!(app.active_flash(), Some("2 warnings, press W to list them"));
+    }
+
+    #[test]
+    fn session_picker_needs_two_sessions() {
+        let mut app = test_app(vec![file("mock/f.rs", vec![])]);
+        app.session_id = Some("s1".into());
+        app.handle_key(KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT));
+        assert_eq!(app.session_picker, None);
+        assert!(app.active_flash().is_some_and(|t| t.contains("--sessions")));
+    }
+
+    #[test]
+    fn agent_picker_selects_filter() {
+        let mut app = test_app(vec![file("src/a.rs", vec![sym("src/a.rs::f", "f")])]);
+        app.agents_seen = vec!["main".into(), "agent-1".into(), "agent-2".into()];
+        let press = |app: &mut App, code| app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT));
+        assert_eq!(app.agent_picker, Some(0));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.agent_picker, None);
+        assert_eq!(app.agent_filter.as_deref(), Some("agent-1"));
+
+        // Reopening starts on the current filter; the cursor stays in bounds.
+        app.handle_key(KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT));
+        assert_eq!(app.agent_picker, Some(2));
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.agent_picker, Some(3));
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.agent_filter, None);
+
+        // Esc closes without changing the filter.
+        app.agent_filter = Some("main".into());
+        app.handle_key(KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.agent_filter.as_deref(), Some("main"));
+        // Picker keys didn't move the tree selection.
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn compact_chains_merges_single_child_containers() {
+        let leaf = sym("src/a.rs::a/b/c/f", "f");
+        let mut c = sym_with_children("src/a.rs::a/b/c", "c", vec![leaf]);
+        (c.label, c.line_range, c.estimated_tokens) = ("impl".into(), 5..9, 40);
+        let b = sym_with_children("src/a.rs::a/b", "b", vec![c]);
+        let mut a = sym_with_children("src/a.rs::a", "a", vec![b]);
+        (a.label, a.line_range, a.estimated_tokens) = ("mod".into(), 1..20, 200);
+        let mut app = test_app(vec![file("src/a.rs", vec![a])]);
+        app.collapsed.clear();
+        app.rebuild_tree_rows();
+        assert_eq!(app.tree_rows.len(), 5);
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE));
+        let names: Vec<_> = app.tree_rows.iter().map(|r| r.display_name.as_str()).collect();
+        assert_eq!(names, vec!["src/a.rs", "a::b::c", "f"]);
+        // Every field of the merged row comes from the innermost node it stands for.
+        let row = &app.tree_rows[1];
+        assert_eq!(row.symbol_id, "src/a.rs::a/b/c");
+        assert_eq!((row.label.as_str(), row.line_range.as_str()), ("impl", "L5-9"));
+        assert_eq!((row.token_count, row.line_count), (40, 5));
+        assert_eq!(app.tree_rows[2].depth, 2);
+    }
+
+    #[test]
+    fn note_file_changed_lists_unseen_symbols() {
+        let mut app = test_app(vec![file(
+            "src/a.rs",
+            vec![
+                sym_with_lines("src/a.rs::read", "read", 1, 5),
+                sym_with_lines("src/a.rs::unread", "unread", 6, 9),
+            ],
+        )]);
+        app.process_agent_event(tool_call_targeted("Read", "/test/project/src/a.rs", ReadDepth::FullBody, "read"));
+
+        app.note_file_changed(Path::new("src/a.rs"));
+        let alert = app.unseen_alert.as_ref().unwrap();
+        assert_eq!(alert.symbols.len(), 1);
+        assert_eq!(alert.symbols[0].name, "unread");
+
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(app.unseen_alert.is_none());
+
+        // No alert once everything has been read.
+        app.process_agent_event(tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody));
+        app.note_file_changed(Path::new("src/a.rs"));
+        assert!(app.unseen_alert.is_none());
+    }
+
+    #[test]
+    fn session_start_tracks_earliest_timestamp() {
+        use chrono::TimeZone;
+
+        let mut app = test_app(vec![]);
+        let mut late = tool_call("Read", "/x.rs", ReadDepth::FullBody);
+        late.timestamp_str = "2025-01-01T12:00:00.000Z".into();
+        let mut early = late.clone();
+        early.timestamp_str = "2025-01-01T10:30:00Z".into();
+        let mut bad = late.clone();
+        bad.timestamp_str = String::new();
+
+        app.process_agent_event(late);
+        app.process_agent_event(early);
+        app.process_agent_event(bad);
+        assert_eq!(app.session_start, Some(Utc.with_ymd_and_hms(2025, 1, 1, 10, 30, 0).unwrap()));
+
+        assert!(app.session_age().is_none());
+        app.now = Some(Utc.with_ymd_and_hms(2025, 1, 1, 13, 0, 0).unwrap().with_timezone(&Local));
+        assert_eq!(app.session_age(), Some(chrono::Duration::minutes(150)));
+    }
+
+    #[test]
+    fn flat_view_ranks_symbols_across_files() {
+        let mut big = sym("src/b.rs::big", "big");
+        big.estimated_tokens = 400;
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::small", "small")]),
+            file("src/b.rs", vec![big]),
+        ]);
+        app.handle_key(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE));
+
+        let names: Vec<_> = app.tree_rows.iter().map(|r| r.display_name.as_str()).collect();
+        assert_eq!(names, vec!["big", "small"]);
+        assert!(app.tree_rows.iter().all(|r| !r.is_file));
+        assert_eq!(app.tree_rows[0].line_range, "src/b.rs:1-10");
+    }
+
+    #[test]
+    fn parallel_reads_in_one_message_all_register() {
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::a", "a")]),
+            file("src/b.rs", vec![sym("src/b.rs::b", "b")]),
+        ]);
+        let line = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Read","input":{"file_path":"/test/project/src/a.rs"}},{"type":"tool_use","name":"Read","input":{"file_path":"/test/project/src/b.rs"}}]}}"#;
+        for event in crate::ingest::claude::parse_jsonl_line(line, "agent-1") {
+            app.process_agent_event(event);
+        }
+        assert_eq!(app.ledger.depth_of("src/a.rs::a"), ReadDepth::FullBody);
+        assert_eq!(app.ledger.depth_of("src/b.rs::b"), ReadDepth::FullBody);
+        assert_eq!(app.activity.len(), 2);
+    }
+
+    #[test]
+    fn pinned_file_sorts_first_and_flashes_when_covered() {
+        let mut app = test_app(vec![
+            file("src/a.rs", vec![sym("src/a.rs::a", "a")]),
+            file("src/b.rs", vec![sym("src/b.rs::b", "b")]),
+        ]);
+        app.selected_index = 1;
+        app.handle_key(KeyEvent::new(KeyCode::Char('P'), KeyModifiers::SHIFT));
+        assert!(app.pinned.contains("src/b.rs"));
+        assert_eq!(app.tree_rows[0].display_name, "src/b.rs");
+        assert!(app.tree_rows[0].is_pinned);
+        assert_eq!(app.selected_index, 0);
+
+        app.process_agent_event(tool_call("Read", "/test/project/src/a.rs", ReadDepth::FullBody));
+        assert!(app.active_flash().is_none());
+        app.process_agent_event(tool_call("Read", "/test/project/src/b.rs", ReadDepth::FullBody));
+        assert_eq!(app.active_flash(), Some("Pinned file src/b.rs is fully covered"));
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('P'), KeyModifiers::SHIFT));
+        assert!(app.pinned.is_empty());
+    }
 }
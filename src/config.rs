@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::Deserialize;
+
+use crate::rules::Rules;
+
+/// Name of the per-project config file, looked up in the project root.
+pub const CONFIG_FILE: &str = ".ambits.toml";
+
+/// User settings loaded from `.ambits.toml`. Every section is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Key binding overrides: action name → one key spec or a list of them.
+    #[serde(default)]
+    pub keys: HashMap<String, KeyBinding>,
+    /// Coverage policy checked by `--ci`.
+    #[serde(default)]
+    pub rules: Rules,
+    /// TUI behavior.
+    #[serde(default)]
+    pub tui: TuiConfig,
+    /// Which files a tree-sitter scan keeps.
+    #[serde(default)]
+    pub scan: ScanConfig,
+}
+
+/// The `[tui]` section.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TuiConfig {
+    /// Ask before `q` quits (`Q` always quits straight away).
+    pub confirm_quit: bool,
+    /// Files with more symbols than this stay collapsed until expanded by hand
+    /// (0 for no limit).
+    pub large_file_symbols: usize,
+}
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        Self { confirm_quit: true, large_file_symbols: 2000 }
+    }
+}
+
+/// The `[scan]` section.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ScanConfig {
+    /// Markers that flag a file as generated when found in its first few lines;
+    /// such files are left out of the tree. An empty list keeps everything.
+    pub generated_markers: Vec<String>,
+    /// Carry coverage over to a symbol whose body matches one that just
+    /// disappeared from the same file, so pure renames keep what was read.
+    pub follow_renames: bool,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            generated_markers: ["@generated", "DO NOT EDIT", "<auto-generated"]
+                .map(String::from)
+                .to_vec(),
+            follow_renames: false,
+        }
+    }
+}
+
+/// A single key spec (`"ctrl-d"`) or several (`["j", "down"]`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    pub fn specs(&self) -> Vec<&str> {
+        match self {
+            KeyBinding::One(s) => vec![s.as_str()],
+            KeyBinding::Many(v) => v.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+impl Config {
+    /// Load `<project_root>/.ambits.toml`, or the defaults if there is none.
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = project_root.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&text).wrap_err_with(|| format!("Invalid config in {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys_section() {
+        let config = Config::parse(
+            r#"
+            [keys]
+            quit = "x"
+            down = ["j", "ctrl-n"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.keys["quit"].specs(), vec!["x"]);
+        assert_eq!(config.keys["down"].specs(), vec!["j", "ctrl-n"]);
+    }
+
+    #[test]
+    fn tui_section_defaults_to_confirming_quit() {
+        assert!(Config::parse("").unwrap().tui.confirm_quit);
+        let config = Config::parse("[tui]\nconfirm_quit = false\n").unwrap();
+        assert!(!config.tui.confirm_quit);
+        assert!(Config::parse("[tui]\nconfirm = false\n").is_err());
+        assert_eq!(config.tui.large_file_symbols, 2000);
+        assert_eq!(Config::parse("[tui]\nlarge_file_symbols = 0\n").unwrap().tui.large_file_symbols, 0);
+    }
+
+    #[test]
+    fn scan_section_replaces_generated_markers() {
+        assert!(Config::parse("").unwrap().scan.generated_markers.contains(&"@generated".to_string()));
+        let config = Config::parse("[scan]\ngenerated_markers = []\n").unwrap();
+        assert!(config.scan.generated_markers.is_empty());
+    }
+
+    #[test]
+    fn missing_file_uses_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert!(config.keys.is_empty());
+    }
+
+    #[test]
+    fn unknown_section_is_rejected() {
+        assert!(Config::parse("[colours]\nfoo = 1\n").is_err());
+    }
+}
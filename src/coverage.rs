@@ -3,11 +3,11 @@
 //! This module provides structures and formatters for generating coverage reports
 //! that show how much of a project's symbols have been seen by an LLM agent.
 
-use crate::symbols::{ProjectTree, SymbolNode};
+use crate::symbols::{visit_symbols, walk_symbols, ProjectTree, SymbolNode};
 use crate::tracking::{ContextLedger, ReadDepth};
 
-/// Per-file coverage metrics.
-#[derive(Debug, Clone)]
+/// Per-file coverage metrics; serializes to the lines of `--dump-coverage-jsonl`.
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct FileCoverage {
     /// Full relative path to the file.
     pub path: String,
@@ -17,6 +17,8 @@ pub struct FileCoverage {
     pub seen_count: usize,
     /// Symbols with depth == FullBody.
     pub full_count: usize,
+    /// Estimated tokens across all of the file's symbols.
+    pub total_tokens: usize,
 }
 
 impl FileCoverage {
@@ -39,6 +41,116 @@ impl FileCoverage {
     }
 }
 
+/// One symbol's coverage gap, for the flat "what hasn't been read" ranking.
+#[derive(Debug, Clone)]
+pub struct SymbolGap {
+    pub symbol_id: String,
+    pub name: String,
+    pub label: String,
+    /// Relative path of the file containing the symbol.
+    pub path: String,
+    pub line_range: std::ops::Range<usize>,
+    pub depth: ReadDepth,
+    pub tokens: usize,
+    /// Tokens not covered by children × how unread the symbol is. Higher is worse.
+    pub score: f64,
+    /// Review note left on the symbol, if any.
+    pub note: Option<String>,
+}
+
+/// How much of a symbol is still unknown at a given read depth, from 1.0 (unseen) to 0.0 (fully read).
+pub fn coverage_gap(depth: ReadDepth) -> f64 {
+    match depth {
+        ReadDepth::Unseen => 1.0,
+        ReadDepth::NameOnly => 0.9,
+        ReadDepth::Stale => 0.8,
+        ReadDepth::Overview => 0.7,
+        ReadDepth::Signature => 0.4,
+        ReadDepth::FullBody => 0.0,
+    }
+}
+
+/// Every symbol in the project ranked by coverage gap, worst first.
+/// With `agent`, only that agent's reads count.
+pub fn ranked_symbol_gaps(
+    project_tree: &ProjectTree,
+    ledger: &ContextLedger,
+    agent: Option<&str>,
+) -> Vec<SymbolGap> {
+    fn walk(
+        sym: &SymbolNode,
+        path: &str,
+        ledger: &ContextLedger,
+        agent: Option<&str>,
+        out: &mut Vec<SymbolGap>,
+    ) {
+        let depth = match agent {
+            Some(agent_id) => ledger.depth_for_agent(&sym.id, agent_id),
+            None => ledger.depth_of(&sym.id),
+        };
+        // Containers span their children; score only the part that is theirs.
+        let child_tokens: usize = sym.children.iter().map(|c| c.estimated_tokens).sum();
+        let own_tokens = sym.estimated_tokens.saturating_sub(child_tokens);
+        out.push(SymbolGap {
+            symbol_id: sym.id.clone(),
+            name: sym.name.clone(),
+            label: sym.label.clone(),
+            path: path.to_string(),
+            line_range: sym.line_range.clone(),
+            depth,
+            tokens: sym.estimated_tokens,
+            score: own_tokens as f64 * coverage_gap(depth),
+            note: ledger.notes.get(&sym.id).map(str::to_string),
+        });
+        for child in &sym.children {
+            walk(child, path, ledger, agent, out);
+        }
+    }
+
+    let mut gaps = Vec::new();
+    for file in &project_tree.files {
+        let path = file.file_path.to_string_lossy();
+        for sym in &file.symbols {
+            walk(sym, &path, ledger, agent, &mut gaps);
+        }
+    }
+    gaps.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.symbol_id.cmp(&b.symbol_id))
+    });
+    gaps
+}
+
+/// Every symbol at `ReadDepth::Stale`, in file order, as an instruction an
+/// agent can be handed to re-read them. `None` when nothing is stale.
+pub fn stale_report(project_tree: &ProjectTree, ledger: &ContextLedger) -> Option<String> {
+    let mut lines = Vec::new();
+    for file in &project_tree.files {
+        visit_symbols(&file.symbols, |sym| {
+            if ledger.depth_of(&sym.id) == ReadDepth::Stale {
+                lines.push(format!(
+                    "- {}:L{}-{} {} {}\n",
+                    file.file_path.display(),
+                    sym.line_range.start,
+                    sym.line_range.end,
+                    sym.label,
+                    sym.name,
+                ));
+            }
+        });
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    let mut out = String::from(
+        "These symbols changed after you last read them. Re-read them before relying on or editing them:\n",
+    );
+    out.extend(lines);
+    Some(out)
+}
+
 /// Complete coverage report for a project.
 #[derive(Debug, Clone)]
 pub struct CoverageReport {
@@ -46,22 +158,30 @@ pub struct CoverageReport {
     pub session_id: Option<String>,
     /// Per-file coverage metrics.
     pub files: Vec<FileCoverage>,
+    /// Every symbol ranked by coverage gap, worst first.
+    pub symbols: Vec<SymbolGap>,
 }
 
 impl CoverageReport {
     /// Build a coverage report from a project tree and context ledger.
     pub fn from_project(project_tree: &ProjectTree, ledger: &ContextLedger) -> Self {
+        Self::from_project_with(project_tree, ledger, CountMode::ALL)
+    }
+
+    /// Like `from_project`, choosing which symbols count toward the totals.
+    pub fn from_project_with(project_tree: &ProjectTree, ledger: &ContextLedger, mode: CountMode) -> Self {
         let mut files: Vec<FileCoverage> = project_tree
             .files
             .iter()
             .map(|file| {
                 let path = file.file_path.to_string_lossy().to_string();
-                let (total, seen, full) = count_symbols(&file.symbols, ledger);
+                let (total, seen, full) = count_symbols_for_agent(&file.symbols, ledger, None, mode);
                 FileCoverage {
                     path,
                     total_symbols: total,
                     seen_count: seen,
                     full_count: full,
+                    total_tokens: file.symbols.iter().map(|s| s.total_tokens()).sum(),
                 }
             })
             .collect();
@@ -76,6 +196,7 @@ impl CoverageReport {
         Self {
             session_id: None,
             files,
+            symbols: ranked_symbol_gaps(project_tree, ledger, None),
         }
     }
 
@@ -115,15 +236,89 @@ impl CoverageReport {
     }
 }
 
+/// `--idle-snapshot <minutes>=<dir>`: write a report to `dir` once the agent
+/// has been idle for `idle_after`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdleSnapshot {
+    pub idle_after: std::time::Duration,
+    pub dir: std::path::PathBuf,
+}
+
+impl std::str::FromStr for IdleSnapshot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (minutes, dir) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <minutes>=<path>, got `{s}`"))?;
+        let minutes: f64 = minutes
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid minutes `{minutes}`"))?;
+        if !minutes.is_finite() || minutes <= 0.0 {
+            return Err(format!("minutes must be positive, got `{minutes}`"));
+        }
+        if dir.is_empty() {
+            return Err("missing snapshot directory after `=`".into());
+        }
+        Ok(Self {
+            idle_after: std::time::Duration::from_secs_f64(minutes * 60.0),
+            dir: dir.into(),
+        })
+    }
+}
+
+impl IdleSnapshot {
+    /// Report path for a snapshot taken at `at`, e.g. `dir/coverage-20260102-030405.txt`.
+    pub fn path_for(&self, at: chrono::DateTime<chrono::Local>) -> std::path::PathBuf {
+        self.dir.join(format!("coverage-{}.txt", at.format("%Y%m%d-%H%M%S")))
+    }
+}
+
+/// Which symbols count toward coverage totals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CountMode {
+    /// Only symbols without children, so impls/modules/classes don't pad the denominator.
+    pub leaves_only: bool,
+    /// Leave out test-only symbols (and everything inside them), so reading
+    /// tests doesn't count as covering production code.
+    pub exclude_tests: bool,
+}
+
+impl CountMode {
+    /// Every symbol, containers and tests included.
+    pub const ALL: CountMode = CountMode { leaves_only: false, exclude_tests: false };
+}
+
 /// Count symbols recursively, returning (total, seen_count, full_count).
 pub fn count_symbols(symbols: &[SymbolNode], ledger: &ContextLedger) -> (usize, usize, usize) {
+    count_symbols_for_agent(symbols, ledger, None, CountMode::ALL)
+}
+
+/// Like `count_symbols`, but only counts coverage earned by `agent` when one is
+/// given. `mode` can skip container symbols and test-only subtrees.
+pub fn count_symbols_for_agent<'a>(
+    symbols: &'a [SymbolNode],
+    ledger: &ContextLedger,
+    agent: Option<&str>,
+    mode: CountMode,
+) -> (usize, usize, usize) {
     let mut total = 0;
     let mut seen = 0;
     let mut full = 0;
 
-    for sym in symbols {
+    walk_symbols(symbols, 0, &mut |sym: &'a SymbolNode, _depth| -> &'a [SymbolNode] {
+        if mode.exclude_tests && sym.test_only {
+            return &[];
+        }
+        if mode.leaves_only && !sym.children.is_empty() {
+            return &sym.children;
+        }
         total += 1;
-        let depth = ledger.depth_of(&sym.id);
+        let depth = match agent {
+            Some(agent_id) => ledger.depth_for_agent(&sym.id, agent_id),
+            None => ledger.depth_of(&sym.id),
+        };
 
         if depth.is_seen() {
             seen += 1;
@@ -131,23 +326,69 @@ pub fn count_symbols(symbols: &[SymbolNode], ledger: &ContextLedger) -> (usize,
         if depth == ReadDepth::FullBody {
             full += 1;
         }
-
-        // Recurse into children
-        let (child_total, child_seen, child_full) = count_symbols(&sym.children, ledger);
-        total += child_total;
-        seen += child_seen;
-        full += child_full;
-    }
+        &sym.children
+    });
 
     (total, seen, full)
 }
 
+/// Estimated tokens of code in symbols no agent has seen. A container counts
+/// only the tokens outside its children, so nested code isn't counted twice.
+pub fn unseen_tokens(symbols: &[SymbolNode], ledger: &ContextLedger) -> usize {
+    let mut tokens = 0;
+    visit_symbols(symbols, |sym| {
+        if !ledger.depth_of(&sym.id).is_seen() {
+            let nested: usize = sym.children.iter().map(|c| c.estimated_tokens).sum();
+            tokens += sym.estimated_tokens.saturating_sub(nested);
+        }
+    });
+    tokens
+}
+
 /// Trait for formatting coverage reports.
 /// Implement this trait to add new output formats (JSON, CSV, etc.).
 pub trait CoverageFormatter {
     fn format(&self, report: &CoverageReport) -> String;
 }
 
+/// Flat list of every symbol, biggest coverage gap first.
+#[derive(Debug, Clone, Default)]
+pub struct FlatFormatter {
+    /// Only print the first N symbols.
+    pub limit: Option<usize>,
+}
+
+impl CoverageFormatter for FlatFormatter {
+    fn format(&self, report: &CoverageReport) -> String {
+        let mut output = String::new();
+        let session_str = report.session_id.as_deref().unwrap_or("none");
+        output.push_str(&format!("Coverage Gaps (session: {})\n", session_str));
+        output.push_str(&format!(
+            "{:>8}  {:<9}  {:>6}  {}\n",
+            "Score", "Depth", "Tokens", "Symbol"
+        ));
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+        for gap in report.symbols.iter().take(limit) {
+            output.push_str(&format!(
+                "{:>8.0}  {:<9}  {:>6}  {} {}  {}:L{}-{}\n",
+                gap.score,
+                gap.depth.to_string(),
+                gap.tokens,
+                gap.label,
+                gap.name,
+                gap.path,
+                gap.line_range.start,
+                gap.line_range.end,
+            ));
+            if let Some(note) = &gap.note {
+                output.push_str(&format!("{:>29}note: {}\n", "", note));
+            }
+        }
+        output
+    }
+}
+
 /// Text table formatter for terminal output.
 #[derive(Debug, Clone)]
 pub struct TextFormatter {
@@ -168,8 +409,7 @@ impl CoverageFormatter for TextFormatter {
         // Header
         let session_str = report
             .session_id
-            .as_ref()
-            .map(|s| s.as_str())
+            .as_deref()
             .unwrap_or("none");
         output.push_str(&format!("Coverage Report (session: {})\n", session_str));
 
@@ -231,13 +471,355 @@ impl CoverageFormatter for TextFormatter {
             width = max_path_len
         ));
 
+        let mut notes: Vec<&SymbolGap> = report.symbols.iter().filter(|g| g.note.is_some()).collect();
+        if !notes.is_empty() {
+            notes.sort_by(|a, b| (&a.path, a.line_range.start).cmp(&(&b.path, b.line_range.start)));
+            output.push_str("\nNotes\n");
+            for gap in notes {
+                output.push_str(&format!(
+                    "  {}:L{} {} {}: {}\n",
+                    gap.path,
+                    gap.line_range.start,
+                    gap.label,
+                    gap.name,
+                    gap.note.as_deref().unwrap_or_default(),
+                ));
+            }
+        }
+
+        output
+    }
+}
+
+/// LCOV tracefile formatter, for tools that already ingest line coverage.
+/// Every line of a symbol gets its read state: hit once seen at any depth,
+/// not hit while unseen or stale. Where symbols nest, the innermost decides.
+#[derive(Debug, Clone, Default)]
+pub struct LcovFormatter;
+
+impl CoverageFormatter for LcovFormatter {
+    fn format(&self, report: &CoverageReport) -> String {
+        let mut by_path: std::collections::BTreeMap<&str, Vec<&SymbolGap>> = std::collections::BTreeMap::new();
+        for gap in &report.symbols {
+            by_path.entry(&gap.path).or_default().push(gap);
+        }
+
+        let mut output = format!("TN:{}\n", report.session_id.as_deref().unwrap_or(""));
+        for (path, mut gaps) in by_path {
+            // Widest first, so nested symbols overwrite the lines of their parents.
+            gaps.sort_by_key(|g| std::cmp::Reverse(g.line_range.end.saturating_sub(g.line_range.start)));
+            let mut lines = std::collections::BTreeMap::new();
+            for gap in gaps {
+                let hit = gap.depth.is_seen() && gap.depth != ReadDepth::Stale;
+                for line in gap.line_range.start..=gap.line_range.end {
+                    lines.insert(line, hit);
+                }
+            }
+
+            output.push_str(&format!("SF:{path}\n"));
+            for (line, hit) in &lines {
+                output.push_str(&format!("DA:{line},{}\n", u8::from(*hit)));
+            }
+            output.push_str(&format!("LF:{}\n", lines.len()));
+            output.push_str(&format!("LH:{}\n", lines.values().filter(|hit| **hit).count()));
+            output.push_str("end_of_record\n");
+        }
+        output
+    }
+}
+
+/// JSON formatter: the session, one object per file and the project totals,
+/// with snake_case keys in a fixed order.
+#[derive(Debug, Clone, Default)]
+pub struct JsonFormatter {
+    /// Time to put in `generated_at`; None uses the time of formatting.
+    pub generated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonReport<'a> {
+    generated_at: String,
+    session_id: Option<&'a str>,
+    files: Vec<JsonCounts<'a>>,
+    totals: JsonCounts<'a>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonCounts<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+    total_symbols: usize,
+    seen_count: usize,
+    full_count: usize,
+    seen_percent: f64,
+    full_percent: f64,
+}
+
+impl CoverageFormatter for JsonFormatter {
+    fn format(&self, report: &CoverageReport) -> String {
+        let generated_at = self.generated_at.unwrap_or_else(chrono::Utc::now);
+        let json = JsonReport {
+            generated_at: generated_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            session_id: report.session_id.as_deref(),
+            files: report
+                .files
+                .iter()
+                .map(|f| JsonCounts {
+                    path: Some(&f.path),
+                    total_symbols: f.total_symbols,
+                    seen_count: f.seen_count,
+                    full_count: f.full_count,
+                    seen_percent: f.seen_percent(),
+                    full_percent: f.full_percent(),
+                })
+                .collect(),
+            totals: JsonCounts {
+                path: None,
+                total_symbols: report.total_symbols(),
+                seen_count: report.total_seen(),
+                full_count: report.total_full(),
+                seen_percent: report.total_seen_percent(),
+                full_percent: report.total_full_percent(),
+            },
+        };
+        let mut output = serde_json::to_string_pretty(&json).unwrap_or_default();
+        output.push('\n');
+        output
+    }
+}
+
+/// CSV formatter: a header, one row per file, then a `TOTAL` row.
+/// Percentages have one decimal place.
+#[derive(Debug, Clone, Default)]
+pub struct CsvFormatter;
+
+impl CoverageFormatter for CsvFormatter {
+    fn format(&self, report: &CoverageReport) -> String {
+        let mut output = String::from("path,total_symbols,seen_count,full_count,seen_percent,full_percent\n");
+        for file in &report.files {
+            output.push_str(&format!(
+                "{},{},{},{},{:.1},{:.1}\n",
+                csv_field(&file.path),
+                file.total_symbols,
+                file.seen_count,
+                file.full_count,
+                file.seen_percent(),
+                file.full_percent(),
+            ));
+        }
+        output.push_str(&format!(
+            "TOTAL,{},{},{},{:.1},{:.1}\n",
+            report.total_symbols(),
+            report.total_seen(),
+            report.total_full(),
+            report.total_seen_percent(),
+            report.total_full_percent(),
+        ));
+        output
+    }
+}
+
+/// Quote a CSV field when it holds a comma, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// GitHub-flavored Markdown formatter: a summary line, then a table with one
+/// row per file and a bold `TOTAL` row, for pasting into PR descriptions.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownFormatter;
+
+impl CoverageFormatter for MarkdownFormatter {
+    fn format(&self, report: &CoverageReport) -> String {
+        let mut output = match report.session_id {
+            Some(ref sid) => format!("Coverage for session `{sid}`: {:.1}% full\n\n", report.total_full_percent()),
+            None => format!("Coverage: {:.1}% full\n\n", report.total_full_percent()),
+        };
+        output.push_str("| File | Symbols | Seen | Full | Seen% | Full% |\n");
+        output.push_str("|---|---:|---:|---:|---:|---:|\n");
+        for file in &report.files {
+            output.push_str(&format!(
+                "| `{}` | {} | {} | {} | {:.1}% | {:.1}% |\n",
+                file.path.replace('|', "\\|"),
+                file.total_symbols,
+                file.seen_count,
+                file.full_count,
+                file.seen_percent(),
+                file.full_percent(),
+            ));
+        }
+        output.push_str(&format!(
+            "| **TOTAL** | {} | {} | {} | {:.1}% | {:.1}% |\n",
+            report.total_symbols(),
+            report.total_seen(),
+            report.total_full(),
+            report.total_seen_percent(),
+            report.total_full_percent(),
+        ));
+        output
+    }
+}
+
+/// SVG treemap formatter: one rectangle per file, sized by estimated tokens
+/// and colored by full-body coverage.
+#[derive(Debug, Clone)]
+pub struct SvgTreemapFormatter {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for SvgTreemapFormatter {
+    fn default() -> Self {
+        Self { width: 1200.0, height: 800.0 }
+    }
+}
+
+impl CoverageFormatter for SvgTreemapFormatter {
+    fn format(&self, report: &CoverageReport) -> String {
+        let mut files: Vec<&FileCoverage> = report.files.iter().filter(|f| f.total_tokens > 0).collect();
+        files.sort_by(|a, b| b.total_tokens.cmp(&a.total_tokens).then_with(|| a.path.cmp(&b.path)));
+
+        let weights: Vec<f64> = files.iter().map(|f| f.total_tokens as f64).collect();
+        let bounds = TreemapRect { x: 0.0, y: 0.0, w: self.width, h: self.height };
+        let rects = squarify(&weights, bounds);
+
+        let mut output = String::new();
+        output.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"monospace\" font-size=\"11\">\n",
+            w = self.width,
+            h = self.height
+        ));
+        output.push_str(&format!(
+            "<rect width=\"{}\" height=\"{}\" fill=\"#1e1e1e\"/>\n",
+            self.width, self.height
+        ));
+
+        for (file, rect) in files.iter().zip(&rects) {
+            let pct = file.full_percent();
+            let path = xml_escape(&file.path);
+            output.push_str(&format!(
+                "<g><title>{} - {:.0}% full, {:.0}% seen, ~{} tokens</title>\
+                 <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" stroke=\"#1e1e1e\" stroke-width=\"1\"/>",
+                path,
+                pct,
+                file.seen_percent(),
+                file.total_tokens,
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h,
+                coverage_hex(pct),
+            ));
+            // Only label rectangles with room for a line of text.
+            if rect.w > 60.0 && rect.h > 16.0 {
+                output.push_str(&format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"#111111\">{} {:.0}%</text>",
+                    rect.x + 4.0,
+                    rect.y + 13.0,
+                    path,
+                    pct,
+                ));
+            }
+            output.push_str("</g>\n");
+        }
+
+        output.push_str("</svg>\n");
         output
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TreemapRect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Squarified treemap layout (Bruls et al.).
+/// `weights` must be sorted descending; returns one rectangle per weight.
+fn squarify(weights: &[f64], bounds: TreemapRect) -> Vec<TreemapRect> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    let scale = bounds.w * bounds.h / total;
+    let areas: Vec<f64> = weights.iter().map(|w| w * scale).collect();
+
+    let mut out = Vec::with_capacity(areas.len());
+    let mut rect = bounds;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = rect.w.min(rect.h);
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side)
+        {
+            end += 1;
+        }
+
+        let row = &areas[start..end];
+        let row_sum: f64 = row.iter().sum();
+        if rect.w >= rect.h {
+            // Lay the row out as a column along the left edge.
+            let col_w = if rect.h > 0.0 { row_sum / rect.h } else { 0.0 };
+            let mut y = rect.y;
+            for area in row {
+                let h = if col_w > 0.0 { area / col_w } else { 0.0 };
+                out.push(TreemapRect { x: rect.x, y, w: col_w, h });
+                y += h;
+            }
+            rect = TreemapRect { x: rect.x + col_w, w: rect.w - col_w, ..rect };
+        } else {
+            // Lay the row out along the top edge.
+            let row_h = if rect.w > 0.0 { row_sum / rect.w } else { 0.0 };
+            let mut x = rect.x;
+            for area in row {
+                let w = if row_h > 0.0 { area / row_h } else { 0.0 };
+                out.push(TreemapRect { x, y: rect.y, w, h: row_h });
+                x += w;
+            }
+            rect = TreemapRect { y: rect.y + row_h, h: rect.h - row_h, ..rect };
+        }
+        start = end;
+    }
+    out
+}
+
+/// Worst aspect ratio of a row of areas laid along a side of length `side`.
+fn worst_ratio(row: &[f64], side: f64) -> f64 {
+    let sum: f64 = row.iter().sum();
+    let max = row.iter().cloned().fold(f64::MIN, f64::max);
+    let min = row.iter().cloned().fold(f64::MAX, f64::min);
+    let side_sq = side * side;
+    let sum_sq = sum * sum;
+    (side_sq * max / sum_sq).max(sum_sq / (side_sq * min))
+}
+
+/// Same thresholds as the TUI's coverage percentage gradient.
+fn coverage_hex(pct: f64) -> &'static str {
+    match pct.round() as u32 {
+        0..=20 => "#b43c3c",
+        21..=50 => "#e6a03c",
+        51..=80 => "#c8c850",
+        _ => "#50dc78",
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 #[cfg(test)]
 #[path = "../tests/helpers/mod.rs"]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::duplicate_mod)]
 mod helpers;
 
 #[cfg(test)]
@@ -248,19 +830,19 @@ mod tests {
 
     #[test]
     fn seen_percent_basic() {
-        let fc = FileCoverage { path: "a.rs".into(), total_symbols: 10, seen_count: 3, full_count: 1 };
+        let fc = FileCoverage { path: "a.rs".into(), total_symbols: 10, seen_count: 3, full_count: 1, total_tokens: 0 };
         assert!((fc.seen_percent() - 30.0).abs() < 0.01);
     }
 
     #[test]
     fn seen_percent_zero_total() {
-        let fc = FileCoverage { path: "a.rs".into(), total_symbols: 0, seen_count: 0, full_count: 0 };
+        let fc = FileCoverage { path: "a.rs".into(), total_symbols: 0, seen_count: 0, full_count: 0, total_tokens: 0 };
         assert!((fc.seen_percent()).abs() < 0.01);
     }
 
     #[test]
     fn full_percent_basic() {
-        let fc = FileCoverage { path: "a.rs".into(), total_symbols: 4, seen_count: 2, full_count: 2 };
+        let fc = FileCoverage { path: "a.rs".into(), total_symbols: 4, seen_count: 2, full_count: 2, total_tokens: 0 };
         assert!((fc.full_percent() - 50.0).abs() < 0.01);
     }
 
@@ -287,6 +869,47 @@ mod tests {
         assert_eq!(full, 1);
     }
 
+    #[test]
+    fn exclude_tests_skips_test_only_subtrees() {
+        let mut ledger = ContextLedger::new();
+        let mut tests = sym_with_children("tests", "tests", vec![sym("tests/t", "t")]);
+        tests.test_only = true;
+        let symbols = vec![sym("prod", "prod"), tests];
+        ledger.record("tests/t".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+
+        assert_eq!(count_symbols_for_agent(&symbols, &ledger, None, CountMode::ALL), (3, 1, 1));
+        let mode = CountMode { exclude_tests: true, ..CountMode::ALL };
+        assert_eq!(count_symbols_for_agent(&symbols, &ledger, None, mode), (1, 0, 0));
+    }
+
+    #[test]
+    fn unseen_tokens_count_nested_code_once() {
+        let mut ledger = ContextLedger::new();
+        let mut parent = sym_with_children("p", "parent", vec![sym("c1", "child1"), sym("c2", "child2")]);
+        parent.estimated_tokens = 100;
+        assert_eq!(unseen_tokens(std::slice::from_ref(&parent), &ledger), 100);
+
+        ledger.record("c1".into(), ReadDepth::Signature, [0; 32], "ag".into(), 10);
+        assert_eq!(unseen_tokens(std::slice::from_ref(&parent), &ledger), 70);
+
+        // Seeing the parent leaves only the unread child.
+        ledger.record("p".into(), ReadDepth::NameOnly, [0; 32], "ag".into(), 10);
+        assert_eq!(unseen_tokens(&[parent], &ledger), 30);
+    }
+
+    #[test]
+    fn count_symbols_leaves_only() {
+        let mut ledger = ContextLedger::new();
+        let parent = sym_with_children("p", "Parent", vec![sym("c1", "a"), sym("c2", "b")]);
+        ledger.record("c1".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record("c2".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+
+        // The unread impl/class node no longer drags the numbers down.
+        let counts = count_symbols_for_agent(std::slice::from_ref(&parent), &ledger, None, CountMode { leaves_only: true, ..CountMode::ALL });
+        assert_eq!(counts, (2, 2, 2));
+        assert_eq!(count_symbols(&[parent], &ledger), (3, 2, 2));
+    }
+
     #[test]
     fn from_project_sorts_by_full_percent() {
         let mut ledger = ContextLedger::new();
@@ -319,10 +942,108 @@ mod tests {
         assert_eq!(report.total_full(), 1);
     }
 
+    #[test]
+    fn ranked_gaps_put_big_unread_symbols_first() {
+        let mut ledger = ContextLedger::new();
+        let mut big = sym("a.rs::big", "big");
+        big.estimated_tokens = 500;
+        let mut read = sym("a.rs::read", "read");
+        read.estimated_tokens = 900;
+        let small = sym("b.rs::small", "small");
+        let tree = project(vec![file("a.rs", vec![big, read]), file("b.rs", vec![small])]);
+        ledger.record("a.rs::read".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 900);
+
+        let gaps = ranked_symbol_gaps(&tree, &ledger, None);
+        let order: Vec<_> = gaps.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(order, vec!["big", "small", "read"]);
+        assert_eq!(gaps[0].path, "a.rs");
+        assert_eq!(gaps[2].score, 0.0);
+
+        // Per-agent ranking ignores other agents' reads.
+        let gaps = ranked_symbol_gaps(&tree, &ledger, Some("other"));
+        assert_eq!(gaps[0].name, "read");
+    }
+
+    #[test]
+    fn container_scores_own_tokens_only() {
+        let mut child = sym("a.rs::T/f", "f");
+        child.estimated_tokens = 90;
+        let mut parent = sym_with_children("a.rs::T", "T", vec![child]);
+        parent.estimated_tokens = 100;
+        let tree = project(vec![file("a.rs", vec![parent])]);
+        let gaps = ranked_symbol_gaps(&tree, &ContextLedger::new(), None);
+        assert_eq!(gaps[0].name, "f");
+        assert_eq!(gaps[1].score, 10.0);
+    }
+
+    #[test]
+    fn flat_formatter_output() {
+        let tree = project(vec![file("src/a.rs", vec![sym_with_lines("src/a.rs::foo", "foo", 3, 9)])]);
+        let report = CoverageReport::from_project(&tree, &ContextLedger::new());
+        let output = FlatFormatter { limit: Some(5) }.format(&report);
+        assert!(output.contains("Coverage Gaps (session: none)"));
+        assert!(output.contains("unseen"));
+        assert!(output.contains("fn foo  src/a.rs:L3-9"));
+    }
+
+    #[test]
+    fn stale_report_lists_symbols_of_files_changed_since_read() {
+        let mut tree = project(vec![
+            file("src/a.rs", vec![sym_with_children("src/a.rs::T", "T", vec![sym_with_lines("src/a.rs::T/f", "f", 4, 6)])]),
+            file("src/b.rs", vec![sym("src/b.rs::g", "g")]),
+        ]);
+        let mut ledger = ContextLedger::new();
+        assert_eq!(stale_report(&tree, &ledger), None);
+
+        let read_at = chrono::DateTime::parse_from_rfc3339("2026-01-01T10:00:00Z").unwrap().to_utc();
+        for (path, id) in [("src/a.rs", "src/a.rs::T/f"), ("src/b.rs", "src/b.rs::g")] {
+            ledger.record(id.into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+            ledger.record_file_read(std::path::Path::new(path), read_at);
+        }
+        tree.files[0].modified = Some((read_at + chrono::Duration::minutes(5)).into());
+        tree.files[1].modified = Some(read_at.into());
+        ledger.mark_modified_files_stale(&tree);
+
+        // The unread container and the untouched file stay out of it.
+        assert_eq!(ledger.depth_of("src/a.rs::T"), ReadDepth::Unseen);
+        assert_eq!(ledger.depth_of("src/b.rs::g"), ReadDepth::FullBody);
+        let report = stale_report(&tree, &ledger).unwrap();
+        assert!(report.starts_with("These symbols changed"), "{report}");
+        assert!(report.ends_with("\n- src/a.rs:L4-6 fn f\n"), "{report}");
+    }
+
+    #[test]
+    fn notes_appear_in_reports() {
+        let tree = project(vec![file("src/a.rs", vec![sym_with_lines("src/a.rs::foo", "foo", 3, 9)])]);
+        let mut ledger = ContextLedger::new();
+        ledger.notes.set("src/a.rs::foo", "error path unread");
+        let report = CoverageReport::from_project(&tree, &ledger);
+        assert_eq!(report.symbols[0].note.as_deref(), Some("error path unread"));
+
+        let text = TextFormatter::default().format(&report);
+        assert!(text.contains("Notes\n  src/a.rs:L3 fn foo: error path unread"));
+        let flat = FlatFormatter::default().format(&report);
+        assert!(flat.contains("note: error path unread"));
+    }
+
+    #[test]
+    fn idle_snapshot_parses_minutes_and_dir() {
+        use chrono::TimeZone;
+
+        let snap: IdleSnapshot = "1.5=out/reports".parse().unwrap();
+        assert_eq!(snap.idle_after, std::time::Duration::from_secs(90));
+        let at = chrono::Local.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+        assert_eq!(snap.path_for(at), std::path::Path::new("out/reports/coverage-20260102-030405.txt"));
+
+        for bad in ["10", "x=out", "0=out", "-2=out", "5="] {
+            assert!(bad.parse::<IdleSnapshot>().is_err(), "{bad}");
+        }
+    }
+
     #[test]
     fn text_formatter_output() {
-        let report = CoverageReport { session_id: Some("abc-123".into()), files: vec![
-            FileCoverage { path: "src/main.rs".into(), total_symbols: 10, seen_count: 8, full_count: 5 },
+        let report = CoverageReport { session_id: Some("abc-123".into()), symbols: vec![], files: vec![
+            FileCoverage { path: "src/main.rs".into(), total_symbols: 10, seen_count: 8, full_count: 5, total_tokens: 0 },
         ]};
         let formatter = TextFormatter::default();
         let output = formatter.format(&report);
@@ -330,4 +1051,140 @@ mod tests {
         assert!(output.contains("src/main.rs"));
         assert!(output.contains("TOTAL"));
     }
+
+    #[test]
+    fn squarify_fills_bounds() {
+        let bounds = TreemapRect { x: 0.0, y: 0.0, w: 600.0, h: 400.0 };
+        let rects = squarify(&[6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0], bounds);
+        assert_eq!(rects.len(), 7);
+
+        let area: f64 = rects.iter().map(|r| r.w * r.h).sum();
+        assert!((area - 600.0 * 400.0).abs() < 1e-6);
+        for r in &rects {
+            assert!(r.x >= -1e-9 && r.y >= -1e-9);
+            assert!(r.x + r.w <= 600.0 + 1e-6 && r.y + r.h <= 400.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn file_coverage_serializes_as_one_flat_object() {
+        let file = FileCoverage { path: "src/a.rs".into(), total_symbols: 4, seen_count: 2, full_count: 1, total_tokens: 90 };
+        assert_eq!(
+            serde_json::to_string(&file).unwrap(),
+            r#"{"path":"src/a.rs","total_symbols":4,"seen_count":2,"full_count":1,"total_tokens":90}"#,
+        );
+    }
+
+    #[test]
+    fn lcov_output_marks_lines_by_innermost_symbol() {
+        let imp = sym_with_children("src/a.rs::impl A", "impl A", vec![sym_with_lines("src/a.rs::impl A/f", "f", 3, 4)]);
+        let imp = SymbolNode { line_range: 2..5, ..imp };
+        let tree = project(vec![
+            file("src/b.rs", vec![sym_with_lines("src/b.rs::g", "g", 1, 1)]),
+            file("src/a.rs", vec![imp]),
+        ]);
+        let mut ledger = ContextLedger::new();
+        ledger.record("src/a.rs::impl A/f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+
+        let output = LcovFormatter.format(&CoverageReport::from_project(&tree, &ledger));
+        assert_eq!(
+            output,
+            "TN:\nSF:src/a.rs\nDA:2,0\nDA:3,1\nDA:4,1\nDA:5,0\nLF:4\nLH:2\nend_of_record\n\
+             SF:src/b.rs\nDA:1,0\nLF:1\nLH:0\nend_of_record\n"
+        );
+    }
+
+    #[test]
+    fn json_output_parses_back() {
+        use chrono::TimeZone;
+
+        let tree = project(vec![
+            file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")]),
+            file("src/b.rs", vec![sym("src/b.rs::h", "h")]),
+        ]);
+        let mut ledger = ContextLedger::new();
+        ledger.record("src/a.rs::f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record("src/a.rs::g".into(), ReadDepth::NameOnly, [0; 32], "ag".into(), 10);
+        let mut report = CoverageReport::from_project(&tree, &ledger);
+        report.session_id = Some("s1".into());
+
+        let formatter = JsonFormatter { generated_at: Some(chrono::Utc.with_ymd_and_hms(2030, 6, 1, 9, 41, 0).unwrap()) };
+        let json: serde_json::Value = serde_json::from_str(&formatter.format(&report)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "generated_at": "2030-06-01T09:41:00Z",
+                "session_id": "s1",
+                "files": [
+                    {"path": "src/b.rs", "total_symbols": 1, "seen_count": 0, "full_count": 0, "seen_percent": 0.0, "full_percent": 0.0},
+                    {"path": "src/a.rs", "total_symbols": 2, "seen_count": 2, "full_count": 1, "seen_percent": 100.0, "full_percent": 50.0},
+                ],
+                "totals": {
+                    "total_symbols": 3,
+                    "seen_count": 2,
+                    "full_count": 1,
+                    "seen_percent": report.total_seen_percent(),
+                    "full_percent": report.total_full_percent(),
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn csv_output_has_header_rows_and_total() {
+        let tree = project(vec![file("src/a,b.rs", vec![sym("src/a,b.rs::f", "f")])]);
+        let mut ledger = ContextLedger::new();
+        ledger.record("src/a,b.rs::f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        let report = CoverageReport::from_project(&tree, &ledger);
+
+        let output = CsvFormatter.format(&report);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "path,total_symbols,seen_count,full_count,seen_percent,full_percent",
+                "\"src/a,b.rs\",1,1,1,100.0,100.0",
+                "TOTAL,1,1,1,100.0,100.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_output_is_a_table_with_a_total() {
+        let tree = project(vec![
+            file("src/a.rs", vec![sym("src/a.rs::f", "f"), sym("src/a.rs::g", "g")]),
+            file("src/b.rs", vec![sym("src/b.rs::h", "h")]),
+        ]);
+        let mut ledger = ContextLedger::new();
+        ledger.record("src/a.rs::f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record("src/a.rs::g".into(), ReadDepth::NameOnly, [0; 32], "ag".into(), 10);
+        let mut report = CoverageReport::from_project(&tree, &ledger);
+        report.session_id = Some("s1".into());
+
+        let output = MarkdownFormatter.format(&report);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Coverage for session `s1`: 33.3% full");
+        assert_eq!(lines[2], "| File | Symbols | Seen | Full | Seen% | Full% |");
+        assert_eq!(lines[3], "|---|---:|---:|---:|---:|---:|");
+        assert!(lines.contains(&"| `src/a.rs` | 2 | 2 | 1 | 100.0% | 50.0% |"), "{output}");
+        assert_eq!(lines.last(), Some(&"| **TOTAL** | 3 | 2 | 1 | 66.7% | 33.3% |"));
+    }
+
+    #[test]
+    fn svg_treemap_output() {
+        let report = CoverageReport { session_id: None, symbols: vec![], files: vec![
+            FileCoverage { path: "src/a&b.rs".into(), total_symbols: 2, seen_count: 2, full_count: 2, total_tokens: 300 },
+            FileCoverage { path: "src/c.rs".into(), total_symbols: 1, seen_count: 0, full_count: 0, total_tokens: 100 },
+            FileCoverage { path: "src/empty.rs".into(), total_symbols: 0, seen_count: 0, full_count: 0, total_tokens: 0 },
+        ]};
+        let output = SvgTreemapFormatter::default().format(&report);
+        assert!(output.starts_with("<svg"));
+        assert!(output.trim_end().ends_with("</svg>"));
+        // Background plus one rectangle per file with tokens.
+        assert_eq!(output.matches("<rect").count(), 3);
+        assert!(output.contains("src/a&amp;b.rs"));
+        assert!(output.contains("#50dc78"));
+        assert!(output.contains("#b43c3c"));
+        assert!(!output.contains("src/empty.rs"));
+    }
 }
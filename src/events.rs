@@ -1,5 +1,6 @@
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::time::Duration;
 
 use crossterm::event::{self, Event, KeyEvent, MouseEvent};
@@ -12,7 +13,14 @@ pub enum AppEvent {
     Key(KeyEvent),
     Mouse(MouseEvent),
     FileChanged(PathBuf),
-    AgentEvent(AgentToolCall),
+    /// The terminal was resized to (columns, rows).
+    Resize(u16, u16),
+    /// The terminal gained (true) or lost (false) focus.
+    Focus(bool),
+    #[allow(dead_code)]
+    AgentEvent(Box<AgentToolCall>),
+    /// A JSONL file under the log directory was created or appended to.
+    LogFileChanged(PathBuf),
     Tick,
 }
 
@@ -21,26 +29,27 @@ pub fn spawn_key_reader(tx: mpsc::Sender<AppEvent>) {
     std::thread::spawn(move || loop {
         if event::poll(Duration::from_millis(50)).unwrap_or(false) {
             match event::read() {
-                Ok(Event::Key(key)) => {
-                    if tx.send(AppEvent::Key(key)).is_err() {
-                        break;
-                    }
-                }
-                Ok(Event::Mouse(mouse)) => {
-                    if tx.send(AppEvent::Mouse(mouse)).is_err() {
-                        break;
-                    }
-                }
+                Ok(Event::Key(key)) if tx.send(AppEvent::Key(key)).is_err() => break,
+                Ok(Event::Mouse(mouse)) if tx.send(AppEvent::Mouse(mouse)).is_err() => break,
+                Ok(Event::Resize(w, h)) if tx.send(AppEvent::Resize(w, h)).is_err() => break,
+                Ok(Event::FocusGained) if tx.send(AppEvent::Focus(true)).is_err() => break,
+                Ok(Event::FocusLost) if tx.send(AppEvent::Focus(false)).is_err() => break,
                 _ => {}
             }
         }
     });
 }
 
-/// Spawn a tick timer that sends Tick events at the given interval.
-pub fn spawn_tick_timer(tx: mpsc::Sender<AppEvent>, interval: Duration) {
+/// Spawn a tick timer that sends Tick events at the given interval, or at
+/// `idle_interval` while `idle` is set.
+pub fn spawn_tick_timer(
+    tx: mpsc::Sender<AppEvent>,
+    interval: Duration,
+    idle_interval: Duration,
+    idle: Arc<AtomicBool>,
+) {
     std::thread::spawn(move || loop {
-        std::thread::sleep(interval);
+        std::thread::sleep(if idle.load(Ordering::Relaxed) { idle_interval } else { interval });
         if tx.send(AppEvent::Tick).is_err() {
             break;
         }
@@ -1,12 +1,13 @@
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use serde_json::Value;
 
-use crate::tracking::ReadDepth;
+use crate::tracking::{ReadDepth, ReadSource};
 
-use super::AgentToolCall;
+use super::{AgentToolCall, END_OF_FILE};
 
 /// Derive the Claude Code log directory for a given project path.
 /// Claude stores logs at ~/.claude/projects/<slug>/ where slug is the
@@ -15,9 +16,8 @@ pub fn log_dir_for_project(project_path: &Path) -> Option<PathBuf> {
     let canonical = project_path.canonicalize().ok()?;
     let slug = canonical
         .to_string_lossy()
-        .replace('/', "-")
-        .replace('.', "-");  // Claude Code also replaces dots with hyphens
-    let home = dirs_home()?;
+        .replace(['/', '.'], "-"); // Claude Code also replaces dots with hyphens
+    let home = home_dir()?;
     let dir = home.join(".claude").join("projects").join(&slug);
     if dir.is_dir() {
         Some(dir)
@@ -26,8 +26,18 @@ pub fn log_dir_for_project(project_path: &Path) -> Option<PathBuf> {
     }
 }
 
-fn dirs_home() -> Option<PathBuf> {
-    std::env::var_os("HOME").map(PathBuf::from)
+/// `$HOME`, read once per process.
+fn home_dir() -> Option<&'static Path> {
+    static HOME: OnceLock<Option<PathBuf>> = OnceLock::new();
+    HOME.get_or_init(|| std::env::var_os("HOME").map(PathBuf::from)).as_deref()
+}
+
+/// How session logs are read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogOptions {
+    /// Count code pasted into user messages as read; see `pasted_code_events`.
+    /// Off by default (`--pasted-code`), since it's a heuristic.
+    pub pasted_code: bool,
 }
 
 /// Find the most recent session ID by scanning for UUID-named .jsonl files
@@ -36,11 +46,30 @@ pub fn find_latest_session(log_dir: &Path) -> Option<String> {
     find_session_from_files(log_dir)
 }
 
+/// A session log discovered in a Claude Code log directory.
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub path: PathBuf,
+    pub modified: std::time::SystemTime,
+}
+
 /// Find the latest session by scanning for UUID-named .jsonl files.
 fn find_session_from_files(log_dir: &Path) -> Option<String> {
-    let entries = fs::read_dir(log_dir).ok()?;
+    list_sessions(log_dir)
+        .into_iter()
+        .next()
+        .map(|s| s.session_id)
+}
 
-    entries
+/// List all non-empty UUID-named session logs, most recently modified first.
+pub fn list_sessions(log_dir: &Path) -> Vec<SessionInfo> {
+    let entries = match fs::read_dir(log_dir) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut sessions: Vec<SessionInfo> = entries
         .flatten()
         .filter_map(|entry| {
             let path = entry.path();
@@ -58,11 +87,16 @@ fn find_session_from_files(log_dir: &Path) -> Option<String> {
             if meta.len() == 0 {
                 return None;
             }
-            let mtime = meta.modified().ok()?;
-            Some((stem.to_string(), mtime))
+            let modified = meta.modified().ok()?;
+            Some(SessionInfo {
+                session_id: stem.to_string(),
+                path: path.clone(),
+                modified,
+            })
         })
-        .max_by_key(|(_, mtime)| *mtime)
-        .map(|(session_id, _)| session_id)
+        .collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.modified));
+    sessions
 }
 
 /// Check if a string looks like a UUID (8-4-4-4-12 hex chars).
@@ -82,6 +116,12 @@ fn is_uuid(s: &str) -> bool {
 /// Supports both old format (agent files flat in log dir) and new format
 /// (agent files in `<session-id>/subagents/`).
 pub fn session_log_files(log_dir: &Path, session_id: &str) -> Vec<PathBuf> {
+    session_log_files_with(log_dir, session_id, |path| agent_belongs_to_session(path, session_id))
+}
+
+/// `session_log_files`, asking `belongs` whether each old-format agent file
+/// is the session's.
+fn session_log_files_with(log_dir: &Path, session_id: &str, mut belongs: impl FnMut(&Path) -> bool) -> Vec<PathBuf> {
     let mut files = Vec::new();
 
     // Main session file.
@@ -116,10 +156,7 @@ pub fn session_log_files(log_dir: &Path, session_id: &str) -> Vec<PathBuf> {
                 .file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("");
-            if name.starts_with("agent-")
-                && name.ends_with(".jsonl")
-                && agent_belongs_to_session(&path, session_id)
-            {
+            if name.starts_with("agent-") && name.ends_with(".jsonl") && belongs(&path) {
                 files.push(path);
             }
         }
@@ -128,6 +165,27 @@ pub fn session_log_files(log_dir: &Path, session_id: &str) -> Vec<PathBuf> {
     files
 }
 
+/// Whether a log file at `path` could be one `session_log_files` lists for
+/// the session, judging by its name and place alone. Other sessions' main
+/// files and anything under their directories can't be. Both paths are
+/// resolved first, as the watcher may spell them through a symlink.
+pub fn may_belong_to_session(log_dir: &Path, session_id: &str, path: &Path) -> bool {
+    let real = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    let log_dir = real(log_dir);
+    let path = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => real(dir).join(name),
+        _ => path.to_path_buf(),
+    };
+    if path.starts_with(log_dir.join(session_id)) {
+        return true;
+    }
+    path.parent() == Some(log_dir.as_path())
+        && path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n == format!("{session_id}.jsonl") || n.starts_with("agent-"))
+}
+
 fn agent_belongs_to_session(path: &Path, session_id: &str) -> bool {
     let file = match fs::File::open(path) {
         Ok(f) => f,
@@ -145,24 +203,47 @@ fn agent_belongs_to_session(path: &Path, session_id: &str) -> bool {
     false
 }
 
+/// Agent id for lines without a sessionId: the file name without `.jsonl`.
+fn default_agent_id(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Whether an event parsed from a file with `default_id` came from a line of
+/// `session_id`. Events carry their line's sessionId as the agent id, or the
+/// default id when the line has none; lines naming another session don't count.
+fn from_session(event: &AgentToolCall, session_id: &str, default_id: &str) -> bool {
+    event.agent_id == session_id || event.agent_id == default_id
+}
+
+/// Parse the events of one of `session_id`'s log files, dropping lines that
+/// name a different session (an old-format agent log shared across sessions).
+pub fn parse_session_log_file(path: &Path, session_id: &str, options: LogOptions) -> Vec<AgentToolCall> {
+    let default_id = default_agent_id(path);
+    let mut events = parse_log_file_with(path, options);
+    events.retain(|e| from_session(e, session_id, &default_id));
+    events
+}
+
 /// Parse all events from a JSONL log file.
 pub fn parse_log_file(path: &Path) -> Vec<AgentToolCall> {
+    parse_log_file_with(path, LogOptions::default())
+}
+
+/// `parse_log_file`, with options.
+pub fn parse_log_file_with(path: &Path, options: LogOptions) -> Vec<AgentToolCall> {
     let mut events = Vec::new();
     let file = match fs::File::open(path) {
         Ok(f) => f,
         Err(_) => return events,
     };
     let reader = BufReader::new(file);
-
-    // Derive a default agent ID from the filename.
-    let default_id = path
-        .file_stem()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let default_id = default_agent_id(path);
 
     for line in reader.lines().map_while(Result::ok) {
-        events.extend(parse_jsonl_line(&line, &default_id));
+        events.extend(parse_jsonl_line_with(&line, &default_id, options.pasted_code));
     }
     events
 }
@@ -170,6 +251,11 @@ pub fn parse_log_file(path: &Path) -> Vec<AgentToolCall> {
 /// Parse a single JSONL line from a Claude Code session log.
 /// Returns tool call events found in assistant messages.
 pub fn parse_jsonl_line(line: &str, default_agent_id: &str) -> Vec<AgentToolCall> {
+    parse_jsonl_line_with(line, default_agent_id, false)
+}
+
+/// `parse_jsonl_line`, saying whether pasted code counts.
+pub fn parse_jsonl_line_with(line: &str, default_agent_id: &str, pasted_code: bool) -> Vec<AgentToolCall> {
     let mut events = Vec::new();
 
     let obj: Value = match serde_json::from_str(line) {
@@ -177,8 +263,15 @@ pub fn parse_jsonl_line(line: &str, default_agent_id: &str) -> Vec<AgentToolCall
         Err(_) => return events,
     };
 
-    let msg_type = obj.get("type").and_then(|v| v.as_str()).unwrap_or("");
-    if msg_type != "assistant" {
+    // Claude logs tag lines with `type`; OpenAI-style messages use `role`.
+    let msg_type = obj
+        .get("type")
+        .or_else(|| obj.get("role"))
+        .or_else(|| obj.pointer("/message/role"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let pasted = pasted_code && msg_type == "user";
+    if msg_type != "assistant" && !pasted {
         return events;
     }
 
@@ -194,24 +287,13 @@ pub fn parse_jsonl_line(line: &str, default_agent_id: &str) -> Vec<AgentToolCall
         .unwrap_or("")
         .to_string();
 
-    let content = match obj.pointer("/message/content") {
-        Some(Value::Array(arr)) => arr,
-        _ => return events,
-    };
-
-    for block in content {
-        if block.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
-            continue;
-        }
-
-        let tool_name = match block.get("name").and_then(|v| v.as_str()) {
-            Some(n) => n,
-            None => continue,
-        };
-
-        let input = block.get("input").cloned().unwrap_or(Value::Null);
+    if pasted {
+        return pasted_code_events(&obj, &agent_id, &timestamp_str);
+    }
 
-        let event = map_tool_call(tool_name, &input, &agent_id, &timestamp_str)
+    for (sub_index, (tool_name, input)) in tool_uses(&obj).into_iter().enumerate() {
+        let tool_name = tool_name.as_str();
+        let mut event = map_tool_call(tool_name, &input, &agent_id, &timestamp_str)
             .unwrap_or_else(|| AgentToolCall {
                 agent_id: agent_id.clone(),
                 tool_name: tool_name.to_string(),
@@ -221,13 +303,176 @@ pub fn parse_jsonl_line(line: &str, default_agent_id: &str) -> Vec<AgentToolCall
                 timestamp_str: timestamp_str.clone(),
                 target_symbol: None,
                 target_lines: None,
+                path_glob: None,
+                symbol_depth_limit: None,
+                todos: Vec::new(),
+                source: ReadSource::Explicit,
+                sub_index: 0,
             });
+        event.sub_index = sub_index;
         events.push(event);
     }
 
     events
 }
 
+/// Fenced code blocks in a user message that name their file, as full reads
+/// of it: the path after the language (```` ```rust src/app.rs ````, or
+/// ```` ```rust // src/app.rs ````) or alone in a comment on the block's first
+/// line. Only the message's own text counts, not tool results. The paste may
+/// be part of the file, so these reads are inferred.
+fn pasted_code_events(obj: &Value, agent_id: &str, timestamp_str: &str) -> Vec<AgentToolCall> {
+    let texts: Vec<&str> = match obj.pointer("/message/content") {
+        Some(Value::String(text)) => vec![text.as_str()],
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|v| v.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|v| v.as_str()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    texts
+        .into_iter()
+        .flat_map(pasted_paths)
+        .enumerate()
+        .map(|(sub_index, path)| AgentToolCall {
+            agent_id: agent_id.to_string(),
+            tool_name: "PastedCode".to_string(),
+            description: format!("Pasted {}", short_path(&path)),
+            file_path: Some(PathBuf::from(path)),
+            read_depth: ReadDepth::FullBody,
+            timestamp_str: timestamp_str.to_string(),
+            target_symbol: None,
+            target_lines: None,
+            path_glob: None,
+            symbol_depth_limit: None,
+            todos: Vec::new(),
+            source: ReadSource::Inferred,
+            sub_index,
+        })
+        .collect()
+}
+
+/// The path header of each complete fenced code block in `text`.
+fn pasted_paths(text: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    // Inside a block: its header path so far, and whether the first line is next.
+    let mut block: Option<(Option<&str>, bool)> = None;
+    for line in text.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut block, fence) {
+            (None, Some(info)) => {
+                let header = info.split_whitespace().find(|word| looks_like_file(word));
+                block = Some((header, true));
+            }
+            (Some((header, _)), Some(_)) => {
+                paths.extend(header.map(str::to_string));
+                block = None;
+            }
+            (Some((header, first)), None) => {
+                if *first && header.is_none() {
+                    *header = comment_path(line);
+                }
+                *first = false;
+            }
+            (None, None) => {}
+        }
+    }
+    paths
+}
+
+/// The path in a first line like `// src/app.rs`, `# app.py` or `-- init.lua`.
+fn comment_path(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let rest = ["//", "#", "--"].iter().find_map(|c| line.strip_prefix(c))?.trim();
+    (!rest.contains(char::is_whitespace) && looks_like_file(rest)).then_some(rest)
+}
+
+/// Whether a word reads as a file path: a name with an extension, not a URL.
+fn looks_like_file(word: &str) -> bool {
+    let name = word.rsplit('/').next().unwrap_or(word);
+    !word.contains("://")
+        && name
+            .rsplit_once('.')
+            .is_some_and(|(stem, ext)| !stem.is_empty() && !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Collect (tool name, input) pairs from a log line. Handles Claude's
+/// `message.content` tool_use blocks and OpenAI-style `tool_calls` arrays,
+/// whose `function.arguments` is usually a JSON-encoded string.
+fn tool_uses(obj: &Value) -> Vec<(String, Value)> {
+    let mut uses = Vec::new();
+
+    // A plain string `content` is a text-only turn with nothing to track.
+    if let Some(Value::Array(content)) = obj.pointer("/message/content") {
+        for block in content {
+            if block.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
+                continue;
+            }
+            if let Some(name) = block.get("name").and_then(|v| v.as_str()) {
+                let input = block.get("input").cloned().unwrap_or(Value::Null);
+                uses.push((name.to_string(), input));
+            }
+        }
+    }
+
+    let calls = ["/tool_calls", "/toolCalls", "/message/tool_calls", "/message/toolCalls"]
+        .iter()
+        .find_map(|ptr| obj.pointer(ptr).and_then(|v| v.as_array()));
+    for call in calls.into_iter().flatten() {
+        let func = call.get("function").unwrap_or(call);
+        let Some(name) = func.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let input = match func.get("arguments").or_else(|| func.get("input")) {
+            Some(Value::String(s)) => serde_json::from_str(s).unwrap_or(Value::Null),
+            Some(v) => v.clone(),
+            None => Value::Null,
+        };
+        uses.push((name.to_string(), input));
+    }
+
+    uses
+}
+
+/// Lines (1-based, end-exclusive) covering every edit's text in the file as it
+/// is on disk now. Each edit is located by its `new_string`, which is what's
+/// there once it has been applied (always, when reading logs afterwards), or
+/// else by its `old_string`, for an edit not applied yet. None (the whole
+/// file) if the file can't be read, or any edit can't be pinned to one place:
+/// neither string occurs exactly once, or it replaces all occurrences.
+fn edited_lines(path: &Path, edits: &[&Value]) -> Option<std::ops::Range<usize>> {
+    if edits.is_empty() {
+        return None;
+    }
+    let source = fs::read_to_string(path).ok()?;
+    let line_at = |idx: usize| source[..idx].matches('\n').count() + 1;
+
+    let mut span: Option<std::ops::Range<usize>> = None;
+    for edit in edits {
+        if edit.get("replace_all").and_then(|v| v.as_bool()) == Some(true) {
+            return None;
+        }
+        let unique = |key: &str| {
+            let text = edit.get(key)?.as_str().filter(|s| !s.is_empty())?;
+            let mut found = source.match_indices(text).map(|(idx, _)| idx);
+            match (found.next(), found.next()) {
+                (Some(idx), None) => Some((idx, text)),
+                _ => None,
+            }
+        };
+        let (idx, text) = unique("new_string").or_else(|| unique("old_string"))?;
+        let start = line_at(idx);
+        let end = start + text.trim_end_matches('\n').matches('\n').count() + 1;
+        span = Some(match span {
+            Some(s) => s.start.min(start)..s.end.max(end),
+            None => start..end,
+        });
+    }
+    span
+}
+
 /// Map a tool call to an AgentToolCall with appropriate ReadDepth.
 fn map_tool_call(
     tool_name: &str,
@@ -235,21 +480,27 @@ fn map_tool_call(
     agent_id: &str,
     timestamp_str: &str,
 ) -> Option<AgentToolCall> {
+    let mut path_glob = None;
+    let mut symbol_depth_limit = None;
+    let mut todos = Vec::new();
     let (file_path, depth, desc, target_symbol, target_lines) = match tool_name {
         // Full file reads.
         "mcp__acp__Read" | "Read" | "mcp__plugin_serena_serena__read_file" => {
             let path = input.get("file_path")
                 .or_else(|| input.get("relative_path"))
                 .and_then(|v| v.as_str())?;
-            // If offset and limit are present, compute a target line range.
+            // With an offset or limit, only part of the file was read: a missing
+            // offset starts at the top, a missing limit runs to the end.
             let target_lines = match (
                 input.get("offset").and_then(|v| v.as_u64()),
                 input.get("limit").and_then(|v| v.as_u64()),
             ) {
-                (Some(offset), Some(limit)) => {
-                    Some(offset as usize..(offset as usize + limit as usize))
+                (None, None) => None,
+                (offset, limit) => {
+                    let start = offset.unwrap_or(0) as usize;
+                    let end = limit.map_or(END_OF_FILE, |limit| start.saturating_add(limit as usize));
+                    Some(start..end)
                 }
-                _ => None,
             };
             (
                 Some(PathBuf::from(path)),
@@ -260,18 +511,23 @@ fn map_tool_call(
             )
         }
 
-        // Edits imply the file was read.
-        "mcp__acp__Edit" | "Edit"
+        // Edits imply the file was read. Where the edited text can be found in
+        // the file, only those lines are targeted; otherwise the whole file is.
+        "mcp__acp__Edit" | "Edit" | "MultiEdit"
         | "mcp__plugin_serena_serena__replace_content" => {
             let path = input.get("file_path")
                 .or_else(|| input.get("relative_path"))
                 .and_then(|v| v.as_str())?;
+            let edits: Vec<&Value> = match input.get("edits").and_then(|v| v.as_array()) {
+                Some(edits) => edits.iter().collect(),
+                None => vec![input],
+            };
             (
                 Some(PathBuf::from(path)),
                 ReadDepth::FullBody,
                 format!("Edit {}", short_path(path)),
                 None,
-                None,
+                edited_lines(Path::new(path), &edits),
             )
         }
 
@@ -301,6 +557,9 @@ fn map_tool_call(
                 .get("path")
                 .or_else(|| input.get("relative_path"))
                 .and_then(|v| v.as_str());
+            if tool_name == "Glob" {
+                path_glob = Some(pattern.to_string());
+            }
             (path.map(PathBuf::from), ReadDepth::NameOnly, format!("Glob {pattern}"), None, None)
         }
 
@@ -316,16 +575,19 @@ fn map_tool_call(
                 .get("path")
                 .or_else(|| input.get("relative_path"))
                 .and_then(|v| v.as_str());
+            path_glob = search_scope_glob(input);
             (path.map(PathBuf::from), ReadDepth::Overview, format!("Search \"{pattern}\""), None, None)
         }
 
-        // Serena symbol overview.
+        // Serena symbol overview: lists symbol names down to `depth` levels
+        // below the top (0 by default), so only those count, and as names.
         "mcp__serena__get_symbols_overview"
         | "mcp__plugin_serena_serena__get_symbols_overview" => {
             let path = input.get("relative_path").and_then(|v| v.as_str());
+            symbol_depth_limit = Some(input.get("depth").and_then(|v| v.as_u64()).unwrap_or(0) as usize);
             (
                 path.map(PathBuf::from),
-                ReadDepth::Overview,
+                ReadDepth::NameOnly,
                 format!("Overview {}", path.unwrap_or("?")),
                 None,
                 None,
@@ -479,10 +741,23 @@ fn map_tool_call(
             )
         }
 
+        // Todo list: no coverage, but the open items say what the agent plans to touch.
+        "TodoWrite" => {
+            let items = input.get("todos").and_then(|v| v.as_array())?;
+            todos = items
+                .iter()
+                .filter(|item| item.get("status").and_then(|v| v.as_str()) != Some("completed"))
+                .filter_map(|item| item.get("content").and_then(|v| v.as_str()))
+                .map(str::to_string)
+                .collect();
+            let count = items.len();
+            (None, ReadDepth::Unseen, format!("TodoWrite {count} item{}", if count == 1 { "" } else { "s" }), None, None)
+        }
+
         _ => return None,
     };
 
-    Some(AgentToolCall {
+    let mut event = AgentToolCall {
         agent_id: agent_id.to_string(),
         tool_name: tool_name.to_string(),
         file_path,
@@ -491,7 +766,74 @@ fn map_tool_call(
         timestamp_str: timestamp_str.to_string(),
         target_symbol,
         target_lines,
-    })
+        path_glob,
+        symbol_depth_limit,
+        todos,
+        source: ReadSource::Explicit,
+        sub_index: 0,
+    };
+    // An edit means the agent read the code at some point, not that this call
+    // showed it; searches and reference lookups show only matching lines.
+    let inferred = matches!(
+        tool_name,
+        "Grep"
+            | "mcp__serena__search_for_pattern"
+            | "mcp__plugin_serena_serena__search_for_pattern"
+            | "mcp__serena__find_referencing_symbols"
+            | "mcp__plugin_serena_serena__find_referencing_symbols"
+    );
+    if inferred || event.is_edit() {
+        event.source = ReadSource::Inferred;
+    }
+    Some(event)
+}
+
+/// Extensions of the common ripgrep `--type` names, for Grep's `type` filter.
+const RG_TYPES: &[(&str, &[&str])] = &[
+    ("c", &["c", "h"]),
+    ("cpp", &["cc", "cpp", "cxx", "c++", "h", "hh", "hpp", "hxx", "inl"]),
+    ("css", &["css", "scss"]),
+    ("go", &["go"]),
+    ("html", &["htm", "html"]),
+    ("java", &["java", "jsp"]),
+    ("js", &["js", "jsx", "mjs", "cjs", "vue"]),
+    ("json", &["json"]),
+    ("lua", &["lua"]),
+    ("markdown", &["md", "markdown", "mdx"]),
+    ("md", &["md", "markdown", "mdx"]),
+    ("py", &["py", "pyi"]),
+    ("ruby", &["rb", "gemspec"]),
+    ("rust", &["rs"]),
+    ("sh", &["sh", "bash", "zsh"]),
+    ("toml", &["toml"]),
+    ("ts", &["ts", "tsx", "mts", "cts"]),
+    ("yaml", &["yaml", "yml"]),
+];
+
+/// Work out which files a search was scoped to, as a glob relative to its path.
+/// Searches without a file filter return None so they don't claim every file
+/// under their path (the whole project, for a search of the root), and so do
+/// searches filtered by a `type` ripgrep name we don't know.
+fn search_scope_glob(input: &Value) -> Option<String> {
+    input
+        .get("glob")
+        .or_else(|| input.get("paths_include_glob"))
+        .and_then(|v| v.as_str())
+        .filter(|g| !g.is_empty())
+        .map(|g| {
+            // ripgrep-style globs without a separator match at any depth.
+            if g.contains('/') { g.to_string() } else { format!("**/{g}") }
+        })
+        .or_else(|| {
+            input
+                .get("type")
+                .and_then(|v| v.as_str())
+                .and_then(|name| RG_TYPES.iter().find(|(n, _)| *n == name))
+                .map(|(_, exts)| match exts {
+                    [ext] => format!("**/*.{ext}"),
+                    _ => format!("**/*.{{{}}}", exts.join(",")),
+                })
+        })
 }
 
 /// Shorten a file path for display (last 2 components).
@@ -510,6 +852,12 @@ fn short_path(path: &str) -> String {
 pub struct LogTailer {
     files: Vec<PathBuf>,
     positions: std::collections::HashMap<PathBuf, u64>,
+    /// When set, lines naming another session are dropped as they're read.
+    session_id: Option<String>,
+    options: LogOptions,
+    /// Old-format agent files found to be another session's, with their
+    /// length then; they're checked again only once they've grown.
+    rejected: std::collections::HashMap<PathBuf, u64>,
 }
 
 impl LogTailer {
@@ -523,10 +871,58 @@ impl LogTailer {
                 positions.insert(f.clone(), meta.len());
             }
         }
-        Self { files, positions }
+        Self {
+            files,
+            positions,
+            session_id: None,
+            options: LogOptions::default(),
+            rejected: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Only pass on events from `session_id`'s lines.
+    pub fn with_session(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
+    /// Read new lines with `options`.
+    pub fn with_options(mut self, options: LogOptions) -> Self {
+        self.options = options;
+        self
     }
 
+    /// Start tailing any of the session's log files in `log_dir` not tailed
+    /// yet. Files already tailed or already rejected (and unchanged since)
+    /// aren't opened again. Needs `with_session`.
+    pub fn refresh(&mut self, log_dir: &Path) {
+        let Some(session_id) = self.session_id.clone() else { return };
+        let (positions, rejected) = (&self.positions, &mut self.rejected);
+        let files = session_log_files_with(log_dir, &session_id, |path| {
+            if positions.contains_key(path) {
+                return true;
+            }
+            let len = fs::metadata(path).map_or(0, |m| m.len());
+            if rejected.get(path) == Some(&len) {
+                return false;
+            }
+            let belongs = agent_belongs_to_session(path, &session_id);
+            if belongs {
+                rejected.remove(path);
+            } else {
+                rejected.insert(path.to_path_buf(), len);
+            }
+            belongs
+        });
+        for file in files {
+            self.add_file(file);
+        }
+    }
 
+    /// Whether `path` is one of the tailed files.
+    pub fn is_tailing(&self, path: &Path) -> bool {
+        self.positions.contains_key(path)
+    }
 
     /// Add a new file to tail (e.g., a newly created agent log).
     pub fn add_file(&mut self, path: PathBuf) {
@@ -540,51 +936,63 @@ impl LogTailer {
     /// Returns any new agent tool call events.
     pub fn read_new_events(&mut self) -> Vec<AgentToolCall> {
         let mut events = Vec::new();
+        for i in 0..self.files.len() {
+            let file_path = self.files[i].clone();
+            self.read_into(&file_path, &mut events);
+        }
+        events
+    }
+
+    /// Read new lines from one tracked file, for when a watcher says it changed.
+    /// Files that aren't tailed give nothing.
+    pub fn read_file_events(&mut self, file_path: &Path) -> Vec<AgentToolCall> {
+        let mut events = Vec::new();
+        if self.is_tailing(file_path) {
+            self.read_into(file_path, &mut events);
+        }
+        events
+    }
 
-        for file_path in &self.files {
-            let pos = self.positions.get(file_path).copied().unwrap_or(0);
-            let current_len = fs::metadata(file_path)
-                .map(|m| m.len())
-                .unwrap_or(0);
+    fn read_into(&mut self, file_path: &Path, events: &mut Vec<AgentToolCall>) {
+        let pos = self.positions.get(file_path).copied().unwrap_or(0);
+        let current_len = fs::metadata(file_path)
+            .map(|m| m.len())
+            .unwrap_or(0);
 
-            if current_len <= pos {
-                continue;
-            }
+        if current_len <= pos {
+            return;
+        }
 
-            let default_id = file_path
-                .file_stem()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown")
-                .to_string();
-
-            if let Ok(file) = fs::File::open(file_path) {
-                use std::io::{Seek, SeekFrom};
-                let mut reader = BufReader::new(file);
-                if reader.seek(SeekFrom::Start(pos)).is_ok() {
-                    let mut line = String::new();
-                    loop {
-                        line.clear();
-                        match reader.read_line(&mut line) {
-                            Ok(0) => break,
-                            Ok(_) => {
-                                events.extend(parse_jsonl_line(line.trim(), &default_id));
-                            }
-                            Err(_) => break,
+        let default_id = default_agent_id(file_path);
+
+        if let Ok(file) = fs::File::open(file_path) {
+            use std::io::{Seek, SeekFrom};
+            let mut reader = BufReader::new(file);
+            if reader.seek(SeekFrom::Start(pos)).is_ok() {
+                let mut line = String::new();
+                loop {
+                    line.clear();
+                    match reader.read_line(&mut line) {
+                        Ok(0) => break,
+                        Ok(_) => {
+                            let parsed = parse_jsonl_line_with(line.trim(), &default_id, self.options.pasted_code);
+                            events.extend(parsed.into_iter().filter(|e| {
+                                self.session_id.as_deref().is_none_or(|sid| from_session(e, sid, &default_id))
+                            }));
                         }
+                        Err(_) => break,
                     }
                 }
             }
-
-            self.positions.insert(file_path.clone(), current_len);
         }
 
-        events
+        self.positions.insert(file_path.to_path_buf(), current_len);
     }
 }
 
 #[cfg(test)]
 #[path = "../../tests/helpers/mod.rs"]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::duplicate_mod)]
 mod helpers;
 
 #[cfg(test)]
@@ -619,6 +1027,66 @@ mod tests {
         assert!(events.is_empty());
     }
 
+    #[test]
+    fn pasted_code_with_a_path_header_is_an_inferred_read() {
+        let text = "Here it is:\n```rust src/app.rs\nfn a() {}\n```\n\n```python\n# tools/gen.py\nx = 1\n```\n```\nno header\n```\n```text\n// https://example.com/a.rs\n```";
+        let line = serde_json::json!({
+            "type": "user",
+            "sessionId": "s1",
+            "timestamp": "2025-01-01T00:00:00Z",
+            "message": {"role": "user", "content": [
+                {"type": "text", "text": text},
+                {"type": "tool_result", "content": "```rust ignored.rs\n```"},
+            ]},
+        })
+        .to_string();
+
+        assert!(parse_jsonl_line_with(&line, "d", false).is_empty());
+        let events = parse_jsonl_line_with(&line, "d", true);
+        let paths: Vec<_> = events.iter().map(|e| e.file_path.clone().unwrap()).collect();
+        assert_eq!(paths, [PathBuf::from("src/app.rs"), PathBuf::from("tools/gen.py")]);
+        assert_eq!(events[0].read_depth, ReadDepth::FullBody);
+        assert_eq!(events[0].source, ReadSource::Inferred);
+        assert_eq!(events[0].agent_id, "s1");
+        assert_eq!(events[1].sub_index, 1);
+
+        // An unclosed fence isn't a paste.
+        assert!(pasted_paths("```rust src/a.rs\nfn a() {}").is_empty());
+    }
+
+    #[test]
+    fn test_multi_block_message_sub_index() {
+        let line = r#"{"type":"assistant","timestamp":"2025-01-01T00:00:00Z","message":{"content":[{"type":"text","text":"reading"},{"type":"tool_use","name":"Read","input":{"file_path":"/p/a.rs"}},{"type":"tool_use","name":"Read","input":{"file_path":"/p/b.rs"}}]}}"#;
+        let events = parse_jsonl_line(line, "default");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].file_path, Some(PathBuf::from("/p/a.rs")));
+        assert_eq!(events[1].file_path, Some(PathBuf::from("/p/b.rs")));
+        assert_eq!((events[0].sub_index, events[1].sub_index), (0, 1));
+        assert_eq!(events[0].timestamp_str, events[1].timestamp_str);
+    }
+
+    #[test]
+    fn test_parse_openai_tool_calls() {
+        let line = r#"{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"Read","arguments":"{\"file_path\":\"/foo.rs\"}"}}]}"#;
+        let events = parse_jsonl_line(line, "default");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].tool_name, "Read");
+        assert_eq!(events[0].file_path, Some(PathBuf::from("/foo.rs")));
+        assert_eq!(events[0].agent_id, "default");
+
+        // camelCase key nested under message, object arguments.
+        let line = r#"{"type":"assistant","message":{"content":"thinking...","toolCalls":[{"function":{"name":"Grep","arguments":{"pattern":"x"}}}]}}"#;
+        let events = parse_jsonl_line(line, "default");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].read_depth, ReadDepth::Overview);
+    }
+
+    #[test]
+    fn test_string_content_is_skipped() {
+        let line = r#"{"type":"assistant","message":{"role":"assistant","content":"just text"}}"#;
+        assert!(parse_jsonl_line(line, "default").is_empty());
+    }
+
     #[test]
     fn test_ignores_type_a() {
         // "type":"A" does not appear in real logs; only "assistant" should be accepted.
@@ -666,6 +1134,20 @@ mod tests {
         assert_eq!(result, Some(uuid2.to_string()));
     }
 
+    #[test]
+    fn test_list_sessions_newest_first() {
+        let tmp = tempfile::tempdir().unwrap();
+        let old = "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee";
+        let new = "11111111-2222-3333-4444-555555555555";
+        fs::write(tmp.path().join(format!("{old}.jsonl")), "{}").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        fs::write(tmp.path().join(format!("{new}.jsonl")), "{}").unwrap();
+        fs::write(tmp.path().join("agent-abc123.jsonl"), "{}").unwrap();
+
+        let ids: Vec<String> = list_sessions(tmp.path()).into_iter().map(|s| s.session_id).collect();
+        assert_eq!(ids, vec![new.to_string(), old.to_string()]);
+    }
+
     #[test]
     fn test_session_log_files_subagents_dir() {
         // Create a temp dir mimicking the new format:
@@ -716,6 +1198,84 @@ mod tests {
         assert!(!files.contains(&agent_other));
     }
 
+    #[test]
+    fn may_belong_to_session_judges_by_place_and_name() {
+        let dir = Path::new("/logs");
+        assert!(may_belong_to_session(dir, "s1", Path::new("/logs/s1.jsonl")));
+        assert!(may_belong_to_session(dir, "s1", Path::new("/logs/s1/subagents/agent-a.jsonl")));
+        assert!(may_belong_to_session(dir, "s1", Path::new("/logs/agent-b.jsonl")));
+        assert!(!may_belong_to_session(dir, "s1", Path::new("/logs/s2.jsonl")));
+        assert!(!may_belong_to_session(dir, "s1", Path::new("/logs/s2/subagents/agent-a.jsonl")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn may_belong_to_session_sees_through_symlinked_log_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let real = tmp.path().join("real");
+        let link = tmp.path().join("link");
+        fs::create_dir_all(real.join("s1/subagents")).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        assert!(may_belong_to_session(&link, "s1", &real.join("s1.jsonl")));
+        assert!(may_belong_to_session(&real, "s1", &link.join("s1/subagents/agent-a.jsonl")));
+        assert!(!may_belong_to_session(&link, "s1", &real.join("s2.jsonl")));
+    }
+
+    #[test]
+    fn tailer_reads_added_files_from_the_start() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main_file = tmp.path().join("s.jsonl");
+        std::fs::write(&main_file, "").unwrap();
+        let agent_file = tmp.path().join("agent-a.jsonl");
+        let mut tailer = LogTailer::new(vec![main_file.clone()]);
+        assert!(tailer.is_tailing(&main_file));
+        assert!(!tailer.is_tailing(&agent_file));
+
+        let line = r#"{"type":"assistant","timestamp":"2026-01-01T00:00:00Z","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/p/a.rs"}}]}}"#;
+        std::fs::write(&agent_file, format!("{line}\n")).unwrap();
+        tailer.add_file(agent_file.clone());
+        assert!(tailer.is_tailing(&agent_file));
+        assert_eq!(tailer.read_file_events(&agent_file).len(), 1);
+        assert!(tailer.read_new_events().is_empty());
+        assert!(tailer.read_file_events(&tmp.path().join("other.jsonl")).is_empty());
+
+        // Pasted code counts only for tailers told to count it.
+        let paste = r#"{"type":"user","message":{"content":"```rust src/a.rs\nfn a() {}\n```"}}"#;
+        let mut pasting = LogTailer::new(vec![main_file.clone()]).with_options(LogOptions { pasted_code: true });
+        let mut f = fs::OpenOptions::new().append(true).open(&main_file).unwrap();
+        writeln!(f, "{paste}").unwrap();
+        assert!(tailer.read_file_events(&main_file).is_empty());
+        assert_eq!(pasting.read_file_events(&main_file).len(), 1);
+    }
+
+    #[test]
+    fn tailer_drops_lines_from_other_sessions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let session = "abcd1234-abcd-abcd-abcd-abcd12345678";
+        let read = |sid: &str| {
+            format!(r#"{{"type":"assistant","sessionId":"{sid}","message":{{"content":[{{"type":"tool_use","name":"Read","input":{{"file_path":"/p/a.rs"}}}}]}}}}"#)
+        };
+        let shared = tmp.path().join("agent-shared.jsonl");
+        fs::write(&shared, format!("{}\n", read(session))).unwrap();
+        let other = tmp.path().join("agent-other.jsonl");
+        fs::write(&other, format!("{}\n", read("other-session"))).unwrap();
+
+        let mut tailer = LogTailer::new(Vec::new()).with_session(session);
+        tailer.refresh(tmp.path());
+        assert!(tailer.is_tailing(&shared));
+        assert!(!tailer.is_tailing(&other));
+        assert_eq!(tailer.read_file_events(&shared).len(), 1);
+
+        // The file is reused by another session: its lines don't leak in.
+        let mut f = fs::OpenOptions::new().append(true).open(&shared).unwrap();
+        writeln!(f, "{}", read("other-session")).unwrap();
+        writeln!(f, "{}", read(session)).unwrap();
+        let events = tailer.read_file_events(&shared);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].agent_id, session);
+        assert_eq!(parse_session_log_file(&shared, session, LogOptions::default()).len(), 2);
+    }
+
     // --- map_tool_call coverage tests (via parse_jsonl_line) ---
 
     use super::helpers::{jsonl_assistant, jsonl_user_msg};
@@ -728,6 +1288,60 @@ mod tests {
         assert_eq!(events[0].file_path.as_ref().unwrap(), &PathBuf::from("/src/app.rs"));
     }
 
+    #[test]
+    fn edits_and_searches_are_inferred_reads() {
+        let source = |tool: &str, input: &str| parse_jsonl_line(&jsonl_assistant(tool, input), "d")[0].source;
+        assert_eq!(source("Read", r#"{"file_path":"/src/app.rs"}"#), ReadSource::Explicit);
+        assert_eq!(source("Write", r#"{"file_path":"/src/app.rs","content":"x"}"#), ReadSource::Explicit);
+        assert_eq!(
+            source("mcp__serena__find_symbol", r#"{"name_path_pattern":"App","include_body":true}"#),
+            ReadSource::Explicit,
+        );
+        assert_eq!(source("Edit", r#"{"file_path":"/src/app.rs","old_string":"a","new_string":"b"}"#), ReadSource::Inferred);
+        assert_eq!(source("Grep", r#"{"pattern":"fn main","path":"/src"}"#), ReadSource::Inferred);
+    }
+
+    #[test]
+    fn map_edit_targets_located_lines() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.rs");
+        fs::write(&path, "fn a() {}\n\nfn b() {\n    2\n}\n\nfn c() {}\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let edit = |tool: &str, input: serde_json::Value| {
+            let line = jsonl_assistant(tool, &input.to_string());
+            parse_jsonl_line(&line, "d").remove(0)
+        };
+
+        // An edit not applied yet is located by its old_string.
+        let ev = edit("Edit", serde_json::json!({"file_path": path, "old_string": "fn b() {\n    2", "new_string": "x"}));
+        assert_eq!(ev.target_lines, Some(3..5));
+        // One already applied, as when reading logs afterwards, by its new_string.
+        let ev = edit("Edit", serde_json::json!({"file_path": path, "old_string": "gone", "new_string": "fn c() {}"}));
+        assert_eq!(ev.target_lines, Some(7..8));
+        let ev = edit("Edit", serde_json::json!({"file_path": path, "old_string": "gone", "new_string": "missing"}));
+        assert_eq!(ev.target_lines, None);
+
+        let ev = edit("MultiEdit", serde_json::json!({"file_path": path, "edits": [
+            {"old_string": "fn c", "new_string": "fn cc"},
+            {"old_string": "    2", "new_string": "    3"},
+        ]}));
+        assert_eq!(ev.target_lines, Some(4..8));
+
+        // Anything not found falls back to the whole file.
+        let ev = edit("MultiEdit", serde_json::json!({"file_path": path, "edits": [
+            {"old_string": "fn c", "new_string": "fn cc"},
+            {"old_string": "nope", "new_string": "nada"},
+        ]}));
+        assert_eq!(ev.target_lines, None);
+        assert_eq!(ev.read_depth, ReadDepth::FullBody);
+
+        // A snippet that occurs more than once could be any of them.
+        let ev = edit("Edit", serde_json::json!({"file_path": path, "old_string": "() {", "new_string": "() -> () {"}));
+        assert_eq!(ev.target_lines, None);
+        let ev = edit("Edit", serde_json::json!({"file_path": path, "old_string": "fn a", "new_string": "fn z", "replace_all": true}));
+        assert_eq!(ev.target_lines, None);
+    }
+
     #[test]
     fn map_write_tool() {
         let line = jsonl_assistant("mcp__acp__Write", r#"{"file_path":"/src/new.rs","content":"fn main(){}"}"#);
@@ -735,6 +1349,19 @@ mod tests {
         assert_eq!(events[0].read_depth, ReadDepth::FullBody);
     }
 
+    #[test]
+    fn map_todo_write_keeps_open_items() {
+        let line = jsonl_assistant(
+            "TodoWrite",
+            r#"{"todos":[{"content":"Fix parse_line","status":"in_progress"},{"content":"Read app.rs","status":"completed"}]}"#,
+        );
+        let events = parse_jsonl_line(&line, "d");
+        assert_eq!(events[0].read_depth, ReadDepth::Unseen);
+        assert!(events[0].file_path.is_none());
+        assert_eq!(events[0].description, "TodoWrite 2 items");
+        assert_eq!(events[0].todos, ["Fix parse_line"]);
+    }
+
     #[test]
     fn map_glob_tool() {
         let line = jsonl_assistant("Glob", r#"{"pattern":"**/*.rs","path":"/src"}"#);
@@ -742,6 +1369,38 @@ mod tests {
         assert_eq!(events[0].read_depth, ReadDepth::NameOnly);
     }
 
+    #[test]
+    fn map_glob_tool_keeps_pattern() {
+        let line = jsonl_assistant("Glob", r#"{"pattern":"src/**/*.rs"}"#);
+        let events = parse_jsonl_line(&line, "d");
+        assert_eq!(events[0].path_glob.as_deref(), Some("src/**/*.rs"));
+        assert!(events[0].file_path.is_none());
+    }
+
+    #[test]
+    fn map_grep_scopes() {
+        // Unscoped grep claims nothing.
+        let line = jsonl_assistant("Grep", r#"{"pattern":"foo"}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].path_glob, None);
+
+        // A directory without a file filter claims nothing beneath it either.
+        let line = jsonl_assistant("Grep", r#"{"pattern":"foo","path":"/p/src"}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].path_glob, None);
+
+        // Glob filters without a separator match at any depth.
+        let line = jsonl_assistant("Grep", r#"{"pattern":"foo","glob":"*.py"}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].path_glob.as_deref(), Some("**/*.py"));
+
+        // File type filters use ripgrep's type names, some of which cover several extensions.
+        let line = jsonl_assistant("Grep", r#"{"pattern":"foo","type":"rust"}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].path_glob.as_deref(), Some("**/*.rs"));
+        let line = jsonl_assistant("Grep", r#"{"pattern":"foo","type":"py"}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].path_glob.as_deref(), Some("**/*.{py,pyi}"));
+        // An unknown type claims nothing rather than a made-up extension.
+        let line = jsonl_assistant("Grep", r#"{"pattern":"foo","type":"rs"}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].path_glob, None);
+    }
+
     #[test]
     fn map_find_file() {
         let line = jsonl_assistant("mcp__serena__find_file", r#"{"file_mask":"*.rs","relative_path":"src"}"#);
@@ -753,8 +1412,12 @@ mod tests {
     fn map_symbols_overview() {
         let line = jsonl_assistant("mcp__serena__get_symbols_overview", r#"{"relative_path":"src/app.rs"}"#);
         let events = parse_jsonl_line(&line, "d");
-        assert_eq!(events[0].read_depth, ReadDepth::Overview);
+        assert_eq!(events[0].read_depth, ReadDepth::NameOnly);
+        assert_eq!(events[0].symbol_depth_limit, Some(0));
         assert_eq!(events[0].file_path.as_ref().unwrap(), &PathBuf::from("src/app.rs"));
+
+        let line = jsonl_assistant("mcp__serena__get_symbols_overview", r#"{"relative_path":"src/app.rs","depth":1}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].symbol_depth_limit, Some(1));
     }
 
     #[test]
@@ -829,6 +1492,18 @@ mod tests {
         assert_eq!(events[0].target_lines, Some(10..30));
     }
 
+    #[test]
+    fn read_with_only_limit_or_offset() {
+        let line = jsonl_assistant("Read", r#"{"file_path":"/src/main.rs","limit":40}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].target_lines, Some(0..40));
+
+        let line = jsonl_assistant("Read", r#"{"file_path":"/src/main.rs","offset":100}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].target_lines, Some(100..END_OF_FILE));
+
+        let line = jsonl_assistant("Read", r#"{"file_path":"/src/main.rs"}"#);
+        assert_eq!(parse_jsonl_line(&line, "d")[0].target_lines, None);
+    }
+
     #[test]
     fn parse_malformed_json() {
         let events = parse_jsonl_line("not valid json {{{", "d");
@@ -3,21 +3,79 @@ pub mod claude;
 
 use std::path::PathBuf;
 
-use crate::tracking::ReadDepth;
+use serde::Serialize;
+
+use crate::tracking::{ReadDepth, ReadSource};
+
+/// `target_lines` end for a read that runs to the end of the file.
+pub const END_OF_FILE: usize = usize::MAX;
 
 /// A parsed agent tool call event.
-#[derive(Debug, Clone)]
+/// Serializes to the JSON printed by `--emit-events`.
+#[derive(Debug, Clone, Serialize)]
 pub struct AgentToolCall {
     pub agent_id: String,
     pub tool_name: String,
     pub file_path: Option<PathBuf>,
     pub read_depth: ReadDepth,
     pub description: String,
+    #[serde(rename = "timestamp")]
     pub timestamp_str: String,
     /// Optional symbol name path to target (e.g. "MyClass/my_method").
     pub target_symbol: Option<String>,
-    /// Optional line range to target (1-based, e.g. 10..25).
+    /// Optional line range to target (1-based, e.g. 10..25); ends at
+    /// `END_OF_FILE` when open-ended.
     pub target_lines: Option<Range<usize>>,
+    /// Optional glob selecting several files, relative to `file_path`
+    /// (a directory) or to the project root when there is no path.
+    pub path_glob: Option<String>,
+    /// For untargeted calls, mark only symbols at most this many levels below
+    /// the file's top level (0 = top-level only); None marks every symbol.
+    pub symbol_depth_limit: Option<usize>,
+    /// For todo list updates, the items not yet completed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub todos: Vec<String>,
+    /// Whether the call showed the code or only implies it was read.
+    pub source: ReadSource,
+    /// Position of this call among the tool calls in its log message (0-based).
+    /// Calls from one message share a timestamp; this orders them.
+    pub sub_index: usize,
+}
+
+impl AgentToolCall {
+    /// Whether the call replaces the agent's todo list.
+    pub fn is_plan(&self) -> bool {
+        self.tool_name == "TodoWrite"
+    }
+
+    /// Whether the call modifies existing code (as opposed to reading it or
+    /// writing a whole file).
+    pub fn is_edit(&self) -> bool {
+        matches!(
+            self.tool_name.as_str(),
+            "Edit"
+                | "mcp__acp__Edit"
+                | "MultiEdit"
+                | "NotebookEdit"
+                | "mcp__plugin_serena_serena__replace_content"
+                | "mcp__serena__replace_symbol_body"
+                | "mcp__plugin_serena_serena__replace_symbol_body"
+                | "mcp__serena__insert_after_symbol"
+                | "mcp__plugin_serena_serena__insert_after_symbol"
+                | "mcp__serena__insert_before_symbol"
+                | "mcp__plugin_serena_serena__insert_before_symbol"
+                | "mcp__serena__rename_symbol"
+                | "mcp__plugin_serena_serena__rename_symbol"
+        )
+    }
+
+    /// Whether the call writes a whole file, replacing any existing content.
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self.tool_name.as_str(),
+            "Write" | "mcp__acp__Write" | "mcp__plugin_serena_serena__create_text_file"
+        )
+    }
 }
 
 /// Trait for agent event sources.
@@ -26,3 +84,28 @@ pub trait AgentEventSource {
     /// Parse all events from existing log files.
     fn parse_existing(&self) -> color_eyre::Result<Vec<AgentToolCall>>;
 }
+
+#[cfg(test)]
+#[path = "../../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use crate::tracking::ReadDepth;
+
+    #[test]
+    fn tool_call_serializes_to_flat_json() {
+        let mut call = tool_call_targeted("Read", "/p/src/a.rs", ReadDepth::FullBody, "Foo/bar");
+        call.target_lines = Some(10..25);
+        let json = serde_json::to_value(&call).unwrap();
+        assert_eq!(json["tool_name"], "Read");
+        assert_eq!(json["file_path"], "/p/src/a.rs");
+        assert_eq!(json["read_depth"], "full_body");
+        assert_eq!(json["target_symbol"], "Foo/bar");
+        assert_eq!(json["target_lines"], serde_json::json!({ "start": 10, "end": 25 }));
+        assert!(json.get("timestamp").is_some());
+        assert!(json.get("timestamp_str").is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{bail, eyre, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::KeyBinding;
+
+/// Something a key press can do in the TUI's normal (non-search) mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ForceQuit,
+    Down,
+    Up,
+    Expand,
+    Collapse,
+    Top,
+    Bottom,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    Search,
+    Sort,
+    CycleAgent,
+    ToggleAgentMode,
+    Focus,
+    Details,
+    CompactChains,
+    ToggleSizeUnit,
+    ToggleActivity,
+    FlatView,
+    Pin,
+    Note,
+    GroupImpls,
+    AgentPicker,
+    SessionPicker,
+    SameName,
+    ExportView,
+    StaleReport,
+    Warnings,
+    ReplayPause,
+    ReplayFaster,
+    ReplaySlower,
+}
+
+impl Action {
+    /// Every action with its config name, in help order.
+    pub const ALL: &'static [(Action, &'static str)] = &[
+        (Action::Quit, "quit"),
+        (Action::ForceQuit, "force_quit"),
+        (Action::Down, "down"),
+        (Action::Up, "up"),
+        (Action::Expand, "expand"),
+        (Action::Collapse, "collapse"),
+        (Action::Top, "top"),
+        (Action::Bottom, "bottom"),
+        (Action::PageDown, "page_down"),
+        (Action::PageUp, "page_up"),
+        (Action::HalfPageDown, "half_page_down"),
+        (Action::HalfPageUp, "half_page_up"),
+        (Action::Search, "search"),
+        (Action::Sort, "sort"),
+        (Action::CycleAgent, "agent"),
+        (Action::ToggleAgentMode, "agent_mode"),
+        (Action::Focus, "focus"),
+        (Action::Details, "details"),
+        (Action::CompactChains, "compact"),
+        (Action::ToggleSizeUnit, "size_unit"),
+        (Action::ToggleActivity, "activity"),
+        (Action::FlatView, "flat"),
+        (Action::Pin, "pin"),
+        (Action::Note, "note"),
+        (Action::GroupImpls, "group_impls"),
+        (Action::AgentPicker, "agent_picker"),
+        (Action::SessionPicker, "session_picker"),
+        (Action::SameName, "same_name"),
+        (Action::ExportView, "export_view"),
+        (Action::StaleReport, "stale_report"),
+        (Action::Warnings, "warnings"),
+        (Action::ReplayPause, "replay_pause"),
+        (Action::ReplayFaster, "replay_faster"),
+        (Action::ReplaySlower, "replay_slower"),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Self::ALL.iter().find(|(_, n)| *n == name).map(|(a, _)| *a)
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::ForceQuit => &["Q"],
+            Action::Down => &["j", "down"],
+            Action::Up => &["k", "up"],
+            Action::Expand => &["l", "right", "enter"],
+            Action::Collapse => &["h", "left"],
+            Action::Top => &["g"],
+            Action::Bottom => &["G"],
+            Action::PageDown => &["pagedown"],
+            Action::PageUp => &["pageup"],
+            Action::HalfPageDown => &["ctrl-d"],
+            Action::HalfPageUp => &["ctrl-u"],
+            Action::Search => &["/"],
+            Action::Sort => &["s"],
+            Action::CycleAgent => &["a"],
+            Action::ToggleAgentMode => &["u"],
+            Action::Focus => &["tab"],
+            Action::Details => &["i"],
+            Action::CompactChains => &["c"],
+            Action::ToggleSizeUnit => &["t"],
+            Action::ToggleActivity => &["F"],
+            Action::FlatView => &["f"],
+            Action::Pin => &["P"],
+            Action::Note => &["n"],
+            Action::GroupImpls => &["m"],
+            Action::AgentPicker => &["A"],
+            Action::SessionPicker => &["S"],
+            Action::SameName => &["*"],
+            Action::ExportView => &["x"],
+            Action::StaleReport => &["X"],
+            Action::Warnings => &["W"],
+            Action::ReplayPause => &["space"],
+            Action::ReplayFaster => &["+", "="],
+            Action::ReplaySlower => &["-"],
+        }
+    }
+}
+
+/// A key plus the modifiers that matter for matching (Ctrl and Alt).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeySpec {
+    pub code: KeyCode,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl KeySpec {
+    /// Parse a spec like `q`, `G`, `ctrl-d`, `alt-x`, `enter`, `pagedown`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut ctrl = false;
+        let mut alt = false;
+        let mut rest = spec;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if rest.len() > 5 && lower.starts_with("ctrl-") {
+                ctrl = true;
+                rest = &rest[5..];
+            } else if rest.len() > 4 && lower.starts_with("alt-") {
+                alt = true;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "esc" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                _ => bail!("unrecognized key spec {spec:?}"),
+            },
+        };
+        Ok(Self { code, ctrl, alt })
+    }
+
+    fn from_event(key: &KeyEvent) -> Self {
+        Self {
+            code: key.code,
+            ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+            alt: key.modifiers.contains(KeyModifiers::ALT),
+        }
+    }
+}
+
+/// Maps key presses to actions. Starts from the built-in bindings; an action
+/// listed in the `[keys]` config section has its defaults replaced.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: HashMap<KeySpec, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        for &(action, _) in Action::ALL {
+            for spec in action.default_keys() {
+                let key = KeySpec::parse(spec).expect("default key specs are valid");
+                bindings.insert(key, action);
+            }
+        }
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    /// Build a key map from the defaults plus `[keys]` overrides.
+    pub fn from_config(overrides: &HashMap<String, KeyBinding>) -> Result<Self> {
+        let mut map = Self::default();
+        // Apply in a stable order so conflicting overrides resolve the same way every run.
+        let mut names: Vec<&String> = overrides.keys().collect();
+        names.sort();
+        for name in names {
+            let action = Action::from_name(name).ok_or_else(|| {
+                let known: Vec<&str> = Action::ALL.iter().map(|(_, n)| *n).collect();
+                eyre!("unknown key action {name:?} (expected one of: {})", known.join(", "))
+            })?;
+            map.bindings.retain(|_, a| *a != action);
+            for spec in overrides[name].specs() {
+                map.bindings.insert(KeySpec::parse(spec)?, action);
+            }
+        }
+        Ok(map)
+    }
+
+    pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeySpec::from_event(key)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn defaults_match_builtin_keys() {
+        let map = KeyMap::default();
+        assert_eq!(map.action_for(&press(KeyCode::Char('q'), KeyModifiers::NONE)), Some(Action::Quit));
+        assert_eq!(map.action_for(&press(KeyCode::Down, KeyModifiers::NONE)), Some(Action::Down));
+        // Shift is implied by the character itself.
+        assert_eq!(map.action_for(&press(KeyCode::Char('G'), KeyModifiers::SHIFT)), Some(Action::Bottom));
+        assert_eq!(map.action_for(&press(KeyCode::Char('d'), KeyModifiers::CONTROL)), Some(Action::HalfPageDown));
+        assert_eq!(map.action_for(&press(KeyCode::Char('d'), KeyModifiers::NONE)), None);
+        assert_eq!(map.action_for(&press(KeyCode::Char(' '), KeyModifiers::NONE)), Some(Action::ReplayPause));
+        assert_eq!(map.action_for(&press(KeyCode::Char('='), KeyModifiers::NONE)), Some(Action::ReplayFaster));
+    }
+
+    #[test]
+    fn override_replaces_defaults_for_action() {
+        let mut overrides = HashMap::new();
+        overrides.insert("expand".to_string(), KeyBinding::Many(vec!["o".into(), "enter".into()]));
+        let map = KeyMap::from_config(&overrides).unwrap();
+        assert_eq!(map.action_for(&press(KeyCode::Char('o'), KeyModifiers::NONE)), Some(Action::Expand));
+        assert_eq!(map.action_for(&press(KeyCode::Char('l'), KeyModifiers::NONE)), None);
+        // Other actions keep their defaults.
+        assert_eq!(map.action_for(&press(KeyCode::Char('h'), KeyModifiers::NONE)), Some(Action::Collapse));
+    }
+
+    #[test]
+    fn parse_specs() {
+        let spec = KeySpec::parse("Ctrl-x").unwrap();
+        assert_eq!(spec, KeySpec { code: KeyCode::Char('x'), ctrl: true, alt: false });
+        assert_eq!(KeySpec::parse("pagedown").unwrap().code, KeyCode::PageDown);
+        assert_eq!(KeySpec::parse("-").unwrap().code, KeyCode::Char('-'));
+        assert!(KeySpec::parse("hyper-q").is_err());
+    }
+
+    #[test]
+    fn unknown_action_is_an_error() {
+        let mut overrides = HashMap::new();
+        overrides.insert("teleport".to_string(), KeyBinding::One("t".into()));
+        assert!(KeyMap::from_config(&overrides).is_err());
+    }
+}
@@ -1,6 +1,11 @@
+pub mod anonymize;
 pub mod app;
+pub mod config;
 pub mod coverage;
 pub mod ingest;
+pub mod keymap;
 pub mod parser;
+pub mod replay;
+pub mod rules;
 pub mod symbols;
 pub mod tracking;
@@ -2,6 +2,7 @@
 use ambits::app;
 use ambits::coverage;
 use ambits::ingest;
+use ambits::replay;
 use ambits::symbols;
 use ambits::tracking;
 
@@ -11,17 +12,19 @@ mod serena;
 mod skill;
 mod ui;
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
 use clap::{Parser as ClapParser, Subcommand};
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -30,9 +33,15 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
 use ambits::app::App;
+use ambits::config::Config;
+use ambits::coverage::CountMode;
+use ambits::ingest::claude::LogOptions;
+use ambits::keymap::KeyMap;
 use events::AppEvent;
-use ambits::parser::ParserRegistry;
+use ambits::parser::{looks_generated, source_files, ParseOptions, ParserRegistry, SourceFilter};
+use ambits::symbols::snapshot::ScanSettings;
 use ambits::symbols::{FileSymbols, ProjectTree};
+use ambits::tracking::notes::Notes;
 
 #[derive(ClapParser, Debug)]
 #[command(name = "ambits", about = "Visualize LLM agent context coverage")]
@@ -45,6 +54,10 @@ struct Cli {
     #[arg(short, long)]
     session: Option<String>,
 
+    /// In the TUI, watch several sessions at once (comma-separated IDs), each with its own coverage; the first is shown and S switches.
+    #[arg(long, value_name = "ID", value_delimiter = ',', num_args = 1.., conflicts_with_all = ["session", "replay"])]
+    sessions: Vec<String>,
+
     /// Path to Claude Code log directory (auto-derived if omitted).
     #[arg(long)]
     log_dir: Option<PathBuf>,
@@ -53,22 +66,192 @@ struct Cli {
     #[arg(long)]
     dump: bool,
 
+    /// With --dump, prefix each symbol with its read depth in the session, e.g. [full] or [unseen].
+    #[arg(long, requires = "dump")]
+    with_coverage: bool,
+
     /// Print coverage report to stdout instead of launching TUI.
     #[arg(long)]
     coverage: bool,
 
+    /// Print the session's coverage as JSON lines, one object per file, and exit.
+    #[arg(long)]
+    dump_coverage_jsonl: bool,
+
+    /// Format of the coverage report; implies --coverage.
+    #[arg(long, value_enum)]
+    format: Option<ReportFormat>,
+
     /// Use Serena's LSP symbol cache instead of tree-sitter parsing.
     #[arg(long)]
     serena: bool,
 
+    /// Serena cache directory to read with --serena (default: <project>/.serena/cache).
+    #[arg(long, value_name = "DIR", requires = "serena")]
+    serena_cache: Option<PathBuf>,
+
+    /// With --serena, parse files missing from the Serena cache with tree-sitter.
+    #[arg(long, requires = "serena")]
+    fallback_treesitter: bool,
+
     /// Output directory for event logs. If set, writes processed events to <dir>/<session>.log.
     #[arg(long)]
     log_output: Option<PathBuf>,
 
+    /// Write a coverage treemap (files sized by tokens, colored by coverage) to an SVG file.
+    #[arg(long, value_name = "PATH")]
+    export_svg: Option<PathBuf>,
+
+    /// List all sessions for the project with coverage summaries, then exit.
+    #[arg(long)]
+    list_sessions: bool,
+
+    /// Print every symbol ranked by coverage gap (size × how unread), then exit.
+    #[arg(long)]
+    flat: bool,
+
+    /// Count only leaf symbols (no children) in coverage, ignoring impls/modules/classes.
+    #[arg(long)]
+    leaves_only: bool,
+
+    /// Leave Rust `#[cfg(test)]` items out of coverage counts; they stay in the tree.
+    #[arg(long)]
+    exclude_tests: bool,
+
+    /// In the TUI, start each watched session from the coverage saved in .ambits/ledger-<session>.json,
+    /// reading only log events newer than it, and save it there on quit. Ignored with --replay and --live-only.
+    #[arg(long)]
+    cache_ledger: bool,
+
+    /// Print the project's merkle fingerprint and save it to .ambits/fingerprint.json.
+    #[arg(long)]
+    fingerprint: bool,
+
+    /// Compare against the saved fingerprint, list symbols whose hashes differ, then save the new one.
+    #[arg(long)]
+    since_last: bool,
+
+    /// Ignore comments when hashing symbols, so comment-only edits don't mark them stale.
+    #[arg(long)]
+    ignore_comments: bool,
+
+    /// Only scan N directory levels below the project root (0 = files in the root only).
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Scan files that .gitignore files exclude as well.
+    #[arg(long)]
+    no_ignore: bool,
+
+    /// Add a `(module)` symbol per file for imports and other code outside every top-level symbol.
+    #[arg(long)]
+    module_symbols: bool,
+
+    /// Time each file's parse and print the slowest N (default 20), then exit.
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "20")]
+    profile: Option<usize>,
+
+    /// Scan the project once and print parse time, throughput, peak memory and a per-language breakdown, then exit.
+    #[arg(long, conflicts_with = "profile")]
+    bench: bool,
+
+    /// Save the parsed project tree to a snapshot file for `--load-tree`.
+    #[arg(long, value_name = "PATH")]
+    save_tree: Option<PathBuf>,
+
+    /// Load the project tree from a snapshot instead of parsing; reparses if files changed since.
+    #[arg(long, value_name = "PATH")]
+    load_tree: Option<PathBuf>,
+
+    /// In the TUI, write a timestamped coverage report to DIR after MINUTES without new agent events.
+    #[arg(long, value_name = "MINUTES=DIR")]
+    idle_snapshot: Option<coverage::IdleSnapshot>,
+
+    /// Color theme for the TUI (see --list-themes).
+    #[arg(long, value_name = "NAME", default_value = "default")]
+    theme: String,
+
+    /// Print the built-in color themes with a preview of each, then exit.
+    #[arg(long)]
+    list_themes: bool,
+
+    /// Panel layout: auto (stacked under 100 columns), split or stacked.
+    #[arg(long, value_name = "auto|split|stacked", default_value = "auto")]
+    layout: app::LayoutMode,
+
+    /// In the TUI, skip replaying the session's history and count only events from launch onward.
+    #[arg(long)]
+    live_only: bool,
+
+    /// In the TUI, tick and redraw only every two seconds unless something happens (also applies whenever the terminal loses focus).
+    #[arg(long)]
+    low_power: bool,
+
+    /// In the TUI, play the session's history back at its recorded pace times <speed> (default 1) instead of all at once; Space pauses, +/- change speed.
+    #[arg(long, value_name = "speed", num_args = 0..=1, require_equals = true, default_missing_value = "1", conflicts_with = "live_only", value_parser = replay::parse_speed)]
+    replay: Option<f64>,
+
+    /// Check the session against the [rules] in .ambits.toml; exits 1 if any rule fails.
+    #[arg(long)]
+    ci: bool,
+
+    /// Quiet pre-push check: list symbols edited before being read at full body and exit 1; prints nothing if there are none.
+    #[arg(long)]
+    check: bool,
+
+    /// Print the symbols that changed after the session read them, as an instruction to paste back to the agent.
+    #[arg(long)]
+    stale_report: bool,
+
+    /// Print the session's parsed tool calls as JSON lines, without scanning the project.
+    #[arg(long)]
+    emit_events: bool,
+
+    /// Count fenced code blocks pasted into user messages with a path header (```rust src/app.rs, or a `// src/app.rs` first line) as inferred full reads of that file. Heuristic.
+    #[arg(long)]
+    pasted_code: bool,
+
+    /// In --coverage, --format, --flat, --dump-coverage-jsonl and --emit-events output, number agents (agent-1, agent-2, ...), hash session ids and strip the project root from paths.
+    #[arg(long)]
+    anonymize: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+impl Cli {
+    /// Whether the run ends in the TUI rather than printing a report and exiting.
+    fn launches_tui(&self) -> bool {
+        !(self.dump
+            || self.fingerprint
+            || self.since_last
+            || self.flat
+            || self.dump_coverage_jsonl
+            || self.check
+            || self.stale_report
+            || self.ci
+            || self.coverage
+            || self.format.is_some()
+            || self.export_svg.is_some()
+            || self.list_sessions)
+    }
+}
+
+/// Formats `--coverage` can print.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+    /// Per-file table.
+    Text,
+    /// LCOV tracefile, each symbol's lines hit once it's been seen.
+    Lcov,
+    /// JSON object with per-file counts and project totals.
+    Json,
+    /// CSV with one row per file and a TOTAL row, for spreadsheets.
+    Csv,
+    /// GitHub-flavored Markdown table, for PR descriptions.
+    Md,
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Manage the Claude Code skill for ambit
@@ -105,25 +288,183 @@ fn main() -> Result<()> {
         };
     }
 
+    if cli.list_themes {
+        print_themes();
+        return Ok(());
+    }
+    let palette = ui::colors::theme(&cli.theme).ok_or_else(|| {
+        let names: Vec<&str> = ui::colors::THEMES.iter().map(|(name, _)| *name).collect();
+        color_eyre::eyre::eyre!("unknown theme `{}` (expected one of: {})", cli.theme, names.join(", "))
+    })?;
+    ui::colors::set_palette(palette);
+
+    let launches_tui = cli.launches_tui();
+
     // Original behavior — require --project for all other modes.
     let project = cli.project.ok_or_else(|| {
         color_eyre::eyre::eyre!("--project is required (use `ambits --project <path>`)")
     })?;
-    let project_path = project.canonicalize().unwrap_or(project);
-    let registry = ParserRegistry::new();
-    let project_tree = if cli.serena {
-        serena::scan_project_serena(&project_path)?
-    } else {
-        scan_project(&project_path, &registry)?
+    if !project.is_dir() {
+        bail!(
+            "project path {} does not exist or is not a directory",
+            project.display()
+        );
+    }
+    let project_path = project
+        .canonicalize()
+        .wrap_err_with(|| format!("Failed to resolve project path {}", project.display()))?;
+    let serena_cache = cli.serena.then(|| {
+        cli.serena_cache
+            .clone()
+            .unwrap_or_else(|| serena::default_cache_dir(&project_path))
+    });
+    if let Some(ref cache_dir) = serena_cache {
+        if serena::find_serena_caches(cache_dir).is_empty() {
+            bail!(
+                "--serena was given but no Serena cache exists at {} \
+                 (index the project with Serena first, pass --serena-cache, or drop --serena)",
+                cache_dir.display()
+            );
+        }
+    }
+    let config = Config::load(&project_path)?;
+    let keymap = KeyMap::from_config(&config.keys).wrap_err("Invalid [keys] in .ambits.toml")?;
+    let notes = Notes::load(&project_path)?;
+    let parse_options = ParseOptions {
+        ignore_comments: cli.ignore_comments,
+        module_symbols: cli.module_symbols,
+        follow_renames: config.scan.follow_renames,
+    };
+    let registry = ParserRegistry::with_options(parse_options);
+    let scan_options = ScanOptions {
+        max_depth: cli.max_depth,
+        respect_ignore: !cli.no_ignore,
+        generated_markers: config.scan.generated_markers.clone(),
+        follow_renames: config.scan.follow_renames,
+        progress: io::stderr().is_terminal(),
+    };
+    let log_options = LogOptions { pasted_code: cli.pasted_code };
+    if let Some(top) = cli.profile {
+        return run_profile(&project_path, &registry, &scan_options, top);
+    }
+    if cli.bench {
+        return run_bench(&project_path, &registry, &scan_options);
+    }
+    if cli.emit_events {
+        return run_emit_events(&project_path, &cli.log_dir, &cli.session, log_options, cli.anonymize);
+    }
+    let symbol_source = match (cli.serena, cli.fallback_treesitter) {
+        (false, _) => app::SymbolSource::TreeSitter,
+        (true, false) => app::SymbolSource::Serena,
+        (true, true) => app::SymbolSource::SerenaWithFallback,
+    };
+    let scan_settings = ScanSettings {
+        source: symbol_source,
+        serena_cache: serena_cache.clone(),
+        parse: parse_options,
+        max_depth: scan_options.max_depth,
+        respect_ignore: scan_options.respect_ignore,
+        generated_markers: scan_options.generated_markers.clone(),
+    };
+    let snapshot_tree = match cli.load_tree {
+        Some(ref path) => load_tree_snapshot(path, &project_path, &scan_settings, &registry)?,
+        None => None,
+    };
+    // Serena cache warnings go to stderr for reports, and to the warnings list in the TUI.
+    let mut serena_warnings = Vec::new();
+    let mut project_tree = match snapshot_tree {
+        Some(tree) => tree,
+        None => match serena_cache {
+            Some(ref cache_dir) => {
+                let scan = scan_serena(
+                    &project_path,
+                    cache_dir,
+                    cli.fallback_treesitter.then_some((&registry, &scan_options)),
+                )?;
+                if launches_tui {
+                    serena_warnings = scan.warnings;
+                } else {
+                    for warning in &scan.warnings {
+                        eprintln!("Warning: {warning}");
+                    }
+                }
+                scan.tree
+            }
+            None => scan_project(&project_path, &registry, &scan_options)?,
+        },
     };
+    project_tree.stamp_modified();
+    if let Some(ref path) = cli.save_tree {
+        symbols::snapshot::save(&project_tree, &scan_settings, path)?;
+    }
 
     if cli.dump {
-        dump_tree(&project_path, &project_tree);
+        let ledger = if cli.with_coverage {
+            let (session_id, ledger) = resolve_session_ledger(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options);
+            if session_id.is_none() {
+                bail!("--with-coverage needs a session, but none was found for {}", project_path.display());
+            }
+            Some(ledger)
+        } else {
+            None
+        };
+        dump_tree(&project_path, &project_tree, ledger.as_ref());
         return Ok(());
     }
 
-    if cli.coverage {
-        return run_coverage_report(&project_path, &project_tree, &cli.log_dir, &cli.session);
+    let count_mode = CountMode { leaves_only: cli.leaves_only, exclude_tests: cli.exclude_tests };
+
+    if cli.fingerprint || cli.since_last {
+        return run_fingerprint(&project_path, &project_tree, cli.since_last);
+    }
+
+    let anonymized = |inner: Box<dyn coverage::CoverageFormatter>| -> Box<dyn coverage::CoverageFormatter> {
+        if cli.anonymize {
+            Box::new(ambits::anonymize::AnonymizedFormatter { inner, project_root: project_path.clone() })
+        } else {
+            inner
+        }
+    };
+
+    if cli.flat {
+        let formatter = anonymized(Box::<coverage::FlatFormatter>::default());
+        return run_coverage_report(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options, count_mode, &notes, formatter.as_ref());
+    }
+
+    if cli.dump_coverage_jsonl {
+        return run_dump_coverage_jsonl(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options, count_mode, cli.anonymize);
+    }
+
+    if cli.check {
+        return run_check(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options, count_mode);
+    }
+
+    if cli.stale_report {
+        return run_stale_report(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options);
+    }
+
+    if cli.ci {
+        return run_ci(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options, count_mode, &config.rules);
+    }
+
+    if cli.coverage || cli.format.is_some() {
+        let formatter: Box<dyn coverage::CoverageFormatter> = match cli.format.unwrap_or(ReportFormat::Text) {
+            ReportFormat::Text => Box::new(coverage::TextFormatter::default()),
+            ReportFormat::Lcov => Box::new(coverage::LcovFormatter),
+            ReportFormat::Json => Box::<coverage::JsonFormatter>::default(),
+            ReportFormat::Csv => Box::new(coverage::CsvFormatter),
+            ReportFormat::Md => Box::new(coverage::MarkdownFormatter),
+        };
+        let formatter = anonymized(formatter);
+        return run_coverage_report(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options, count_mode, &notes, formatter.as_ref());
+    }
+
+    if let Some(ref svg_path) = cli.export_svg {
+        return run_export_svg(&project_path, &project_tree, &cli.log_dir, &cli.session, log_options, count_mode, svg_path);
+    }
+
+    if cli.list_sessions {
+        return run_list_sessions(&project_path, &project_tree, &cli.log_dir, log_options, count_mode);
     }
 
     // Resolve log directory and session.
@@ -133,11 +474,15 @@ fn main() -> Result<()> {
     
     let _ = std::fs::write("/tmp/marker-debug.txt", format!("log_dir: {:?}\n", log_dir));
 
-    let session_id = cli.session.or_else(|| {
-        log_dir
-            .as_ref()
-            .and_then(|d| ingest::claude::find_latest_session(d))
-    });
+    // Without --session, take the most recently modified one and say which it was.
+    let auto_session = match (&cli.session, &log_dir) {
+        (None, Some(d)) if cli.sessions.is_empty() => ingest::claude::list_sessions(d).into_iter().next(),
+        _ => None,
+    };
+    let session_id = cli
+        .session
+        .or_else(|| cli.sessions.first().cloned())
+        .or_else(|| auto_session.as_ref().map(|s| s.session_id.clone()));
     
     let _ = std::fs::OpenOptions::new().append(true).open("/tmp/marker-debug.txt")
         .and_then(|mut f| {
@@ -148,7 +493,7 @@ fn main() -> Result<()> {
     // Launch TUI.
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableFocusChange)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
@@ -171,9 +516,29 @@ fn main() -> Result<()> {
 
     let mut app = App::new(project_tree, project_path.clone(), event_log);
     app.session_id = session_id.clone();
+    app.keymap = keymap;
+    app.layout = cli.layout;
+    app.symbol_source = symbol_source;
+    app.confirm_quit = config.tui.confirm_quit;
+    app.large_file_symbols = config.tui.large_file_symbols;
+    app.ledger.notes = notes;
+    app.count_mode = count_mode;
+    app.rebuild_tree_rows();
+    app.add_warnings(serena_warnings);
+    if cli.sessions.len() > 1 {
+        app.watch_sessions(cli.sessions.clone());
+    }
+    // A replay or live-only view starts out empty, so it neither reads nor writes the cache.
+    let cache_ledger = cli.cache_ledger && cli.replay.is_none() && !cli.live_only;
+    // The terminal is in raw mode already, so a bad cache is only reported.
+    if let (true, Err(e)) = (cache_ledger, app.load_ledger_cache()) {
+        app.flash_message(format!("Ignoring ledger cache: {e}"));
+    }
 
-    // Pre-populate the ledger from existing session logs.
-    if let (Some(ref log_dir), Some(ref session_id)) = (&log_dir, &session_id) {
+    // Pre-populate the ledger from existing session logs, unless only live events should count.
+    let mut replayed_events = None;
+    let mut replay_queue = Vec::new();
+    if let (false, Some(ref log_dir), Some(ref session_id)) = (cli.live_only, &log_dir, &session_id) {
         use std::io::Write;
         let mut debug_file = std::fs::OpenOptions::new().create(true).append(true).open("/tmp/marker-debug.txt").unwrap();
         writeln!(debug_file, "=== ENTERING PRE-POPULATION ===").unwrap();
@@ -184,14 +549,21 @@ fn main() -> Result<()> {
                 writeln!(f, "Found {} log files for session {}", log_files.len(), session_id)
             });
         for log_file in &log_files {
-            let events = ingest::claude::parse_log_file(log_file);
+            let events = ingest::claude::parse_session_log_file(log_file, session_id, log_options);
             let _ = std::fs::OpenOptions::new().create(true).append(true).open("/tmp/marker-debug.txt")
                 .and_then(|mut f| {
                     use std::io::Write;
                     writeln!(f, "Parsed {} events from {:?}", events.len(), log_file.file_name())
                 });
+            *replayed_events.get_or_insert(0) += events.len();
+            if cli.replay.is_some() {
+                replay_queue.extend(events);
+                continue;
+            }
             for event in events {
-                app.process_agent_event(event);
+                if !app.ledger.has_applied(&event.timestamp_str) {
+                    app.process_agent_event(event);
+                }
             }
         }
         let _ = std::fs::OpenOptions::new().create(true).append(true).open("/tmp/marker-debug.txt")
@@ -201,8 +573,49 @@ fn main() -> Result<()> {
             });
     }
 
-    let serena_mode = cli.serena;
-    let result = run_tui(&mut terminal, &mut app, &project_path, &log_dir, &session_id, &registry, serena_mode);
+    // Other watched sessions fill their own ledgers from their logs the same way.
+    if let (true, false, Some(ref log_dir)) = (cli.sessions.len() > 1, cli.live_only, &log_dir) {
+        for sid in &cli.sessions[1..] {
+            for log_file in ingest::claude::session_log_files(log_dir, sid) {
+                for event in ingest::claude::parse_session_log_file(&log_file, sid, log_options) {
+                    if !app.session_ledger(sid).is_some_and(|l| l.has_applied(&event.timestamp_str)) {
+                        app.process_session_event(sid, event);
+                    }
+                }
+            }
+        }
+    }
+
+    app.replay = cli.replay.map(|speed| replay::Replay::new(replay_queue, speed));
+
+    let session_notice = auto_session.as_ref().map(|info| {
+        let events = replayed_events.unwrap_or_else(|| {
+            let log_dir = info.path.parent().unwrap_or(Path::new("."));
+            ingest::claude::session_log_files(log_dir, &info.session_id)
+                .iter()
+                .map(|f| ingest::claude::parse_session_log_file(f, &info.session_id, log_options).len())
+                .sum()
+        });
+        auto_session_notice(info, events)
+    });
+    if let Some((text, true)) = &session_notice {
+        app.flash_message(text.clone());
+    }
+
+    let result = run_tui(
+        &mut terminal,
+        &mut app,
+        &project_path,
+        &log_dir,
+        &session_id,
+        log_options,
+        &registry,
+        serena_cache.as_deref(),
+        &scan_options,
+        cli.fallback_treesitter,
+        cli.idle_snapshot.as_ref(),
+        cli.low_power,
+    );
 
     // Flush event log before exiting.
     let _ = std::fs::OpenOptions::new().create(true).append(true).open("/tmp/marker-log-debug.txt")
@@ -216,55 +629,129 @@ fn main() -> Result<()> {
             .and_then(|mut f| { use std::io::Write; writeln!(f, "Flushed event log") });
     }
 
+    let cached = if cache_ledger { app.save_ledger_cache() } else { Ok(()) };
+
     // Restore terminal.
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture, DisableFocusChange)?;
     terminal.show_cursor()?;
 
-    result
+    if let Some((text, suspicious)) = &session_notice {
+        eprintln!("{}{text}", if *suspicious { "Warning: " } else { "" });
+    }
+    if result.is_ok() {
+        println!("{}", app.exit_summary());
+    }
+    result.and(cached)
+}
+
+/// An auto-detected session older than this is probably not the one in use.
+const STALE_SESSION_AGE: Duration = Duration::from_secs(3600);
+
+/// An auto-detected session with fewer events than this is probably not the one in use.
+const FEW_SESSION_EVENTS: usize = 5;
+
+/// One line naming the auto-detected session, its event count and age, and
+/// whether it looks like the wrong one (stale or nearly empty).
+fn auto_session_notice(info: &ingest::claude::SessionInfo, events: usize) -> (String, bool) {
+    let age = info.modified.elapsed().unwrap_or_default();
+    let mut text = format!(
+        "Auto-detected session {} ({events} events, modified {})",
+        info.session_id,
+        format_age(age)
+    );
+    let mut concerns = Vec::new();
+    if age > STALE_SESSION_AGE {
+        concerns.push("was last modified over an hour ago");
+    }
+    if events < FEW_SESSION_EVENTS {
+        concerns.push("has very few events");
+    }
+    if !concerns.is_empty() {
+        text.push_str(&format!(
+            "; it {}. Pass --session to choose another (see --list-sessions)",
+            concerns.join(" and ")
+        ));
+    }
+    (text, !concerns.is_empty())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_tui(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
     project_path: &Path,
     log_dir: &Option<PathBuf>,
     session_id: &Option<String>,
+    log_options: LogOptions,
     registry: &ParserRegistry,
-    serena_mode: bool,
+    serena_cache: Option<&Path>,
+    scan_options: &ScanOptions,
+    treesitter_fallback: bool,
+    idle_snapshot: Option<&coverage::IdleSnapshot>,
+    low_power: bool,
 ) -> Result<()> {
+    // The terminal belongs to the TUI now, so rescans stay quiet.
+    let scan_options = &ScanOptions { progress: false, ..scan_options.clone() };
     let (tx, rx) = mpsc::channel::<AppEvent>();
 
+    // When the last live event arrived; cleared once the idle snapshot for it is written.
+    let mut last_live_event: Option<Instant> = None;
+
     // Spawn key reader thread.
     events::spawn_key_reader(tx.clone());
 
-    // Spawn tick timer (250ms).
-    events::spawn_tick_timer(tx.clone(), Duration::from_millis(250));
+    // Spawn tick timer (250ms, or IDLE_TICK while idle).
+    let idle = Arc::new(AtomicBool::new(low_power));
+    events::spawn_tick_timer(tx.clone(), Duration::from_millis(250), IDLE_TICK, Arc::clone(&idle));
 
-    // Set up file watcher for project source changes.
+    // Set up file watcher for project source changes; the event loop keeps
+    // those of files a parser reads.
     let tx_file = tx.clone();
     let mut _project_watcher = notify::recommended_watcher(move |res: Result<NotifyEvent, notify::Error>| {
         if let Ok(event) = res {
             if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
                 for path in event.paths {
-                    if path.extension().and_then(|e| e.to_str()) == Some("rs") {
-                        let _ = tx_file.send(AppEvent::FileChanged(path));
-                    }
+                    let _ = tx_file.send(AppEvent::FileChanged(path));
                 }
             }
         }
     })?;
     _project_watcher.watch(project_path, RecursiveMode::Recursive)?;
 
-    // Set up log file tailer.
-    let mut log_tailer = if let (Some(ref ld), Some(ref sid)) = (log_dir, session_id) {
-        let files = ingest::claude::session_log_files(ld, sid);
-        Some(ingest::claude::LogTailer::new(files))
-    } else {
-        None
+    // Edits to a symlinked file's target outside the project don't show up under
+    // the link, so watch the targets' directories as well.
+    let symlinks = symlink_targets(project_path, &app.project_tree);
+    let target_dirs: HashSet<&Path> = symlinks
+        .keys()
+        .filter_map(|target| target.parent())
+        .filter(|dir| !dir.starts_with(project_path))
+        .collect();
+    for dir in target_dirs {
+        let _ = _project_watcher.watch(dir, RecursiveMode::NonRecursive);
+    }
+    let source_filter = SourceFilter::new(project_path, scan_options.max_depth, scan_options.respect_ignore);
+
+    // Set up log file tailers, one per watched session.
+    // While replaying, live events wait in the logs until the replay is done.
+    let watched = if app.sessions.is_empty() { session_id.iter().cloned().collect() } else { app.sessions.clone() };
+    let mut holding_live = app.replay.is_some();
+    let mut log_tailers: Vec<(String, ingest::claude::LogTailer)> = match log_dir {
+        Some(ld) => watched
+            .into_iter()
+            .map(|sid| {
+                let files = ingest::claude::session_log_files(ld, &sid);
+                let tailer = ingest::claude::LogTailer::new(files).with_session(&sid).with_options(log_options);
+                (sid, tailer)
+            })
+            .collect(),
+        _ => Vec::new(),
     };
 
-    // Set up file watcher for log directory (to detect new agent files).
+    // Set up file watcher for log directory. Log files are read only when it
+    // reports them changed, and the session's file list is only re-scanned when
+    // a JSONL file the tailer doesn't know yet shows up (e.g. a new agent under
+    // `<session>/subagents/`).
     let tx_log = tx.clone();
     let mut _log_watcher = if let Some(ref ld) = log_dir {
         let ld_clone = ld.clone();
@@ -273,22 +760,21 @@ fn run_tui(
                 if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
                     for path in event.paths {
                         if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
-                            // Signal that log files changed — we'll poll in the tick handler.
-                            let _ = tx_log.send(AppEvent::Tick);
+                            let _ = tx_log.send(AppEvent::LogFileChanged(path));
                         }
                     }
                 }
             }
         })?;
-        watcher.watch(&ld_clone, RecursiveMode::NonRecursive)?;
+        watcher.watch(&ld_clone, RecursiveMode::Recursive)?;
         Some(watcher)
     } else {
         None
     };
 
     // Track Serena .pkl file modification times for live cache rebuilds.
-    let mut pkl_mtimes: Vec<(PathBuf, std::time::SystemTime)> = if serena_mode {
-        serena::find_serena_caches(project_path)
+    let mut pkl_mtimes: Vec<(PathBuf, std::time::SystemTime)> = if let Some(cache_dir) = serena_cache {
+        serena::find_serena_caches(cache_dir)
             .into_iter()
             .filter_map(|p| fs::metadata(&p).ok()?.modified().ok().map(|t| (p, t)))
             .collect()
@@ -296,58 +782,91 @@ fn run_tui(
         Vec::new()
     };
 
+    // Idle (unfocused, or always with --low-power): ticks slow down, the loop
+    // blocks longer, and ticks only redraw when they changed something.
+    let mut focused = true;
+    let mut redraw = true;
     loop {
-        terminal.draw(|f| ui::render(f, app))?;
+        let idle_now = low_power || !focused;
+        idle.store(idle_now, Ordering::Relaxed);
+        if redraw {
+            app.now = Some(chrono::Local::now());
+            terminal.draw(|f| ui::render(f, app))?;
+        }
 
-        match rx.recv_timeout(Duration::from_millis(50)) {
+        let before = redraw_stamp(app);
+        let received = rx.recv_timeout(if idle_now { IDLE_TICK } else { Duration::from_millis(50) });
+        let quiet = matches!(received, Ok(AppEvent::Tick | AppEvent::LogFileChanged(_)) | Err(mpsc::RecvTimeoutError::Timeout))
+            || matches!(&received, Ok(AppEvent::FileChanged(path)) if registry.parser_for(path).is_none());
+        match received {
             Ok(AppEvent::Key(key)) => app.handle_key(key),
+            Ok(AppEvent::Focus(gained)) => focused = gained,
             Ok(AppEvent::Mouse(mouse)) => app.handle_mouse(mouse),
-            Ok(AppEvent::FileChanged(path)) => {
-                // Re-parse the changed file and update the project tree.
-                if let Ok(rel) = path.strip_prefix(project_path) {
-                    if let Some(parser) = registry.parser_for(&path) {
-                        if let Ok(source) = fs::read_to_string(&path) {
-                            if let Ok(new_file) = parser.parse_file(rel, &source) {
-                                // Replace the file in the project tree.
-                                let rel_str = rel.to_string_lossy().to_string();
-                                if let Some(existing) = app.project_tree.files.iter_mut().find(|f| {
-                                    f.file_path.to_string_lossy() == rel_str
-                                }) {
-                                    // Mark symbols as stale if their hashes changed.
-                                    mark_stale_symbols(&existing.symbols, &new_file.symbols, &mut app.ledger);
-                                    *existing = new_file;
-                                } else {
-                                    app.project_tree.files.push(new_file);
-                                    app.project_tree.files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
-                                }
-                                app.rebuild_tree_rows();
-                            }
-                        }
-                    }
+            Ok(AppEvent::Resize(w, h)) => {
+                // Resize and clear the back buffer so the next draw repaints every cell.
+                terminal.resize(ratatui::layout::Rect::new(0, 0, w, h))?;
+            }
+            Ok(AppEvent::FileChanged(path)) if registry.parser_for(&path).is_some() => {
+                // A change to a symlink target is a change to every in-tree link to it.
+                let links = symlinks.get(&path).cloned().unwrap_or_default();
+                for path in std::iter::once(path).chain(links) {
+                    reparse_changed_file(app, &path, project_path, registry, scan_options, &source_filter);
                 }
             }
+            // Build output and other files no parser reads.
+            Ok(AppEvent::FileChanged(_)) => {}
             Ok(AppEvent::AgentEvent(event)) => {
-                app.process_agent_event(event);
+                app.process_agent_event(*event);
+            }
+            Ok(AppEvent::LogFileChanged(path)) => {
+                if let (false, Some(ld)) = (holding_live, log_dir) {
+                    for (sid, tailer) in log_tailers.iter_mut() {
+                        // Only the file that changed is read. A file the tailer doesn't
+                        // know but that may be the session's (a new agent, or a path the
+                        // watcher spells through a symlink) re-lists the session and
+                        // reads every file's new lines.
+                        let events = if tailer.is_tailing(&path) {
+                            tailer.read_file_events(&path)
+                        } else if ingest::claude::may_belong_to_session(ld, sid, &path) {
+                            tailer.refresh(ld);
+                            tailer.read_new_events()
+                        } else {
+                            Vec::new()
+                        };
+                        if !events.is_empty() {
+                            last_live_event = Some(Instant::now());
+                        }
+                        for event in events {
+                            app.process_session_event(sid, event);
+                        }
+                    }
+                }
             }
             Ok(AppEvent::Tick) => {
-                // Poll log tailer for new events.
-                if let Some(ref mut tailer) = log_tailer {
-                    // Check for new agent files in the log directory.
-                    if let (Some(ref ld), Some(ref sid)) = (log_dir, session_id) {
-                        let current_files = ingest::claude::session_log_files(ld, sid);
-                        for f in current_files {
-                            tailer.add_file(f);
+                app.advance_replay(Instant::now());
+                // Catch up on what the session logged while the replay ran.
+                if let (true, Some(ld)) = (holding_live && app.replay.as_ref().is_none_or(|r| r.is_finished()), log_dir) {
+                    holding_live = false;
+                    for (sid, tailer) in log_tailers.iter_mut() {
+                        tailer.refresh(ld);
+                        for event in tailer.read_new_events() {
+                            app.process_session_event(sid, event);
                         }
                     }
-
-                    let new_events = tailer.read_new_events();
-                    for event in new_events {
-                        app.process_agent_event(event);
+                }
+                app.sample_coverage(Instant::now());
+                if let (Some(snap), Some(last)) = (idle_snapshot, last_live_event) {
+                    if last.elapsed() >= snap.idle_after {
+                        last_live_event = None;
+                        match write_idle_snapshot(app, snap) {
+                            Ok(path) => app.flash_message(format!("Agent idle; wrote {}", path.display())),
+                            Err(e) => app.flash_message(format!("Idle snapshot failed: {e}")),
+                        }
                     }
                 }
 
                 // Check if Serena cache files changed.
-                if serena_mode {
+                if let Some(cache_dir) = serena_cache {
                     let mut changed = false;
                     for (path, mtime) in pkl_mtimes.iter_mut() {
                         if let Ok(new_mtime) = fs::metadata(&*path).and_then(|m| m.modified()) {
@@ -358,16 +877,27 @@ fn run_tui(
                         }
                     }
                     if changed {
-                        if let Ok(new_tree) = serena::scan_project_serena(project_path) {
-                            // Collect old hashes, then check staleness against new tree.
-                            let mut old_map = std::collections::HashMap::new();
-                            for file in &app.project_tree.files {
-                                collect_symbol_hashes(&file.symbols, &mut old_map);
-                            }
+                        if let Ok(serena::SerenaScan { tree: mut new_tree, warnings }) = scan_serena(
+                            project_path,
+                            cache_dir,
+                            treesitter_fallback.then_some((registry, scan_options)),
+                        ) {
+                            new_tree.stamp_modified();
+                            // Keep coverage for symbols Serena still reports unchanged, in
+                            // every session. The diff is between the trees, so any ledger's will do.
+                            let diff = {
+                                let (old_tree, ledgers) = app.ledgers_mut();
+                                ledgers.map(|l| l.reconcile(old_tree, &new_tree)).last()
+                            };
                             app.project_tree = new_tree;
-                            for file in &app.project_tree.files {
-                                check_staleness(&file.symbols, &old_map, &mut app.ledger);
+                            app.tree_changed();
+                            if let Some(diff) = diff.filter(|d| *d != tracking::TreeDiff::default()) {
+                                app.flash_message(format!(
+                                    "Serena cache reloaded: {} changed, {} added, {} removed",
+                                    diff.changed, diff.added, diff.removed
+                                ));
                             }
+                            app.add_warnings(warnings);
                             app.rebuild_tree_rows();
                         }
                     }
@@ -376,6 +906,7 @@ fn run_tui(
             Err(mpsc::RecvTimeoutError::Timeout) => {}
             Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
+        redraw = !idle_now || !quiet || redraw_stamp(app) != before;
 
         if app.should_quit {
             break;
@@ -385,6 +916,78 @@ fn run_tui(
     Ok(())
 }
 
+/// Re-parse a changed source file and replace its entry in the project tree.
+fn reparse_changed_file(
+    app: &mut App,
+    path: &Path,
+    project_path: &Path,
+    registry: &ParserRegistry,
+    scan_options: &ScanOptions,
+    source_filter: &SourceFilter,
+) {
+    if let Ok(rel) = path.strip_prefix(project_path) {
+        // Files the scan left out stay out, unless they were in the tree already.
+        if app.project_tree.file(rel).is_none() && source_filter.excludes(path) {
+            return;
+        }
+        if let Some(parser) = registry.parser_for(path) {
+            let source = fs::read_to_string(path)
+                .ok()
+                .filter(|s| !looks_generated(s, &scan_options.generated_markers));
+            if let Some(source) = source {
+                if let Ok(mut new_file) = parser.parse_file(rel, &source) {
+                    new_file.modified = symbols::modified_time(path);
+                    {
+                        let (tree, ledgers) = app.ledgers_mut();
+                        if let Some(existing) = tree.file(rel) {
+                            // Mark symbols as stale in every session if their hashes changed.
+                            for ledger in ledgers {
+                                mark_stale_symbols(&existing.symbols, &new_file.symbols, ledger);
+                                if scan_options.follow_renames {
+                                    ledger.remap_renamed(&existing.symbols, &new_file.symbols);
+                                }
+                            }
+                        }
+                    }
+                    app.project_tree.replace_file(new_file);
+                    app.note_file_changed(rel);
+                    app.tree_changed();
+                    app.rebuild_tree_rows();
+                }
+            }
+        }
+    }
+}
+
+/// Tick interval and longest wait between loop iterations while idle.
+const IDLE_TICK: Duration = Duration::from_secs(2);
+
+/// Changes to any of these mean an idle tick has something new to draw;
+/// the minute keeps the status-bar clock current.
+fn redraw_stamp(app: &App) -> (u64, usize, usize, bool, u32) {
+    use chrono::Timelike;
+    (
+        app.ledger.version(),
+        app.activity.len(),
+        app.tree_rows.len(),
+        app.active_flash().is_some(),
+        chrono::Local::now().minute(),
+    )
+}
+
+/// Write the TUI's current coverage to a timestamped report in the snapshot dir.
+fn write_idle_snapshot(app: &App, snap: &coverage::IdleSnapshot) -> Result<PathBuf> {
+    use coverage::CoverageFormatter;
+
+    let mut report = coverage::CoverageReport::from_project_with(&app.project_tree, &app.ledger, app.count_mode);
+    report.session_id = app.session_id.clone();
+    fs::create_dir_all(&snap.dir).wrap_err_with(|| format!("Failed to create {}", snap.dir.display()))?;
+    let path = snap.path_for(chrono::Local::now());
+    fs::write(&path, coverage::TextFormatter::default().format(&report))
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
 /// Compare old and new symbols and mark changed ones as stale in the ledger.
 fn mark_stale_symbols(
     old_symbols: &[symbols::SymbolNode],
@@ -403,10 +1006,9 @@ fn collect_symbol_hashes(
     symbols: &[symbols::SymbolNode],
     map: &mut std::collections::HashMap<String, [u8; 32]>,
 ) {
-    for sym in symbols {
+    symbols::visit_symbols(symbols, |sym| {
         map.insert(sym.id.clone(), sym.content_hash);
-        collect_symbol_hashes(&sym.children, map);
-    }
+    });
 }
 
 fn check_staleness(
@@ -414,95 +1016,420 @@ fn check_staleness(
     old_map: &std::collections::HashMap<String, [u8; 32]>,
     ledger: &mut tracking::ContextLedger,
 ) {
-    for sym in symbols {
+    symbols::visit_symbols(symbols, |sym| {
         if let Some(old_hash) = old_map.get(&sym.id) {
             if *old_hash != sym.content_hash {
                 ledger.mark_stale_if_changed(&sym.id, sym.content_hash);
             }
         }
-        check_staleness(&sym.children, old_map, ledger);
-    }
+    });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_coverage_report(
     project_path: &Path,
     project_tree: &ProjectTree,
     log_dir_opt: &Option<PathBuf>,
     session_opt: &Option<String>,
+    log_options: LogOptions,
+    count_mode: CountMode,
+    notes: &Notes,
+    formatter: &dyn coverage::CoverageFormatter,
 ) -> Result<()> {
-    use coverage::{CoverageFormatter, CoverageReport, TextFormatter};
-    use tracking::ContextLedger;
+    let report = session_coverage_report(project_path, project_tree, log_dir_opt, session_opt, log_options, count_mode, notes);
+    print!("{}", formatter.format(&report));
+
+    Ok(())
+}
+
+fn run_export_svg(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+    count_mode: CountMode,
+    svg_path: &Path,
+) -> Result<()> {
+    use coverage::{CoverageFormatter, SvgTreemapFormatter};
+
+    let report = session_coverage_report(project_path, project_tree, log_dir_opt, session_opt, log_options, count_mode, &Notes::default());
+    let svg = SvgTreemapFormatter::default().format(&report);
+    fs::write(svg_path, svg)
+        .wrap_err_with(|| format!("Failed to write {}", svg_path.display()))?;
+    println!("Wrote coverage treemap to {}", svg_path.display());
+
+    Ok(())
+}
 
-    // 1. Resolve log directory
+/// Resolve the log directory and session (auto-detecting if not provided),
+/// replay the session into a ledger and build a coverage report from it.
+fn session_coverage_report(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+    count_mode: CountMode,
+    notes: &Notes,
+) -> coverage::CoverageReport {
+    let (session_id, mut ledger) = resolve_session_ledger(project_path, project_tree, log_dir_opt, session_opt, log_options);
+    ledger.notes = notes.clone();
+
+    let mut report = coverage::CoverageReport::from_project_with(project_tree, &ledger, count_mode);
+    report.session_id = session_id;
+    report
+}
+
+/// Resolve the log directory and session (auto-detecting if not provided) and
+/// replay it. The ledger is empty when no session is found.
+fn resolve_session_ledger(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+) -> (Option<String>, tracking::ContextLedger) {
     let log_dir = log_dir_opt
         .clone()
         .or_else(|| ingest::claude::log_dir_for_project(project_path));
 
-    // 2. Find session (auto-detect if not provided)
     let session_id = session_opt.clone().or_else(|| {
         log_dir
             .as_ref()
             .and_then(|d| ingest::claude::find_latest_session(d))
     });
 
-    // 3. Build ledger from session logs
-    let mut ledger = ContextLedger::new();
-    if let (Some(ref log_dir), Some(ref sid)) = (&log_dir, &session_id) {
-        let log_files = ingest::claude::session_log_files(log_dir, sid);
-        for log_file in &log_files {
-            let events = ingest::claude::parse_log_file(log_file);
-            for event in events {
-                if let Some(ref file_path) = event.file_path {
-                    // Normalize the tool call path
-                    let tool_rel = app::normalize_tool_path(file_path, project_path);
-
-                    for file in &project_tree.files {
-                        if file.file_path == tool_rel {
-                            if event.target_symbol.is_some() || event.target_lines.is_some() {
-                                app::mark_targeted_symbols(&file.symbols, &event, &mut ledger);
-                            } else {
-                                app::mark_file_symbols(&file.symbols, &event, &mut ledger);
-                            }
-                        }
-                    }
-                }
+    let ledger = match (&log_dir, &session_id) {
+        (Some(log_dir), Some(sid)) => build_session_ledger(project_path, project_tree, log_dir, sid, log_options).0,
+        _ => tracking::ContextLedger::new(),
+    };
+    (session_id, ledger)
+}
+
+/// Check the session against the `[rules]` in `.ambits.toml`.
+/// Prints one line per rule and exits with status 1 if any fail.
+fn run_ci(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+    count_mode: CountMode,
+    rules: &ambits::rules::Rules,
+) -> Result<()> {
+    if rules.is_empty() && project_tree.files.iter().all(|f| f.required.is_none()) {
+        bail!("--ci needs at least one rule in the [rules] section of .ambits.toml or an `ambits: require` comment");
+    }
+    let (session_id, mut ledger) = resolve_session_ledger(project_path, project_tree, log_dir_opt, session_opt, log_options);
+    let Some(session_id) = session_id else {
+        bail!("No session found to check for {}", project_path.display());
+    };
+
+    let outcomes = rules.evaluate_session(project_tree, &mut ledger, count_mode);
+    println!("Rules (session: {session_id})");
+    print!("{}", ambits::rules::format_outcomes(&outcomes));
+    if outcomes.iter().any(|o| !o.passed) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Print each symbol the session edited before reading it at full body, one
+/// id per line, then each file short of its `ambits: require` comment, and exit 1
+/// if there are any. Silent when there is nothing to report.
+fn run_check(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+    count_mode: CountMode,
+) -> Result<()> {
+    let (session_id, ledger) = resolve_session_ledger(project_path, project_tree, log_dir_opt, session_opt, log_options);
+    if session_id.is_none() {
+        bail!("No session found to check for {}", project_path.display());
+    }
+    let unread = ambits::rules::edited_before_read(&ledger);
+    let unmet = ambits::rules::unmet_file_requirements(project_tree, &ledger, count_mode);
+    if unread.is_empty() && unmet.is_empty() {
+        return Ok(());
+    }
+    for id in unread {
+        println!("{id}");
+    }
+    for message in unmet {
+        println!("{message}");
+    }
+    std::process::exit(1);
+}
+
+/// Print the session's per-file coverage as one JSON object per line, sorted
+/// by path.
+fn run_dump_coverage_jsonl(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+    count_mode: CountMode,
+    anonymize: bool,
+) -> Result<()> {
+    let mut report = session_coverage_report(project_path, project_tree, log_dir_opt, session_opt, log_options, count_mode, &Notes::default());
+    if report.session_id.is_none() {
+        bail!("No session found for {}", project_path.display());
+    }
+    if anonymize {
+        ambits::anonymize::Anonymizer::new(project_path).report(&mut report);
+    }
+    report.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for file in &report.files {
+        serde_json::to_writer(&mut out, file)?;
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Print the session's stale symbols as an instruction for the agent. Without a
+/// file watcher, a symbol counts as stale when its file was modified after the
+/// session last read it.
+fn run_stale_report(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+) -> Result<()> {
+    let (session_id, mut ledger) = resolve_session_ledger(project_path, project_tree, log_dir_opt, session_opt, log_options);
+    if session_id.is_none() {
+        bail!("No session found for {}", project_path.display());
+    }
+    ledger.mark_modified_files_stale(project_tree);
+    match coverage::stale_report(project_tree, &ledger) {
+        Some(report) => print!("{report}"),
+        None => println!("No stale symbols."),
+    }
+    Ok(())
+}
+
+/// Replay a session's logs into a fresh ledger.
+/// Returns the ledger and the number of tool call events parsed.
+fn build_session_ledger(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir: &Path,
+    session_id: &str,
+    log_options: LogOptions,
+) -> (tracking::ContextLedger, usize) {
+    let mut ledger = tracking::ContextLedger::new();
+    let index = symbols::index::SymbolIndex::new(project_tree);
+    let mut event_count = 0;
+    let log_files = ingest::claude::session_log_files(log_dir, session_id);
+    for log_file in &log_files {
+        let events = ingest::claude::parse_session_log_file(log_file, session_id, log_options);
+        event_count += events.len();
+        for event in events {
+            app::apply_event(project_tree, &index, project_path, &event, &mut ledger);
+        }
+    }
+    (ledger, event_count)
+}
+
+fn run_list_sessions(
+    project_path: &Path,
+    project_tree: &ProjectTree,
+    log_dir_opt: &Option<PathBuf>,
+    log_options: LogOptions,
+    count_mode: CountMode,
+) -> Result<()> {
+    use coverage::CoverageReport;
+
+    let log_dir = log_dir_opt
+        .clone()
+        .or_else(|| ingest::claude::log_dir_for_project(project_path))
+        .ok_or_else(|| {
+            color_eyre::eyre::eyre!("No Claude Code log directory found for {}", project_path.display())
+        })?;
+
+    let sessions = ingest::claude::list_sessions(&log_dir);
+    if sessions.is_empty() {
+        println!("No sessions found in {}", log_dir.display());
+        return Ok(());
+    }
+
+    println!("Sessions in {}", log_dir.display());
+    println!(
+        "{:<36} {:>10} {:>7} {:>6} {:>6}",
+        "Session", "Modified", "Events", "Seen%", "Full%"
+    );
+    for session in &sessions {
+        let (ledger, event_count) =
+            build_session_ledger(project_path, project_tree, &log_dir, &session.session_id, log_options);
+        let report = CoverageReport::from_project_with(project_tree, &ledger, count_mode);
+        let age = session.modified.elapsed().unwrap_or_default();
+        println!(
+            "{:<36} {:>10} {:>7} {:>5.0}% {:>5.0}%",
+            session.session_id,
+            format_age(age),
+            event_count,
+            report.total_seen_percent(),
+            report.total_full_percent(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Print each built-in theme's name followed by a colored swatch for every
+/// read depth, for `--list-themes`.
+fn print_themes() {
+    use crossterm::style::Stylize;
+    for (name, p) in ui::colors::THEMES {
+        let swatches = [
+            ("unseen", p.depth_unseen),
+            ("name", p.depth_name_only),
+            ("overview", p.depth_overview),
+            ("signature", p.depth_signature),
+            ("full", p.depth_full_body),
+            ("stale", p.depth_stale),
+        ];
+        print!("{name:<14}");
+        for (label, color) in swatches {
+            print!(" {}", format!("\u{25a0} {label}").with(color.into()));
+        }
+        println!();
+    }
+}
+
+/// Print every tool call in the session as one JSON object per line.
+fn run_emit_events(
+    project_path: &Path,
+    log_dir_opt: &Option<PathBuf>,
+    session_opt: &Option<String>,
+    log_options: LogOptions,
+    anonymize: bool,
+) -> Result<()> {
+    use std::io::Write;
+
+    let log_dir = log_dir_opt
+        .clone()
+        .or_else(|| ingest::claude::log_dir_for_project(project_path))
+        .ok_or_else(|| eyre!("No Claude Code log directory found for {}", project_path.display()))?;
+    let session_id = session_opt
+        .clone()
+        .or_else(|| ingest::claude::find_latest_session(&log_dir))
+        .ok_or_else(|| eyre!("No sessions found in {}", log_dir.display()))?;
+
+    let mut anonymizer = anonymize.then(|| ambits::anonymize::Anonymizer::new(project_path));
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for log_file in ingest::claude::session_log_files(&log_dir, &session_id) {
+        for mut event in ingest::claude::parse_session_log_file(&log_file, &session_id, log_options) {
+            if let Some(ref mut anonymizer) = anonymizer {
+                anonymizer.event(&mut event);
             }
+            serde_json::to_writer(&mut out, &event)?;
+            writeln!(out)?;
         }
     }
+    out.flush()?;
+    Ok(())
+}
 
-    // 4. Generate report
-    let mut report = CoverageReport::from_project(project_tree, &ledger);
-    report.session_id = session_id;
+/// Saved project fingerprint, used by `--since-last`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Fingerprint {
+    root: String,
+    symbols: std::collections::BTreeMap<String, String>,
+}
 
-    // 5. Format and print
-    let formatter = TextFormatter::default();
-    print!("{}", formatter.format(&report));
+fn run_fingerprint(project_path: &Path, project_tree: &ProjectTree, since_last: bool) -> Result<()> {
+    use ambits::symbols::merkle::to_hex;
 
+    let current = Fingerprint {
+        root: to_hex(&project_tree.merkle_root()),
+        symbols: project_tree
+            .symbol_hashes()
+            .iter()
+            .map(|(id, hash)| (id.clone(), to_hex(hash)))
+            .collect(),
+    };
+    let path = project_path.join(".ambits").join("fingerprint.json");
+
+    if since_last {
+        let previous: Option<Fingerprint> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok());
+        match previous {
+            None => println!("No saved fingerprint at {}; saving one now.", path.display()),
+            Some(prev) if prev.root == current.root => println!("Unchanged since last fingerprint."),
+            Some(prev) => {
+                for (id, hash) in &current.symbols {
+                    match prev.symbols.get(id) {
+                        None => println!("  added    {id}"),
+                        Some(old) if old != hash => println!("  changed  {id}"),
+                        _ => {}
+                    }
+                }
+                for id in prev.symbols.keys().filter(|id| !current.symbols.contains_key(*id)) {
+                    println!("  removed  {id}");
+                }
+            }
+        }
+    }
+    println!("{}", current.root);
+
+    fs::create_dir_all(path.parent().unwrap())
+        .wrap_err_with(|| format!("Failed to create {}", path.parent().unwrap().display()))?;
+    fs::write(&path, serde_json::to_string_pretty(&current)?)
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
     Ok(())
 }
 
-fn dump_tree(root: &Path, project_tree: &ProjectTree) {
+/// Format a duration as a coarse "N units ago" string.
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86_400)
+    }
+}
+
+/// Print the symbol tree; with a ledger, each symbol starts with its read depth.
+fn dump_tree(root: &Path, project_tree: &ProjectTree, ledger: Option<&tracking::ContextLedger>) {
     println!(
         "Project: {} ({} files, {} symbols)",
         root.display(),
         project_tree.total_files(),
         project_tree.total_symbols(),
     );
+    for lang in project_tree.language_stats() {
+        println!("  {:<8} {} files, {} symbols", lang.label(), lang.files, lang.symbols);
+    }
     println!();
 
     for file in &project_tree.files {
         println!("  {} ({} lines)", file.file_path.display(), file.total_lines);
         for sym in &file.symbols {
-            print_symbol(sym, 4);
+            print_symbol(sym, 4, ledger);
         }
     }
 }
 
-fn print_symbol(sym: &symbols::SymbolNode, indent: usize) {
+fn print_symbol(sym: &symbols::SymbolNode, indent: usize, ledger: Option<&tracking::ContextLedger>) {
     let pad = " ".repeat(indent);
+    let marker = ledger.map_or(String::new(), |l| format!("{:<12}", format!("[{}]", l.depth_of(&sym.id))));
     println!(
-        "{}{} {} [L{}-{}] (~{} tokens)",
+        "{}{}{} {} [L{}-{}] (~{} tokens)",
         pad,
+        marker,
         sym.label,
         sym.name,
         sym.line_range.start,
@@ -510,13 +1437,160 @@ fn print_symbol(sym: &symbols::SymbolNode, indent: usize) {
         sym.estimated_tokens,
     );
     for child in &sym.children {
-        print_symbol(child, indent + 2);
+        print_symbol(child, indent + 2, ledger);
+    }
+}
+
+/// Load a `--load-tree` snapshot, or None (after saying why) if it is for
+/// another project, was scanned with other settings, or any of its files
+/// changed or were added since, so the caller reparses.
+fn load_tree_snapshot(
+    path: &Path,
+    project_path: &Path,
+    settings: &ScanSettings,
+    registry: &ParserRegistry,
+) -> Result<Option<ProjectTree>> {
+    let snapshot = symbols::snapshot::TreeSnapshot::load(path)?;
+    if snapshot.tree.root != project_path {
+        eprintln!(
+            "Tree snapshot {} is for {}, not {}; reparsing",
+            path.display(),
+            snapshot.tree.root.display(),
+            project_path.display()
+        );
+        return Ok(None);
+    }
+    if snapshot.settings != *settings {
+        eprintln!("Tree snapshot {} was saved with other scan settings; reparsing", path.display());
+        return Ok(None);
+    }
+    // A Serena-only tree holds just what the cache has, so added files don't show up in it.
+    let scanned: Vec<PathBuf> = if settings.source == app::SymbolSource::Serena {
+        Vec::new()
+    } else {
+        source_files(project_path, registry, settings.max_depth, settings.respect_ignore)
+            .into_iter()
+            .filter_map(|p| p.strip_prefix(project_path).ok().map(Path::to_path_buf))
+            .collect()
+    };
+    let changed = snapshot.changed_files(&scanned);
+    if !changed.is_empty() {
+        eprintln!(
+            "{} file(s) changed since tree snapshot {} (first: {}); reparsing",
+            changed.len(),
+            path.display(),
+            changed[0].display()
+        );
+        return Ok(None);
     }
+    Ok(Some(snapshot.tree))
+}
+
+/// How long one file took to parse, for `--profile`.
+struct ParseTiming {
+    path: PathBuf,
+    elapsed: Duration,
+    symbols: usize,
+    bytes: usize,
+}
+
+/// Which directories a tree-sitter scan descends into and which files it keeps.
+#[derive(Debug, Clone, Default)]
+struct ScanOptions {
+    max_depth: Option<usize>,
+    /// Skip what `.gitignore` files exclude.
+    respect_ignore: bool,
+    /// Files with one of these in their first lines are skipped as generated.
+    generated_markers: Vec<String>,
+    /// Move coverage across renames when a watched file is re-parsed.
+    follow_renames: bool,
+    /// Keep a progress line on stderr while scanning.
+    progress: bool,
+}
+
+/// Spinner, file count and current file on one stderr line during a scan,
+/// redrawn at most every 100ms so it doesn't slow the scan down.
+#[derive(Default)]
+struct ScanProgress {
+    parsed: usize,
+    frame: usize,
+    last_draw: Option<Instant>,
 }
 
-fn scan_project(root: &Path, registry: &ParserRegistry) -> Result<ProjectTree> {
+impl ScanProgress {
+    /// Longest tail of the current path shown, so the line never wraps.
+    const MAX_PATH: usize = 60;
+
+    fn parsing(&mut self, path: &Path) {
+        let now = Instant::now();
+        if self.last_draw.is_some_and(|at| now - at < Duration::from_millis(100)) {
+            return;
+        }
+        self.last_draw = Some(now);
+        let spinner = ['|', '/', '-', '\\'][self.frame % 4];
+        self.frame += 1;
+        let path = path.display().to_string();
+        let skip = path.chars().count().saturating_sub(Self::MAX_PATH);
+        let path: String = path.chars().skip(skip).collect();
+        let ellipsis = if skip > 0 { "\u{2026}" } else { "" };
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\r\x1b[2K{spinner} Scanning: {} files parsed  {ellipsis}{path}", self.parsed);
+        let _ = stderr.flush();
+    }
+
+    /// Clear the line, if anything was drawn on it.
+    fn finish(&self) {
+        if self.last_draw.is_some() {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+/// Scan with Serena, filling files its cache lacks from a tree-sitter scan
+/// when `fallback` carries the registry and scan options to use.
+fn scan_serena(
+    root: &Path,
+    cache_dir: &Path,
+    fallback: Option<(&ParserRegistry, &ScanOptions)>,
+) -> Result<serena::SerenaScan> {
+    let fallback = fallback
+        .map(|(registry, options)| scan_project(root, registry, options))
+        .transpose()?;
+    serena::scan_project_serena(root, cache_dir, fallback)
+}
+
+/// In-tree paths of the project's files that resolve somewhere else (the file
+/// or a directory above it is a symlink), keyed by their canonical target.
+fn symlink_targets(root: &Path, tree: &ProjectTree) -> HashMap<PathBuf, Vec<PathBuf>> {
+    let mut links: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for file in &tree.files {
+        let path = root.join(&file.file_path);
+        if let Ok(target) = fs::canonicalize(&path) {
+            if target != path {
+                links.entry(target).or_default().push(path);
+            }
+        }
+    }
+    links
+}
+
+fn scan_project(root: &Path, registry: &ParserRegistry, options: &ScanOptions) -> Result<ProjectTree> {
+    scan_project_timed(root, registry, options, None)
+}
+
+fn scan_project_timed(
+    root: &Path,
+    registry: &ParserRegistry,
+    options: &ScanOptions,
+    timings: Option<&mut Vec<ParseTiming>>,
+) -> Result<ProjectTree> {
     let mut files = Vec::new();
-    walk_dir(root, root, registry, &mut files)?;
+    let mut progress = options.progress.then(ScanProgress::default);
+    let walked = parse_source_files(root, registry, options, &mut files, timings, progress.as_mut());
+    if let Some(ref progress) = progress {
+        progress.finish();
+    }
+    walked?;
     files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
 
     Ok(ProjectTree {
@@ -525,33 +1599,125 @@ fn scan_project(root: &Path, registry: &ParserRegistry) -> Result<ProjectTree> {
     })
 }
 
-fn walk_dir(
-    dir: &Path,
+fn run_profile(project_path: &Path, registry: &ParserRegistry, options: &ScanOptions, top: usize) -> Result<()> {
+    // Drawing progress would count towards the timings.
+    let options = &ScanOptions { progress: false, ..options.clone() };
+    let mut timings = Vec::new();
+    let start = std::time::Instant::now();
+    let tree = scan_project_timed(project_path, registry, options, Some(&mut timings))?;
+    let total = start.elapsed();
+    let parse_total: Duration = timings.iter().map(|t| t.elapsed).sum();
+
+    println!(
+        "Scanned {} files ({} symbols) in {:.1?} ({:.1?} parsing)",
+        tree.total_files(),
+        tree.total_symbols(),
+        total,
+        parse_total,
+    );
+    println!();
+
+    timings.sort_by_key(|t| std::cmp::Reverse(t.elapsed));
+    println!("{:>10} {:>8} {:>9}  File", "Time", "Symbols", "KB");
+    for t in timings.iter().take(top) {
+        println!(
+            "{:>10} {:>8} {:>9.1}  {}",
+            format!("{:.2?}", t.elapsed),
+            t.symbols,
+            t.bytes as f64 / 1024.0,
+            t.path.display(),
+        );
+    }
+
+    Ok(())
+}
+
+fn run_bench(project_path: &Path, registry: &ParserRegistry, options: &ScanOptions) -> Result<()> {
+    // Drawing progress would count towards the timings.
+    let options = &ScanOptions { progress: false, ..options.clone() };
+    let mut timings = Vec::new();
+    let start = std::time::Instant::now();
+    let tree = scan_project_timed(project_path, registry, options, Some(&mut timings))?;
+    let total = start.elapsed();
+    let parse_total: Duration = timings.iter().map(|t| t.elapsed).sum();
+    let per_sec = |count: usize, elapsed: Duration| count as f64 / elapsed.as_secs_f64().max(1e-9);
+
+    println!("Files:       {}", tree.total_files());
+    println!("Symbols:     {}", tree.total_symbols());
+    println!("Scan time:   {total:.1?} ({parse_total:.1?} parsing)");
+    println!("Throughput:  {:.0} symbols/s", per_sec(tree.total_symbols(), total));
+    match peak_rss_kb() {
+        Some(kb) => println!("Peak RSS:    {:.1} MB", kb as f64 / 1024.0),
+        None => println!("Peak RSS:    unavailable"),
+    }
+    println!();
+
+    // Per extension, which is how the registry picks a parser.
+    let mut by_language: BTreeMap<String, (usize, usize, usize, Duration)> = BTreeMap::new();
+    for t in &timings {
+        let ext = t.path.extension().map_or_else(|| "-".to_string(), |e| e.to_string_lossy().to_string());
+        let entry = by_language.entry(ext).or_default();
+        entry.0 += 1;
+        entry.1 += t.symbols;
+        entry.2 += t.bytes;
+        entry.3 += t.elapsed;
+    }
+    println!("{:<10} {:>7} {:>9} {:>10} {:>10} {:>12}", "Language", "Files", "Symbols", "KB", "Parse", "Symbols/s");
+    for (ext, (files, symbols, bytes, elapsed)) in &by_language {
+        println!(
+            "{:<10} {:>7} {:>9} {:>10.1} {:>10} {:>12.0}",
+            format!(".{ext}"),
+            files,
+            symbols,
+            *bytes as f64 / 1024.0,
+            format!("{elapsed:.1?}"),
+            per_sec(*symbols, *elapsed),
+        );
+    }
+
+    Ok(())
+}
+
+/// The process's peak resident set size in KiB, from `/proc/self/status`.
+/// None where that isn't available (anything but Linux).
+fn peak_rss_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn parse_source_files(
     root: &Path,
     registry: &ParserRegistry,
+    options: &ScanOptions,
     out: &mut Vec<FileSymbols>,
+    mut timings: Option<&mut Vec<ParseTiming>>,
+    mut progress: Option<&mut ScanProgress>,
 ) -> Result<()> {
-    let entries = match fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return Ok(()),
-    };
-
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            if name.starts_with('.') || name == "target" || name == "node_modules" {
+    for path in source_files(root, registry, options.max_depth, options.respect_ignore) {
+        if let Some(parser) = registry.parser_for(&path) {
+            let source = fs::read_to_string(&path)?;
+            if looks_generated(&source, &options.generated_markers) {
                 continue;
             }
-        }
-
-        if path.is_dir() {
-            walk_dir(&path, root, registry, out)?;
-        } else if let Some(parser) = registry.parser_for(&path) {
-            let source = fs::read_to_string(&path)?;
             let rel_path = path.strip_prefix(root).unwrap_or(&path);
-            match parser.parse_file(rel_path, &source) {
+            if let Some(ref mut progress) = progress {
+                progress.parsing(rel_path);
+            }
+            let start = std::time::Instant::now();
+            let parsed = parser.parse_file(rel_path, &source);
+            if let Some(ref mut timings) = timings {
+                timings.push(ParseTiming {
+                    path: rel_path.to_path_buf(),
+                    elapsed: start.elapsed(),
+                    symbols: parsed.as_ref().map(|f| f.total_symbols()).unwrap_or(0),
+                    bytes: source.len(),
+                });
+            }
+            if let Some(ref mut progress) = progress {
+                progress.parsed += 1;
+            }
+            match parsed {
                 Ok(file_symbols) => out.push(file_symbols),
                 Err(e) => {
                     eprintln!("Warning: failed to parse {}: {}", path.display(), e);
@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+use color_eyre::eyre::eyre;
+use tree_sitter::{Node, Parser};
+
+use crate::symbols::merkle::{compute_merkle_hash, content_hash, estimate_tokens};
+use crate::symbols::{id_path, FileSymbols, SymbolCategory, SymbolNode};
+
+use super::{comment_ranges, push_module_symbol, rehash_symbols, required_coverage, strip_bom, LanguageParser, ParseOptions};
+
+pub struct LuaParser {
+    options: ParseOptions,
+}
+
+impl LuaParser {
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Default for LuaParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageParser for LuaParser {
+    fn extensions(&self) -> &[&str] {
+        &["lua"]
+    }
+
+    fn parse_file(&self, path: &Path, source: &str) -> color_eyre::Result<FileSymbols> {
+        let source = strip_bom(source);
+        let mut parser = Parser::new();
+        let language = tree_sitter_lua::LANGUAGE;
+        parser
+            .set_language(&language.into())
+            .map_err(|e| eyre!("Failed to set language: {}", e))?;
+
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| eyre!("Failed to parse {}", path.display()))?;
+
+        let root = tree.root_node();
+        let ctx = Ctx { src: source.as_bytes(), file_path: path, path_prefix: id_path(path) };
+        let mut symbols = extract_symbols(root, &ctx);
+
+        let comments = if self.options.ignore_comments {
+            comment_ranges(root, &["comment"])
+        } else {
+            Vec::new()
+        };
+        rehash_symbols(&mut symbols, source, &comments, self.options);
+
+        for sym in symbols.iter_mut() {
+            compute_merkle_hash(sym);
+        }
+
+        if self.options.module_symbols {
+            push_module_symbol(path, source, &mut symbols);
+        }
+
+        let total_lines = source.lines().count();
+
+        Ok(FileSymbols {
+            file_path: path.to_path_buf(),
+            symbols,
+            total_lines,
+            modified: None,
+            required: required_coverage(source),
+        })
+    }
+}
+
+/// Symbol metadata: category and display label
+struct SymbolMeta {
+    category: SymbolCategory,
+    label: &'static str,
+}
+
+const FUNCTION: SymbolMeta = SymbolMeta { category: SymbolCategory::Function, label: "function" };
+const LOCAL_FUNCTION: SymbolMeta = SymbolMeta { category: SymbolCategory::Function, label: "local function" };
+const METHOD: SymbolMeta = SymbolMeta { category: SymbolCategory::Function, label: "method" };
+const TABLE: SymbolMeta = SymbolMeta { category: SymbolCategory::Type, label: "table" };
+
+/// What every symbol in one file shares.
+struct Ctx<'a> {
+    src: &'a [u8],
+    file_path: &'a Path,
+    path_prefix: String,
+}
+
+impl Ctx<'_> {
+    fn symbol(&self, name: &str, name_path: &str, meta: SymbolMeta, node: Node, name_range: Range<usize>) -> SymbolNode {
+        let text = std::str::from_utf8(&self.src[node.byte_range()]).unwrap_or("");
+        SymbolNode {
+            id: format!("{}::{name_path}", self.path_prefix),
+            name: name.to_string(),
+            category: meta.category,
+            label: meta.label.to_string(),
+            file_path: self.file_path.to_path_buf(),
+            byte_range: node.byte_range(),
+            line_range: node.start_position().row + 1..node.end_position().row + 1,
+            content_hash: content_hash(text),
+            merkle_hash: [0u8; 32],
+            children: Vec::new(),
+            estimated_tokens: estimate_tokens(text),
+            derives: Vec::new(),
+            decorators: Vec::new(),
+            test_only: false,
+            name_range: Some(name_range),
+        }
+    }
+
+    fn text(&self, node: Node) -> Option<String> {
+        node.utf8_text(self.src).ok().map(|s| s.to_string())
+    }
+}
+
+/// One top-level statement that defines something; `name_range` is where
+/// the defined name (a member's own, without the table) is in it.
+enum Definition<'a> {
+    /// `function f()`, `local function f()` or `local f = function()`.
+    Function { name: String, name_range: Range<usize>, meta: SymbolMeta, node: Node<'a> },
+    /// `function M.f()`, `function M:f()` or `M.f = function()`; `separator`
+    /// is the `.` or `:` between table and name.
+    Member { table: String, separator: char, name: String, name_range: Range<usize>, meta: SymbolMeta, node: Node<'a> },
+    /// `local M = {}`: a table that members may be added to later.
+    Table { name: String, name_range: Range<usize>, node: Node<'a> },
+}
+
+/// Walk the top-level statements of a chunk, in source order. Functions
+/// stored in a table keep it in their id, so `function M.setup()` is
+/// `M/setup`, but stay top-level symbols named `M.setup`: a table's members
+/// needn't be next to each other, and no range could hold them all without
+/// taking in the code between them. A `local M = {}` is a table symbol of
+/// its own (just that line) once something is added to it.
+fn extract_symbols(root: Node, ctx: &Ctx) -> Vec<SymbolNode> {
+    let mut symbols: Vec<SymbolNode> = Vec::new();
+    let mut declared: HashMap<String, usize> = HashMap::new();
+    let mut extended = HashSet::new();
+
+    let mut cursor = root.walk();
+    for child in root.children(&mut cursor) {
+        match definition(child, ctx) {
+            Some(Definition::Function { name, name_range, meta, node }) => {
+                symbols.push(ctx.symbol(&name, &name, meta, node, name_range))
+            }
+            Some(Definition::Table { name, name_range, node }) if !declared.contains_key(&name) => {
+                declared.insert(name.clone(), symbols.len());
+                symbols.push(ctx.symbol(&name, &name, TABLE, node, name_range));
+            }
+            Some(Definition::Member { table, separator, name, name_range, meta, node }) => {
+                if let Some(&at) = declared.get(&table) {
+                    extended.insert(at);
+                }
+                let display = format!("{table}{separator}{name}");
+                symbols.push(ctx.symbol(&display, &format!("{table}/{name}"), meta, node, name_range));
+            }
+            _ => {}
+        }
+    }
+
+    // A table nothing is added to is just data.
+    let mut index = 0;
+    symbols.retain(|_| {
+        let keep = !declared.values().any(|&at| at == index) || extended.contains(&index);
+        index += 1;
+        keep
+    });
+    symbols
+}
+
+/// What a top-level statement defines, if anything.
+fn definition<'a>(node: Node<'a>, ctx: &Ctx) -> Option<Definition<'a>> {
+    match node.kind() {
+        "function_declaration" => {
+            let local = node.child(0).is_some_and(|c| c.kind() == "local");
+            let target = node.child_by_field_name("name")?;
+            let meta = if local { LOCAL_FUNCTION } else { FUNCTION };
+            named(target, meta, node, ctx)
+        }
+        // `local x = ...` wraps the assignment.
+        "variable_declaration" => {
+            let assignment = node.named_child(0).filter(|c| c.kind() == "assignment_statement")?;
+            assigned(assignment, LOCAL_FUNCTION, node, ctx)
+        }
+        "assignment_statement" => assigned(node, FUNCTION, node, ctx),
+        _ => None,
+    }
+}
+
+/// What `x = value` defines: a function when the value is one, a table when
+/// a table constructor is stored in a plain name. Multiple assignments
+/// (`a, b = 1, 2`) define nothing.
+fn assigned<'a>(assignment: Node, meta: SymbolMeta, node: Node<'a>, ctx: &Ctx) -> Option<Definition<'a>> {
+    let vars = assignment.named_child(0).filter(|c| c.kind() == "variable_list")?;
+    let values = assignment.named_child(1).filter(|c| c.kind() == "expression_list")?;
+    if vars.named_child_count() != 1 || values.named_child_count() != 1 {
+        return None;
+    }
+    let (target, value) = (vars.named_child(0)?, values.named_child(0)?);
+    match value.kind() {
+        "function_definition" => named(target, meta, node, ctx),
+        "table_constructor" if target.kind() == "identifier" => {
+            Some(Definition::Table { name: ctx.text(target)?, name_range: target.byte_range(), node })
+        }
+        _ => None,
+    }
+}
+
+/// The definition a function stored under `target` makes: a plain function
+/// for a name, a member for `table.name` or `table:name`.
+fn named<'a>(target: Node, meta: SymbolMeta, node: Node<'a>, ctx: &Ctx) -> Option<Definition<'a>> {
+    let member = |field: &str, separator, meta| {
+        let name = target.child_by_field_name(field)?;
+        Some(Definition::Member {
+            table: ctx.text(target.child_by_field_name("table")?)?,
+            separator,
+            name: ctx.text(name)?,
+            name_range: name.byte_range(),
+            meta,
+            node,
+        })
+    };
+    match target.kind() {
+        "identifier" => Some(Definition::Function { name: ctx.text(target)?, name_range: target.byte_range(), meta, node }),
+        "dot_index_expression" => member("field", '.', FUNCTION),
+        "method_index_expression" => member("method", ':', METHOD),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::tool_call;
+    use super::*;
+    use crate::parser::LanguageParser;
+    use crate::tracking::{ContextLedger, ReadDepth};
+    use std::path::Path;
+
+    fn parse(src: &str) -> Vec<SymbolNode> {
+        let parser = LuaParser::new();
+        let file = parser.parse_file(Path::new("init.lua"), src).unwrap();
+        file.symbols
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(LuaParser::new().extensions(), &["lua"]);
+    }
+
+    #[test]
+    fn table_members_follow_source_order() {
+        let syms = parse(
+            "local M = {}\n\nlocal function helper()\n  return 1\nend\n\nfunction M.setup(opts)\n  helper()\nend\n\nfunction M:run()\nend\n\nreturn M\n",
+        );
+        let members: Vec<(&str, &str, &str)> =
+            syms.iter().map(|s| (s.id.as_str(), s.label.as_str(), s.name.as_str())).collect();
+        assert_eq!(
+            members,
+            [
+                ("init.lua::M", "table", "M"),
+                ("init.lua::helper", "local function", "helper"),
+                ("init.lua::M/setup", "function", "M.setup"),
+                ("init.lua::M/run", "method", "M:run"),
+            ]
+        );
+        assert_eq!(syms[0].category, SymbolCategory::Type);
+        assert_eq!(syms[0].line_range, 1..1);
+        assert_eq!(syms[2].line_range, 7..9);
+        assert!(syms.iter().all(|s| s.children.is_empty()));
+    }
+
+    #[test]
+    fn reads_between_members_touch_only_what_they_cover() {
+        let syms = parse("local M = {}\nfunction M.a()\nend\nlocal function h()\nend\nfunction M.b()\nend\n");
+        let read_lines = |lines| {
+            let mut read = tool_call("Read", "init.lua", ReadDepth::FullBody);
+            read.target_lines = Some(lines);
+            let mut ledger = ContextLedger::new();
+            crate::app::mark_targeted_symbols(&syms, &read, &mut ledger);
+            ["init.lua::M", "init.lua::M/a", "init.lua::h", "init.lua::M/b"].map(|id| ledger.depth_of(id).is_seen())
+        };
+        assert_eq!(read_lines(4..5), [false, false, true, false]);
+        // Reading the declaration leaves the members unseen.
+        assert_eq!(read_lines(0..2), [true, false, false, false]);
+    }
+
+    #[test]
+    fn undeclared_tables_add_no_symbol() {
+        let syms = parse("function M.a()\nend\n\nM.b = function()\nend\n");
+        let ids: Vec<&str> = syms.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["init.lua::M/a", "init.lua::M/b"]);
+    }
+
+    #[test]
+    fn function_values_and_globals() {
+        let syms = parse("local f = function() end\nfunction g() end\nlocal config = { a = 1 }\nlocal x, y = 1, 2\n");
+        let kinds: Vec<(&str, &str)> = syms.iter().map(|s| (s.name.as_str(), s.label.as_str())).collect();
+        assert_eq!(kinds, [("f", "local function"), ("g", "function")]);
+    }
+
+    #[test]
+    fn hashes_are_computed() {
+        let syms = parse("local M = {}\nfunction M.setup() end\n");
+        assert_ne!(syms[0].content_hash, [0u8; 32]);
+        assert_ne!(syms[0].merkle_hash, [0u8; 32]);
+        assert!(syms[1].estimated_tokens > 0);
+    }
+}
@@ -1,9 +1,64 @@
+pub mod lua;
 pub mod python;
 pub mod rust;
 
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use crate::symbols::FileSymbols;
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+use crate::rules::{FileRequirement, RequiredLevel};
+use crate::symbols::merkle::{compute_merkle_hash, content_hash, estimate_tokens};
+use crate::symbols::{id_path, uncovered_lines, FileSymbols, SymbolCategory, SymbolNode, MODULE_SYMBOL};
+
+/// Options shared by all tree-sitter parsers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseOptions {
+    /// Leave comments out of content hashes, so comment-only edits don't mark symbols stale.
+    pub ignore_comments: bool,
+    /// Add a `(module)` pseudo-symbol per file for code outside every top-level symbol.
+    pub module_symbols: bool,
+    /// Leave each symbol's name out of its content hash, so a rename that
+    /// leaves the body alone keeps the hash.
+    pub follow_renames: bool,
+}
+
+/// How many leading lines `looks_generated` checks for a marker.
+pub const GENERATED_HEADER_LINES: usize = 5;
+
+/// Whether one of `markers` appears in the first `GENERATED_HEADER_LINES`
+/// lines of `source`, as in `// @generated` or `# Code generated by X. DO NOT EDIT.`
+pub fn looks_generated(source: &str, markers: &[String]) -> bool {
+    source
+        .lines()
+        .take(GENERATED_HEADER_LINES)
+        .any(|line| markers.iter().any(|m| line.contains(m.as_str())))
+}
+
+/// How many leading lines `required_coverage` checks for a directive.
+pub const DIRECTIVE_HEADER_LINES: usize = 10;
+
+/// The requirement declared by an `ambits: require <seen|full> [N%]` comment
+/// in the first `DIRECTIVE_HEADER_LINES` lines of `source`, such as
+/// `// ambits: require full` or `# ambits: require seen 80%`. The percentage
+/// defaults to 100.
+pub fn required_coverage(source: &str) -> Option<FileRequirement> {
+    source.lines().take(DIRECTIVE_HEADER_LINES).find_map(|line| {
+        let rest = line.split_once("ambits:")?.1.trim_start().strip_prefix("require")?;
+        let mut words = rest.split_whitespace();
+        let level = match words.next()? {
+            "seen" => RequiredLevel::Seen,
+            "full" => RequiredLevel::Full,
+            _ => return None,
+        };
+        let percent = match words.next() {
+            Some(word) => word.strip_suffix('%').unwrap_or(word).parse().ok()?,
+            None => 100.0,
+        };
+        Some(FileRequirement { level, percent })
+    })
+}
 
 /// Trait for language-specific parsers.
 /// Implement this trait to add support for a new language.
@@ -20,13 +75,24 @@ pub struct ParserRegistry {
     parsers: Vec<Box<dyn LanguageParser>>,
 }
 
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ParserRegistry {
     pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
         let mut registry = Self {
             parsers: Vec::new(),
         };
-        registry.register(Box::new(rust::RustParser::new()));
-        registry.register(Box::new(python::PythonParser::new()));
+        registry.register(Box::new(rust::RustParser::with_options(options)));
+        registry.register(Box::new(python::PythonParser::with_options(options)));
+        registry.register(Box::new(lua::LuaParser::with_options(options)));
         registry
     }
 
@@ -43,3 +109,376 @@ impl ParserRegistry {
             .map(|p| p.as_ref())
     }
 }
+
+/// `source` without a leading UTF-8 byte-order mark, which editors on
+/// Windows like to add and which would otherwise end up in hashed text.
+/// Ranges are then relative to the text after the mark.
+pub(crate) fn strip_bom(source: &str) -> &str {
+    source.strip_prefix('\u{feff}').unwrap_or(source)
+}
+
+/// Directories never scanned, whatever the ignore files say.
+const ALWAYS_SKIPPED: &[&str] = &["target", "node_modules"];
+
+/// Every file under `root` that a parser in `registry` handles, in path
+/// order. Dotfiles and the `ALWAYS_SKIPPED` directories are left out, and so
+/// is whatever `.gitignore` files (nested ones included) exclude unless
+/// `respect_ignore` is false. `max_depth` is how many directory levels below
+/// the root are entered.
+pub fn source_files(root: &Path, registry: &ParserRegistry, max_depth: Option<usize>, respect_ignore: bool) -> Vec<PathBuf> {
+    let mut walker = ignore::WalkBuilder::new(root);
+    walker
+        .standard_filters(respect_ignore)
+        .hidden(true)
+        .require_git(false)
+        .follow_links(true)
+        .max_depth(max_depth.map(|depth| depth + 1))
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !ALWAYS_SKIPPED.iter().any(|name| entry.file_name() == *name)
+        });
+
+    let mut files: Vec<PathBuf> = walker
+        .build()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.is_file() && registry.parser_for(path).is_some())
+        .collect();
+    files.sort();
+    files
+}
+
+/// The rules `source_files` walks by, for checking one path at a time (a file
+/// the watcher saw change) without walking the project again. The ignore files
+/// are read once, when the filter is built.
+#[derive(Debug)]
+pub struct SourceFilter {
+    root: PathBuf,
+    max_depth: Option<usize>,
+    /// `.gitignore` and `.ignore` matchers, parents before their children and
+    /// `.gitignore` before `.ignore` in one directory, so later ones win.
+    ignores: Vec<ignore::gitignore::Gitignore>,
+}
+
+impl SourceFilter {
+    pub fn new(root: &Path, max_depth: Option<usize>, respect_ignore: bool) -> Self {
+        let mut ignores = Vec::new();
+        if respect_ignore {
+            let mut walker = ignore::WalkBuilder::new(root);
+            walker
+                .hidden(true)
+                .require_git(false)
+                .follow_links(true)
+                .max_depth(max_depth.map(|depth| depth + 1))
+                .filter_entry(|entry| {
+                    entry.depth() == 0 || !ALWAYS_SKIPPED.iter().any(|name| entry.file_name() == *name)
+                })
+                .sort_by_file_path(|a, b| a.cmp(b));
+            for dir in walker.build().filter_map(|entry| entry.ok()).filter(|entry| entry.path().is_dir()) {
+                for name in [".gitignore", ".ignore"] {
+                    let file = dir.path().join(name);
+                    if file.is_file() {
+                        let (matcher, _) = ignore::gitignore::Gitignore::new(file);
+                        ignores.push(matcher);
+                    }
+                }
+            }
+            ignores.sort_by_key(|matcher| matcher.path().components().count());
+        }
+        Self { root: root.to_path_buf(), max_depth, ignores }
+    }
+
+    /// Whether a scan of the root would leave `path` out: it's outside the root,
+    /// too deep, under a dotfile or `ALWAYS_SKIPPED` name, or ignored.
+    pub fn excludes(&self, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.root) else {
+            return true;
+        };
+        let names: Vec<_> = rel.components().map(|c| c.as_os_str()).collect();
+        if self.max_depth.is_some_and(|depth| names.len() > depth + 1) {
+            return true;
+        }
+        if names.iter().any(|name| name.to_string_lossy().starts_with('.')) {
+            return true;
+        }
+        if names[..names.len().saturating_sub(1)].iter().any(|name| ALWAYS_SKIPPED.iter().any(|skip| name == skip)) {
+            return true;
+        }
+        let mut ignored = false;
+        for matcher in self.ignores.iter().filter(|m| path.starts_with(m.path())) {
+            match matcher.matched_path_or_any_parents(path, false) {
+                ignore::Match::Ignore(_) => ignored = true,
+                ignore::Match::Whitelist(_) => ignored = false,
+                ignore::Match::None => {}
+            }
+        }
+        ignored
+    }
+}
+
+/// Byte ranges of every node of the given comment kinds under `root`.
+pub(crate) fn comment_ranges(root: Node, kinds: &[&str]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if kinds.contains(&node.kind()) {
+            ranges.push(node.byte_range());
+            continue;
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+    ranges.sort_by_key(|r| r.start);
+    ranges
+}
+
+/// Append the module pseudo-symbol covering the non-blank lines outside every
+/// top-level symbol, if there are any. Its hash is over just those lines, so
+/// editing an import marks it stale without touching the other symbols.
+pub(crate) fn push_module_symbol(path: &Path, source: &str, symbols: &mut Vec<SymbolNode>) {
+    let lines: Vec<&str> = source.lines().collect();
+    let glue: Vec<usize> = uncovered_lines(symbols, lines.len())
+        .into_iter()
+        .filter(|&line| !lines[line - 1].trim().is_empty())
+        .collect();
+    let (Some(&first), Some(&last)) = (glue.first(), glue.last()) else {
+        return;
+    };
+    let glue_lines: Vec<&str> = glue.iter().map(|&line| lines[line - 1]).collect();
+    let text = glue_lines.join("\n");
+
+    let mut sym = SymbolNode {
+        id: format!("{}::{MODULE_SYMBOL}", id_path(path)),
+        name: MODULE_SYMBOL.to_string(),
+        category: SymbolCategory::Module,
+        label: "module".to_string(),
+        file_path: path.to_path_buf(),
+        byte_range: 0..source.len(),
+        line_range: first..last,
+        content_hash: module_hash(&glue_lines),
+        merkle_hash: [0u8; 32],
+        children: Vec::new(),
+        estimated_tokens: estimate_tokens(&text),
+        derives: Vec::new(),
+        decorators: Vec::new(),
+        test_only: false,
+        name_range: None,
+    };
+    compute_merkle_hash(&mut sym);
+    symbols.push(sym);
+}
+
+/// Content hash of the module pseudo-symbol's lines with its import statements
+/// sorted to the front, so reordering imports (by hand or by a formatter)
+/// doesn't mark the module stale. Other lines keep their order.
+fn module_hash(lines: &[&str]) -> [u8; 32] {
+    let mut imports = Vec::new();
+    let mut rest = Vec::new();
+    // An import still open over several lines, with its unclosed bracket count.
+    let mut open: Option<(String, i32)> = None;
+    for &line in lines {
+        let (statement, depth) = match open.take() {
+            Some((statement, depth)) => (format!("{statement}\n{}", line.trim()), depth + bracket_depth(line)),
+            None if is_import(line) => (line.trim().to_string(), bracket_depth(line)),
+            None => {
+                rest.push(line);
+                continue;
+            }
+        };
+        if depth > 0 {
+            open = Some((statement, depth));
+        } else {
+            imports.push(statement);
+        }
+    }
+    imports.extend(open.map(|(statement, _)| statement));
+    imports.sort();
+
+    let text: Vec<&str> = imports.iter().map(String::as_str).chain(rest).collect();
+    content_hash(&text.join("\n"))
+}
+
+/// Whether a line starts an import: Rust `use` (with any visibility), Python
+/// `import`/`from`, or a Lua `local x = require(...)`.
+fn is_import(line: &str) -> bool {
+    let line = line.trim_start();
+    let unqualified = match line.strip_prefix("pub") {
+        Some(rest) if rest.starts_with('(') => rest.split_once(')').map_or(rest, |(_, r)| r).trim_start(),
+        Some(rest) => rest.trim_start(),
+        None => line,
+    };
+    ["use ", "import ", "from "].iter().any(|kw| unqualified.starts_with(kw))
+        || (line.starts_with("local ") && line.contains("require"))
+}
+
+/// Opening minus closing brackets on a line.
+fn bracket_depth(line: &str) -> i32 {
+    line.chars()
+        .map(|c| match c {
+            '(' | '[' | '{' => 1,
+            ')' | ']' | '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Recompute each symbol's content hash over its source without what
+/// `options` leaves out: the `comments` when ignoring them, and the symbol's
+/// name when following renames. Does nothing when neither is on. Merkle
+/// hashes must be computed afterwards.
+pub(crate) fn rehash_symbols(symbols: &mut [SymbolNode], source: &str, comments: &[Range<usize>], options: ParseOptions) {
+    if !options.ignore_comments && !options.follow_renames {
+        return;
+    }
+    for sym in symbols.iter_mut() {
+        let range = sym.byte_range.clone();
+        let inside = |r: &Range<usize>| r.start >= range.start && r.end <= range.end;
+        // Comments keep a separator so `a/* x */b` doesn't hash like `ab`.
+        let mut cuts: Vec<(Range<usize>, &str)> = comments
+            .iter()
+            .filter(|c| options.ignore_comments && inside(c))
+            .map(|c| (c.clone(), " "))
+            .collect();
+        if let Some(name) = sym.name_range.clone().filter(|n| options.follow_renames && inside(n)) {
+            cuts.push((name, ""));
+        }
+        cuts.sort_by_key(|(cut, _)| cut.start);
+
+        let mut text = String::with_capacity(range.len());
+        let mut pos = range.start;
+        for (cut, separator) in cuts {
+            if cut.start < pos {
+                continue;
+            }
+            text.push_str(source.get(pos..cut.start).unwrap_or(""));
+            text.push_str(separator);
+            pos = cut.end;
+        }
+        text.push_str(source.get(pos..range.end).unwrap_or(""));
+        sym.content_hash = content_hash(&text);
+        rehash_symbols(&mut sym.children, source, comments, options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_files_honor_gitignore() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for path in ["src/lib.rs", "src/generated.rs", "gen/out.rs", "target/debug/build.rs", ".hidden/x.rs", "notes.txt"] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), "fn f() {}\n").unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "gen/\n").unwrap();
+        std::fs::write(root.join("src/.gitignore"), "generated.rs\n").unwrap();
+
+        let registry = ParserRegistry::new();
+        let relative = |files: Vec<PathBuf>| -> Vec<String> {
+            files.iter().map(|f| f.strip_prefix(root).unwrap().to_string_lossy().to_string()).collect()
+        };
+        assert_eq!(relative(source_files(root, &registry, None, true)), ["src/lib.rs"]);
+        assert_eq!(
+            relative(source_files(root, &registry, None, false)),
+            ["gen/out.rs", "src/generated.rs", "src/lib.rs"]
+        );
+        assert!(source_files(root, &registry, Some(0), false).is_empty());
+
+        // One path at a time, the filter agrees with the walk.
+        let filter = SourceFilter::new(root, None, true);
+        let kept: Vec<&str> = ["src/lib.rs", "src/generated.rs", "gen/out.rs", "target/debug/build.rs", ".hidden/x.rs"]
+            .into_iter()
+            .filter(|path| !filter.excludes(&root.join(path)))
+            .collect();
+        assert_eq!(kept, ["src/lib.rs"]);
+        assert!(!SourceFilter::new(root, None, false).excludes(&root.join("gen/out.rs")));
+        assert!(SourceFilter::new(root, Some(0), false).excludes(&root.join("src/lib.rs")));
+    }
+
+    /// A source for each registered extension with a few kinds of symbol.
+    const SAMPLES: [(&str, &str); 3] = [
+        ("rs", "use std::fmt;\n\nmod m {\n    pub struct S { x: u8 }\n    impl S {\n        fn f(&self) {}\n    }\n}\nenum E { A }\ntrait T {}\nconst C: u8 = 1;\nmacro_rules! m { () => {} }\n"),
+        ("py", "import os\n\nclass A:\n    @property\n    def p(self):\n        pass\n\ndef f():\n    pass\n"),
+        ("lua", "-- setup\nlocal M = {}\nfunction M.setup() end\nfunction M:run() end\nlocal function helper() end\n"),
+    ];
+
+    fn sample(ext: &str) -> &'static str {
+        SAMPLES.iter().find(|(e, _)| *e == ext).map(|(_, s)| *s).unwrap_or_else(|| panic!("no sample for .{ext}"))
+    }
+
+    #[test]
+    fn bom_and_crlf_sources_parse_like_plain_ones() {
+        let registry = ParserRegistry::with_options(ParseOptions { module_symbols: true, ..Default::default() });
+        for parser in &registry.parsers {
+            for ext in parser.extensions() {
+                let path = PathBuf::from(format!("sample.{ext}"));
+                let summary = |source: &str| {
+                    let file = parser.parse_file(&path, source).unwrap();
+                    let mut symbols = Vec::new();
+                    crate::symbols::visit_symbols(&file.symbols, |sym| {
+                        symbols.push((sym.id.clone(), sym.line_range.clone(), sym.content_hash));
+                    });
+                    (file.total_lines, symbols)
+                };
+                let plain = summary(sample(ext));
+                assert!(plain.1.len() > 1, ".{ext}");
+                assert_eq!(summary(&format!("\u{feff}{}", sample(ext))), plain, ".{ext} with a BOM");
+                assert_eq!(summary(&sample(ext).replace('\n', "\r\n")), plain, ".{ext} with CRLF");
+            }
+        }
+    }
+
+    #[test]
+    fn every_parser_labels_its_symbols() {
+        let registry = ParserRegistry::with_options(ParseOptions { module_symbols: true, ..Default::default() });
+        for parser in &registry.parsers {
+            for ext in parser.extensions() {
+                let source = sample(ext);
+                let file = parser.parse_file(Path::new(&format!("sample.{ext}")), source).unwrap();
+                let mut count = 0;
+                crate::symbols::visit_symbols(&file.symbols, |sym| {
+                    assert!(!sym.label.is_empty(), ".{ext}: {} has no label", sym.id);
+                    count += 1;
+                });
+                assert!(count > 1, ".{ext}: the sample should produce symbols");
+            }
+        }
+    }
+
+    #[test]
+    fn module_hash_ignores_import_order() {
+        let hash = |src: &str| module_hash(&src.lines().collect::<Vec<_>>());
+        assert_eq!(
+            hash("import os\nfrom typing import (\n    Any,\n    List,\n)\nx = 1"),
+            hash("from typing import (\n    Any,\n    List,\n)\nimport os\nx = 1"),
+        );
+        assert_eq!(hash("use b;\npub(crate) use a;\n"), hash("pub(crate) use a;\nuse b;\n"));
+
+        // Changing an import, or reordering other code, still counts.
+        assert_ne!(hash("import os\nimport sys"), hash("import os\nimport re"));
+        assert_ne!(hash("x = 1\ny = 2"), hash("y = 2\nx = 1"));
+        assert_ne!(hash("use a::{b, c};"), hash("use a::{b, d};"));
+    }
+
+    #[test]
+    fn generated_markers_only_count_in_the_header() {
+        let markers = vec!["@generated".to_string(), "DO NOT EDIT".to_string()];
+        assert!(looks_generated("// @generated by protoc\nfn a() {}\n", &markers));
+        assert!(looks_generated("#!/usr/bin/env python\n# Code generated by tool. DO NOT EDIT.\n", &markers));
+        assert!(!looks_generated(&format!("{}// @generated\n", "\n".repeat(GENERATED_HEADER_LINES)), &markers));
+        assert!(!looks_generated("// @generated\n", &[]));
+    }
+
+    #[test]
+    fn require_directives_parse_level_and_percent() {
+        let full = FileRequirement { level: RequiredLevel::Full, percent: 100.0 };
+        assert_eq!(required_coverage("//! Core\n// ambits: require full\nfn a() {}\n"), Some(full));
+        assert_eq!(
+            required_coverage("# ambits: require seen 80%\n"),
+            Some(FileRequirement { level: RequiredLevel::Seen, percent: 80.0 })
+        );
+        assert_eq!(required_coverage("// ambits: require most\n"), None);
+        assert_eq!(required_coverage(&format!("{}// ambits: require full\n", "\n".repeat(DIRECTIVE_HEADER_LINES))), None);
+    }
+}
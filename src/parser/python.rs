@@ -4,17 +4,27 @@ use color_eyre::eyre::eyre;
 use tree_sitter::{Node, Parser};
 
 use crate::symbols::merkle::{compute_merkle_hash, content_hash, estimate_tokens};
-use crate::symbols::{FileSymbols, SymbolCategory, SymbolNode};
+use crate::symbols::{id_path, FileSymbols, SymbolCategory, SymbolNode};
 
-use super::LanguageParser;
+use super::{comment_ranges, push_module_symbol, rehash_symbols, required_coverage, strip_bom, LanguageParser, ParseOptions};
 
 pub struct PythonParser {
-    _private: (),
+    options: ParseOptions,
 }
 
 impl PythonParser {
     pub fn new() -> Self {
-        Self { _private: () }
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Default for PythonParser {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -24,6 +34,7 @@ impl LanguageParser for PythonParser {
     }
 
     fn parse_file(&self, path: &Path, source: &str) -> color_eyre::Result<FileSymbols> {
+        let source = strip_bom(source);
         let mut parser = Parser::new();
         let language = tree_sitter_python::LANGUAGE;
         parser
@@ -35,22 +46,35 @@ impl LanguageParser for PythonParser {
             .ok_or_else(|| eyre!("Failed to parse {}", path.display()))?;
 
         let root = tree.root_node();
-        let path_prefix = path.to_string_lossy();
+        let path_prefix = id_path(path);
         let src = source.as_bytes();
         let mut symbols = Vec::new();
 
         extract_symbols(root, src, path, &path_prefix, "", &mut symbols);
 
+        let comments = if self.options.ignore_comments {
+            comment_ranges(root, &["comment"])
+        } else {
+            Vec::new()
+        };
+        rehash_symbols(&mut symbols, source, &comments, self.options);
+
         for sym in symbols.iter_mut() {
             compute_merkle_hash(sym);
         }
 
+        if self.options.module_symbols {
+            push_module_symbol(path, source, &mut symbols);
+        }
+
         let total_lines = source.lines().count();
 
         Ok(FileSymbols {
             file_path: path.to_path_buf(),
             symbols,
             total_lines,
+            modified: None,
+            required: required_coverage(source),
         })
     }
 }
@@ -63,6 +87,9 @@ struct SymbolMeta {
 
 const CLASS: SymbolMeta = SymbolMeta { category: SymbolCategory::Type, label: "class" };
 const DEF: SymbolMeta = SymbolMeta { category: SymbolCategory::Function, label: "def" };
+const PROPERTY: SymbolMeta = SymbolMeta { category: SymbolCategory::Variable, label: "property" };
+const STATICMETHOD: SymbolMeta = SymbolMeta { category: SymbolCategory::Function, label: "staticmethod" };
+const CLASSMETHOD: SymbolMeta = SymbolMeta { category: SymbolCategory::Function, label: "classmethod" };
 
 /// Walk top-level children of a Python module node and extract symbols.
 fn extract_symbols(
@@ -113,6 +140,10 @@ fn extract_symbols(
                 merkle_hash: [0u8; 32],
                 children: Vec::new(),
                 estimated_tokens: estimate_tokens(text),
+                derives: Vec::new(),
+                decorators: Vec::new(),
+                test_only: false,
+                name_range: name_range(&child),
             };
 
             // For classes, recurse into the body block to find methods.
@@ -125,6 +156,31 @@ fn extract_symbols(
             out.push(sym);
         }
     }
+    merge_overloads(out, src);
+}
+
+/// Fold `@overload` stubs into the definition that follows them, so a
+/// function's signatures and its implementation are one symbol with one id.
+fn merge_overloads(symbols: &mut Vec<SymbolNode>, src: &[u8]) {
+    let mut merged: Vec<SymbolNode> = Vec::with_capacity(symbols.len());
+    for mut sym in symbols.drain(..) {
+        match merged.last_mut() {
+            Some(prev) if prev.name == sym.name && is_overload(prev) => {
+                sym.byte_range.start = prev.byte_range.start;
+                sym.line_range.start = prev.line_range.start;
+                let text = std::str::from_utf8(&src[sym.byte_range.clone()]).unwrap_or("");
+                sym.content_hash = content_hash(text);
+                sym.estimated_tokens = estimate_tokens(text);
+                *prev = sym;
+            }
+            _ => merged.push(sym),
+        }
+    }
+    *symbols = merged;
+}
+
+fn is_overload(sym: &SymbolNode) -> bool {
+    sym.decorators.iter().any(|d| d.rsplit('.').next() == Some("overload"))
 }
 
 /// Handle decorated definitions (@decorator followed by def/class).
@@ -145,8 +201,11 @@ fn extract_decorated(
                     Some(n) => n,
                     None => return,
                 };
+                let decorators = decorator_names(node, src);
                 let meta = match child.kind() {
                     "class_definition" => CLASS,
+                    // Only class bodies are recursed into, so a parent means a method.
+                    _ if !parent_name_path.is_empty() => method_meta(&decorators),
                     _ => DEF,
                 };
 
@@ -175,6 +234,10 @@ fn extract_decorated(
                     merkle_hash: [0u8; 32],
                     children: Vec::new(),
                     estimated_tokens: estimate_tokens(text),
+                    derives: Vec::new(),
+                    decorators,
+                    test_only: false,
+                    name_range: name_range(&child),
                 };
 
                 if meta.category == SymbolCategory::Type {
@@ -190,6 +253,37 @@ fn extract_decorated(
     }
 }
 
+/// Names of the decorators on a decorated_definition, in source order.
+/// Calls keep only the callee, so `@app.route("/")` gives `app.route`.
+fn decorator_names(node: &Node, src: &[u8]) -> Vec<String> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|c| c.kind() == "decorator")
+        .filter_map(|decorator| {
+            let mut expr = decorator.named_child(0)?;
+            if expr.kind() == "call" {
+                expr = expr.child_by_field_name("function")?;
+            }
+            expr.utf8_text(src).ok().map(|s| s.to_string())
+        })
+        .collect()
+}
+
+/// The flavor of a decorated method: properties (including their setters and
+/// deleters), static methods and class methods; anything else is a plain def.
+fn method_meta(decorators: &[String]) -> SymbolMeta {
+    for decorator in decorators {
+        let last = decorator.rsplit('.').next().unwrap_or(decorator);
+        match last {
+            "property" | "cached_property" | "setter" | "getter" | "deleter" => return PROPERTY,
+            "staticmethod" => return STATICMETHOD,
+            "classmethod" => return CLASSMETHOD,
+            _ => {}
+        }
+    }
+    DEF
+}
+
 /// Extract the name from a function_definition or class_definition node.
 fn child_name(node: &Node, src: &[u8]) -> Option<String> {
     node.child_by_field_name("name")?
@@ -198,6 +292,11 @@ fn child_name(node: &Node, src: &[u8]) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// Byte range of a function_definition or class_definition's name.
+fn name_range(node: &Node) -> Option<std::ops::Range<usize>> {
+    node.child_by_field_name("name").map(|n| n.byte_range())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,6 +315,17 @@ mod tests {
         assert_eq!(parser.extensions(), &["py"]);
     }
 
+    #[test]
+    fn ignore_comments_keeps_hash_stable() {
+        let parser = PythonParser::with_options(ParseOptions { ignore_comments: true, ..Default::default() });
+        let hash = |src: &str| parser.parse_file(Path::new("test.py"), src).unwrap().symbols[0].content_hash;
+        assert_eq!(
+            hash("def foo():\n    # old note\n    return 1\n"),
+            hash("def foo():\n    # new note\n    return 1\n"),
+        );
+        assert_ne!(hash("def foo():\n    return 1\n"), hash("def foo():\n    return 2\n"));
+    }
+
     #[test]
     fn parse_empty_file() {
         let syms = parse("");
@@ -380,6 +490,15 @@ mod tests {
         assert_eq!(file.symbols[0].id, "src/main.py::foo");
     }
 
+    #[test]
+    fn ids_drop_leading_dot_components() {
+        let parser = PythonParser::new();
+        let file = parser
+            .parse_file(Path::new("./src/main.py"), "def foo():\n    pass\n")
+            .unwrap();
+        assert_eq!(file.symbols[0].id, "src/main.py::foo");
+    }
+
     #[test]
     fn parse_only_comments_and_imports() {
         // Statements that aren't function/class definitions should be ignored
@@ -399,12 +518,64 @@ mod tests {
         assert_eq!(syms[0].children[0].category, SymbolCategory::Function);
     }
 
+    #[test]
+    fn method_decorators_set_the_kind() {
+        let syms = parse(
+            "class Foo:\n    @property\n    def size(self):\n        return 1\n\n    @size.setter\n    def size(self, v):\n        pass\n\n    @staticmethod\n    def make():\n        pass\n\n    @classmethod\n    def load(cls):\n        pass\n\n    @cache\n    def run(self):\n        pass\n",
+        );
+        let kinds: Vec<(&str, SymbolCategory, &str)> = syms[0]
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.category, c.label.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("size", SymbolCategory::Variable, "property"),
+                ("size", SymbolCategory::Variable, "property"),
+                ("make", SymbolCategory::Function, "staticmethod"),
+                ("load", SymbolCategory::Function, "classmethod"),
+                ("run", SymbolCategory::Function, "def"),
+            ]
+        );
+        // Ids and ranges are unaffected by the kind.
+        assert_eq!(syms[0].children[2].id, "test.py::Foo/make");
+        assert_eq!(syms[0].children[2].line_range, 10..12);
+    }
+
+    #[test]
+    fn module_level_property_decorator_stays_a_def() {
+        let syms = parse("@property\ndef foo():\n    pass\n");
+        assert_eq!(syms[0].label, "def");
+    }
+
+    #[test]
+    fn overloads_merge_into_the_implementation() {
+        let syms = parse(
+            "@overload\ndef get(key: int) -> int: ...\n@typing.overload\ndef get(key: str) -> str: ...\ndef get(key):\n    return key\n\ndef other():\n    pass\n",
+        );
+        let names: Vec<&str> = syms.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["get", "other"]);
+        assert_eq!(syms[0].id, "test.py::get");
+        assert_eq!(syms[0].line_range, 1..6);
+        assert!(syms[0].decorators.is_empty());
+        assert_eq!(syms[0].label, "def");
+    }
+
     #[test]
     fn parse_multiple_decorators() {
         let syms = parse("@decorator1\n@decorator2\ndef foo():\n    pass\n");
         assert_eq!(syms.len(), 1);
         assert_eq!(syms[0].name, "foo");
         assert_eq!(syms[0].line_range.start, 1);
+        assert_eq!(syms[0].decorators, vec!["decorator1", "decorator2"]);
+    }
+
+    #[test]
+    fn decorator_calls_keep_the_callee() {
+        let syms = parse("@app.route(\"/\", methods=[\"GET\"])\n@login_required\ndef index():\n    pass\n\ndef plain():\n    pass\n");
+        assert_eq!(syms[0].decorators, vec!["app.route", "login_required"]);
+        assert!(syms[1].decorators.is_empty());
     }
 
     #[test]
@@ -427,4 +598,12 @@ mod tests {
         assert!(range.start < range.end);
         assert!(range.end <= src.len());
     }
+
+    #[test]
+    fn following_renames_skips_the_name_not_the_decorator() {
+        let parser = PythonParser::with_options(ParseOptions { follow_renames: true, ..ParseOptions::default() });
+        let hash = |src: &str| parser.parse_file(Path::new("m.py"), src).unwrap().symbols[0].content_hash;
+        // `c` first occurs in the decorator.
+        assert_eq!(hash("@decorate\nclass c:\n    pass\n"), hash("@decorate\nclass k:\n    pass\n"));
+    }
 }
@@ -4,17 +4,27 @@ use color_eyre::eyre::eyre;
 use tree_sitter::{Node, Parser};
 
 use crate::symbols::merkle::{compute_merkle_hash, content_hash, estimate_tokens};
-use crate::symbols::{FileSymbols, SymbolCategory, SymbolNode};
+use crate::symbols::{id_path, FileSymbols, SymbolCategory, SymbolNode};
 
-use super::LanguageParser;
+use super::{comment_ranges, push_module_symbol, rehash_symbols, required_coverage, strip_bom, LanguageParser, ParseOptions};
 
 pub struct RustParser {
-    _private: (),
+    options: ParseOptions,
 }
 
 impl RustParser {
     pub fn new() -> Self {
-        Self { _private: () }
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Default for RustParser {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -24,6 +34,7 @@ impl LanguageParser for RustParser {
     }
 
     fn parse_file(&self, path: &Path, source: &str) -> color_eyre::Result<FileSymbols> {
+        let source = strip_bom(source);
         let mut parser = Parser::new();
         let language = tree_sitter_rust::LANGUAGE;
         parser
@@ -35,22 +46,35 @@ impl LanguageParser for RustParser {
             .ok_or_else(|| eyre!("Failed to parse {}", path.display()))?;
 
         let root = tree.root_node();
-        let path_prefix = path.to_string_lossy();
+        let path_prefix = id_path(path);
         let src = source.as_bytes();
         let mut symbols = Vec::new();
 
         extract_symbols(root, src, path, &path_prefix, "", &mut symbols);
 
+        let comments = if self.options.ignore_comments {
+            comment_ranges(root, &["line_comment", "block_comment"])
+        } else {
+            Vec::new()
+        };
+        rehash_symbols(&mut symbols, source, &comments, self.options);
+
         for sym in symbols.iter_mut() {
             compute_merkle_hash(sym);
         }
 
+        if self.options.module_symbols {
+            push_module_symbol(path, source, &mut symbols);
+        }
+
         let total_lines = source.lines().count();
 
         Ok(FileSymbols {
             file_path: path.to_path_buf(),
             symbols,
             total_lines,
+            modified: None,
+            required: required_coverage(source),
         })
     }
 }
@@ -63,6 +87,9 @@ struct SymbolMeta {
 
 const MOD: SymbolMeta = SymbolMeta { category: SymbolCategory::Module, label: "mod" };
 const STRUCT: SymbolMeta = SymbolMeta { category: SymbolCategory::Type, label: "struct" };
+const UNION: SymbolMeta = SymbolMeta { category: SymbolCategory::Type, label: "union" };
+const FIELD: SymbolMeta = SymbolMeta { category: SymbolCategory::Variable, label: "field" };
+const EXTERN: SymbolMeta = SymbolMeta { category: SymbolCategory::Module, label: "extern" };
 const ENUM: SymbolMeta = SymbolMeta { category: SymbolCategory::Type, label: "enum" };
 const TRAIT: SymbolMeta = SymbolMeta { category: SymbolCategory::Type, label: "trait" };
 const IMPL: SymbolMeta = SymbolMeta { category: SymbolCategory::Implementation, label: "impl" };
@@ -81,11 +108,28 @@ fn extract_symbols(
     out: &mut Vec<SymbolNode>,
 ) {
     let mut cursor = node.walk();
+    // Derives and `#[cfg(test)]` from the attributes directly above the current item.
+    let mut pending_derives: Vec<String> = Vec::new();
+    let mut pending_test_only = false;
     for child in node.children(&mut cursor) {
+        match child.kind() {
+            "attribute_item" => {
+                pending_derives.extend(derive_list(&child, src));
+                pending_test_only |= is_cfg_test(&child, src);
+                continue;
+            }
+            "line_comment" | "block_comment" => continue,
+            _ => {}
+        }
+        let derives = std::mem::take(&mut pending_derives);
+        let test_only = std::mem::take(&mut pending_test_only);
+
         let symbol_info = match child.kind() {
             "function_item" => named_symbol(&child, src, &FN),
             "struct_item" => named_symbol(&child, src, &STRUCT),
             "enum_item" => named_symbol(&child, src, &ENUM),
+            "union_item" => named_symbol(&child, src, &UNION),
+            "foreign_mod_item" => extern_symbol(&child, src),
             "trait_item" => named_symbol(&child, src, &TRAIT),
             "impl_item" => impl_symbol(&child, src),
             "const_item" => named_symbol(&child, src, &CONST),
@@ -121,6 +165,10 @@ fn extract_symbols(
                 merkle_hash: [0u8; 32],
                 children: Vec::new(),
                 estimated_tokens: estimate_tokens(text),
+                derives,
+                decorators: Vec::new(),
+                test_only,
+                name_range: name_range(&child),
             };
 
             // Recurse into container types for their children.
@@ -131,6 +179,12 @@ fn extract_symbols(
                     extract_body_children(body, src, file_path, path_prefix, &name_path, &mut sym.children);
                 }
             }
+            // Unions list their fields as children.
+            if meta.label == "union" {
+                if let Some(body) = child_by_kind(&child, "field_declaration_list") {
+                    extract_body_children(body, src, file_path, path_prefix, &name_path, &mut sym.children);
+                }
+            }
 
             out.push(sym);
         }
@@ -145,10 +199,18 @@ fn extract_body_children(
     parent_name_path: &str,
     out: &mut Vec<SymbolNode>,
 ) {
+    let in_extern = body.parent().is_some_and(|p| p.kind() == "foreign_mod_item");
     let mut cursor = body.walk();
     for child in body.children(&mut cursor) {
         let symbol_info = match child.kind() {
             "function_item" => named_symbol(&child, src, &FN),
+            // Bodyless declarations count only in extern blocks, where they are the whole API.
+            "function_signature_item" if in_extern => named_symbol(&child, src, &FN),
+            "static_item" if in_extern => named_symbol(&child, src, &STATIC),
+            "field_declaration" => child
+                .child_by_field_name("name")
+                .and_then(|n| n.utf8_text(src).ok())
+                .map(|name| (name.to_string(), FIELD)),
             "const_item" => named_symbol(&child, src, &CONST),
             "type_item" => named_symbol(&child, src, &TYPE_ALIAS),
             "macro_definition" => named_symbol(&child, src, &MACRO),
@@ -162,6 +224,7 @@ fn extract_body_children(
             let start_line = child.start_position().row + 1;
             let end_line = child.end_position().row + 1;
             let text = std::str::from_utf8(&src[byte_range.clone()]).unwrap_or("");
+            let content_hash = content_hash(text);
 
             out.push(SymbolNode {
                 id,
@@ -171,21 +234,64 @@ fn extract_body_children(
                 file_path: file_path.to_path_buf(),
                 byte_range,
                 line_range: start_line..end_line,
-                content_hash: content_hash(text),
+                content_hash,
                 merkle_hash: [0u8; 32],
                 children: Vec::new(),
                 estimated_tokens: estimate_tokens(text),
+                derives: Vec::new(),
+                decorators: Vec::new(),
+                test_only: false,
+                name_range: name_range(&child),
             });
         }
     }
 }
 
+/// Trait names from a `#[derive(A, b::C)]` attribute; empty for any other attribute.
+fn derive_list(attr_item: &Node, src: &[u8]) -> Vec<String> {
+    let Some(attr) = child_by_kind(attr_item, "attribute") else {
+        return Vec::new();
+    };
+    let is_derive = attr
+        .child(0)
+        .and_then(|n| n.utf8_text(src).ok())
+        .is_some_and(|name| name == "derive");
+    if !is_derive {
+        return Vec::new();
+    }
+    let Some(args) = child_by_kind(&attr, "token_tree") else {
+        return Vec::new();
+    };
+    let text = args.utf8_text(src).unwrap_or("");
+    text.trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|s| s.split_whitespace().collect::<String>())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Whether an attribute is exactly `#[cfg(test)]`.
+fn is_cfg_test(attr_item: &Node, src: &[u8]) -> bool {
+    child_by_kind(attr_item, "attribute")
+        .and_then(|attr| attr.utf8_text(src).ok())
+        .is_some_and(|text| text.split_whitespace().collect::<String>() == "cfg(test)")
+}
+
 /// Extract name from a node that has an `identifier` or `type_identifier` child.
 fn named_symbol(node: &Node, src: &[u8], meta: &SymbolMeta) -> Option<(String, SymbolMeta)> {
     let name = find_name(node, src)?;
     Some((name, SymbolMeta { category: meta.category, label: meta.label }))
 }
 
+/// Name `extern` blocks by their ABI string, `"C"` when none is given.
+fn extern_symbol(node: &Node, src: &[u8]) -> Option<(String, SymbolMeta)> {
+    let abi = child_by_kind(node, "extern_modifier")
+        .and_then(|m| child_by_kind(&m, "string_literal").and_then(|s| s.utf8_text(src).ok().map(str::to_string)))
+        .unwrap_or_else(|| "\"C\"".to_string());
+    Some((abi, EXTERN))
+}
+
 /// Build a descriptive name for `impl` blocks: "Foo" or "Trait for Foo".
 /// The label "impl" is provided separately, so we don't include it in the name.
 fn impl_symbol(node: &Node, src: &[u8]) -> Option<(String, SymbolMeta)> {
@@ -226,6 +332,11 @@ fn find_name(node: &Node, src: &[u8]) -> Option<String> {
     None
 }
 
+/// Byte range of an item's `name` field; impl and extern blocks have none.
+fn name_range(node: &Node) -> Option<std::ops::Range<usize>> {
+    node.child_by_field_name("name").map(|n| n.byte_range())
+}
+
 fn child_by_kind<'a>(node: &'a Node<'a>, kind: &str) -> Option<Node<'a>> {
     let mut cursor = node.walk();
     let result = node.children(&mut cursor).find(|c| c.kind() == kind);
@@ -244,6 +355,46 @@ mod tests {
         file.symbols
     }
 
+    #[test]
+    fn parse_union_with_fields() {
+        let syms = parse("union IntOrFloat {\n    i: u32,\n    f: f32,\n}\n");
+        assert_eq!(syms.len(), 1);
+        assert_eq!(syms[0].name, "IntOrFloat");
+        assert_eq!(syms[0].label, "union");
+        assert_eq!(syms[0].category, SymbolCategory::Type);
+        let fields: Vec<_> = syms[0].children.iter().map(|c| (c.id.as_str(), c.label.as_str())).collect();
+        assert_eq!(fields, [("test.rs::IntOrFloat/i", "field"), ("test.rs::IntOrFloat/f", "field")]);
+    }
+
+    #[test]
+    fn parse_extern_block_signatures() {
+        let syms = parse(
+            "extern \"C\" {\n    fn abs(x: i32) -> i32;\n    fn strlen(s: *const u8) -> usize;\n    static errno: i32;\n}\n",
+        );
+        assert_eq!(syms.len(), 1);
+        assert_eq!(syms[0].label, "extern");
+        assert_eq!(syms[0].name, "\"C\"");
+        let children: Vec<_> = syms[0].children.iter().map(|c| (c.name.as_str(), c.category)).collect();
+        assert_eq!(
+            children,
+            [
+                ("abs", SymbolCategory::Function),
+                ("strlen", SymbolCategory::Function),
+                ("errno", SymbolCategory::Variable),
+            ]
+        );
+        assert_eq!(syms[0].children[1].line_range, 3..3);
+    }
+
+    #[test]
+    fn cfg_test_items_are_test_only() {
+        let syms = parse("fn prod() {}\n\n#[cfg(test)]\nmod tests {\n    fn helper() {}\n}\n\n#[cfg(feature = \"x\")]\nfn gated() {}\n");
+        let flags: Vec<_> = syms.iter().map(|s| (s.name.as_str(), s.test_only)).collect();
+        assert_eq!(flags, [("prod", false), ("tests", true), ("gated", false)]);
+        // Children inherit through the parent; they aren't flagged themselves.
+        assert!(!syms[1].children[0].test_only);
+    }
+
     #[test]
     fn parse_function() {
         let syms = parse("fn foo() {}");
@@ -252,6 +403,84 @@ mod tests {
         assert_eq!(syms[0].category, SymbolCategory::Function);
     }
 
+    #[test]
+    fn ignore_comments_keeps_hash_stable() {
+        let opts = ParseOptions { ignore_comments: true, ..Default::default() };
+        let hash = |parser: &RustParser, src: &str| {
+            parser.parse_file(Path::new("test.rs"), src).unwrap().symbols[0].merkle_hash
+        };
+        let a = "mod m {\n    /// Adds.\n    fn f() { 1 /* one */ }\n}";
+        let b = "mod m {\n    /// Adds two numbers.\n    fn f() { 1 /* uno */ }\n}";
+        let c = "mod m {\n    /// Adds.\n    fn f() { 2 /* one */ }\n}";
+
+        let ignoring = RustParser::with_options(opts);
+        assert_eq!(hash(&ignoring, a), hash(&ignoring, b));
+        assert_ne!(hash(&ignoring, a), hash(&ignoring, c));
+
+        // Default: comment edits count as changes.
+        let default = RustParser::new();
+        assert_ne!(hash(&default, a), hash(&default, b));
+    }
+
+    #[test]
+    fn module_symbol_covers_glue_lines() {
+        let parser = RustParser::with_options(ParseOptions { module_symbols: true, ..Default::default() });
+        let parse = |src: &str| parser.parse_file(Path::new("src/m.rs"), src).unwrap().symbols;
+
+        let syms = parse("use std::fmt;\n\nfn f() {\n    1\n}\n\nuse std::io;\n");
+        let module = syms.last().unwrap();
+        assert!(module.is_module_symbol());
+        assert_eq!(module.id, "src/m.rs::(module)");
+        assert_eq!(module.line_range, 1..7);
+
+        // Editing an import changes only the module symbol's hash.
+        let edited = parse("use std::fmt::Display;\n\nfn f() {\n    1\n}\n\nuse std::io;\n");
+        assert_ne!(edited[1].content_hash, module.content_hash);
+        assert_eq!(edited[0].content_hash, syms[0].content_hash);
+
+        // Reordering imports doesn't.
+        let reordered = parse("use std::io;\n\nfn f() {\n    1\n}\n\nuse std::fmt;\n");
+        assert_eq!(reordered[1].content_hash, module.content_hash);
+
+        // Nothing outside the symbols, no pseudo-symbol; and it's off by default.
+        assert_eq!(parse("fn f() {}\n\n").len(), 1);
+        assert_eq!(RustParser::new().parse_file(Path::new("a.rs"), "use a;\nfn f() {}").unwrap().symbols.len(), 1);
+    }
+
+    #[test]
+    fn macro_rules_spans_every_arm() {
+        let src = "fn before() {}\n\
+                   macro_rules! point {\n\
+                   \x20   () => { Point { x: 0, y: 0 } };\n\
+                   \x20   ($x:expr) => { Point { x: $x, y: 0 } };\n\
+                   \x20   ($x:expr, $y:expr) => {\n\
+                   \x20       Point { x: $x, y: $y }\n\
+                   \x20   };\n\
+                   }\n\
+                   fn after() {}\n";
+        let syms = parse(src);
+        assert_eq!(syms.len(), 3);
+        let mac = &syms[1];
+        assert_eq!(mac.name, "point");
+        assert_eq!(mac.category, SymbolCategory::Macro);
+        assert_eq!(mac.line_range, 2..8);
+        let text = &src[mac.byte_range.clone()];
+        assert!(text.starts_with("macro_rules! point"));
+        assert!(text.ends_with('}'));
+        assert_eq!(mac.estimated_tokens, estimate_tokens(text));
+        assert!(mac.children.is_empty());
+    }
+
+    #[test]
+    fn parse_derive_list() {
+        let syms = parse(
+            "#[derive(Debug, Clone)]\n/// Doc.\n#[serde(rename_all = \"camelCase\")]\n#[derive(serde::Serialize)]\nstruct Point { x: i32 }\nfn plain() {}",
+        );
+        assert_eq!(syms[0].derives, vec!["Debug", "Clone", "serde::Serialize"]);
+        // Attributes don't leak onto the following item.
+        assert!(syms[1].derives.is_empty());
+    }
+
     #[test]
     fn parse_struct_with_impl() {
         let syms = parse(
@@ -281,4 +510,23 @@ mod tests {
         let syms = parse("");
         assert!(syms.is_empty());
     }
+
+    #[test]
+    fn following_renames_hashes_without_the_declared_name() {
+        let renaming = RustParser::with_options(ParseOptions { follow_renames: true, ..ParseOptions::default() });
+        let hash = |parser: &RustParser, src: &str| {
+            parser.parse_file(Path::new("test.rs"), src).unwrap().symbols[0].content_hash
+        };
+
+        // `f` also occurs in `fn`, `D` in `Debug` and in the doc comment.
+        let cases = [
+            ("fn f() { 1 }", "fn g() { 1 }"),
+            ("/// D does it.\n#[derive(Debug)]\nstruct D { x: u8 }", "/// D does it.\n#[derive(Debug)]\nstruct E { x: u8 }"),
+        ];
+        for (before, after) in cases {
+            assert_eq!(hash(&renaming, before), hash(&renaming, after), "{before}");
+            assert_ne!(hash(&RustParser::new(), before), hash(&RustParser::new(), after), "{before}");
+        }
+        assert_ne!(hash(&renaming, "fn f() { 1 }"), hash(&renaming, "fn g() { 2 }"));
+    }
 }
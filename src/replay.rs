@@ -0,0 +1,201 @@
+//! Replaying a recorded session's tool calls on a timer, for `--replay`.
+//!
+//! Events are spaced by their real timestamps, scaled by the playback speed.
+//! Idle stretches longer than `MAX_GAP` are shortened to it so a replay never
+//! sits still for minutes.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::ingest::AgentToolCall;
+
+/// Longest wait between two replayed events, in session time.
+pub const MAX_GAP: Duration = Duration::from_secs(10);
+
+/// Slowest and fastest playback speeds `slower` and `faster` allow.
+pub const MIN_SPEED: f64 = 0.25;
+pub const MAX_SPEED: f64 = 64.0;
+
+/// Parse a `--replay` speed. Anything but a finite number is refused; finite
+/// speeds out of range are clamped by `Replay::new`.
+pub fn parse_speed(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(speed) if speed.is_finite() => Ok(speed),
+        Ok(_) => Err(format!("speed must be a finite number, got {arg}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A session's events waiting to be applied, and the playback state.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// Events with their offset from the first one (gaps capped at `MAX_GAP`).
+    queue: VecDeque<(Duration, AgentToolCall)>,
+    total: usize,
+    pub speed: f64,
+    pub paused: bool,
+    /// Session time replayed so far.
+    position: Duration,
+    last_advance: Option<Instant>,
+}
+
+impl Replay {
+    /// Queue `events` in timestamp order. Events without a parseable
+    /// timestamp keep the time of the event before them.
+    pub fn new(events: Vec<AgentToolCall>, speed: f64) -> Self {
+        let mut timed: Vec<(Option<DateTime<FixedOffset>>, AgentToolCall)> = Vec::with_capacity(events.len());
+        let mut last = None;
+        for event in events {
+            let at = DateTime::parse_from_rfc3339(&event.timestamp_str).ok().or(last);
+            last = at;
+            timed.push((at, event));
+        }
+        timed.sort_by_key(|(at, _)| *at);
+
+        let mut queue = VecDeque::with_capacity(timed.len());
+        let mut offset = Duration::ZERO;
+        let mut prev: Option<DateTime<FixedOffset>> = None;
+        for (at, event) in timed {
+            if let (Some(prev), Some(at)) = (prev, at) {
+                let gap = (at - prev).to_std().unwrap_or_default();
+                offset += gap.min(MAX_GAP);
+            }
+            prev = at.or(prev);
+            queue.push_back((offset, event));
+        }
+
+        Self {
+            total: queue.len(),
+            queue,
+            speed: speed.clamp(MIN_SPEED, MAX_SPEED),
+            paused: false,
+            position: Duration::ZERO,
+            last_advance: None,
+        }
+    }
+
+    /// Move session time forward by the wall time since the last call, scaled
+    /// by the speed, and return the events that became due. Nothing plays
+    /// while paused, and the first call only starts the clock.
+    pub fn advance(&mut self, now: Instant) -> Vec<AgentToolCall> {
+        if let Some(last) = self.last_advance {
+            if !self.paused {
+                self.position += now.duration_since(last).mul_f64(self.speed);
+            }
+        }
+        self.last_advance = Some(now);
+
+        let mut due = Vec::new();
+        while self.queue.front().is_some_and(|(offset, _)| *offset <= self.position) {
+            due.extend(self.queue.pop_front().map(|(_, event)| event));
+        }
+        due
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Double the speed, up to `MAX_SPEED`.
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    /// Halve the speed, down to `MIN_SPEED`.
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+
+    /// Events not yet applied.
+    pub fn remaining(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Events already applied.
+    pub fn played(&self) -> usize {
+        self.total - self.queue.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use super::*;
+    use crate::tracking::ReadDepth;
+
+    fn at(path: &str, timestamp: &str) -> AgentToolCall {
+        let mut event = tool_call("Read", path, ReadDepth::FullBody);
+        event.timestamp_str = timestamp.into();
+        event
+    }
+
+    fn paths(events: &[AgentToolCall]) -> Vec<String> {
+        events.iter().map(|e| e.file_path.as_ref().unwrap().display().to_string()).collect()
+    }
+
+    #[test]
+    fn events_play_at_scaled_timestamps() {
+        let mut replay = Replay::new(
+            vec![
+                at("b.rs", "2025-01-01T00:00:04Z"),
+                at("a.rs", "2025-01-01T00:00:00Z"),
+                at("c.rs", "2025-01-01T00:00:08Z"),
+            ],
+            2.0,
+        );
+        let start = Instant::now();
+        assert_eq!(paths(&replay.advance(start)), ["a.rs"]);
+        // Four session seconds take two wall seconds at 2x.
+        assert!(replay.advance(start + Duration::from_millis(1900)).is_empty());
+        assert_eq!(paths(&replay.advance(start + Duration::from_secs(2))), ["b.rs"]);
+        assert_eq!(replay.played(), 2);
+        assert_eq!(replay.remaining(), 1);
+    }
+
+    #[test]
+    fn long_gaps_are_capped_and_pause_stops_the_clock() {
+        let mut replay = Replay::new(
+            vec![at("a.rs", "2025-01-01T00:00:00Z"), at("b.rs", "2025-01-01T01:00:00Z")],
+            1.0,
+        );
+        let start = Instant::now();
+        replay.advance(start);
+        replay.toggle_pause();
+        assert!(replay.advance(start + MAX_GAP * 2).is_empty());
+        replay.toggle_pause();
+        assert!(replay.advance(start + MAX_GAP * 3 - Duration::from_secs(1)).is_empty());
+        assert_eq!(paths(&replay.advance(start + MAX_GAP * 3)), ["b.rs"]);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn speed_stays_within_bounds() {
+        let mut replay = Replay::new(Vec::new(), 100.0);
+        assert_eq!(replay.speed, MAX_SPEED);
+        replay.faster();
+        assert_eq!(replay.speed, MAX_SPEED);
+        for _ in 0..10 {
+            replay.slower();
+        }
+        assert_eq!(replay.speed, MIN_SPEED);
+    }
+
+    #[test]
+    fn speed_must_be_finite() {
+        assert_eq!(parse_speed("2.5"), Ok(2.5));
+        assert!(parse_speed("nan").is_err());
+        assert!(parse_speed("inf").is_err());
+        assert!(parse_speed("fast").is_err());
+    }
+}
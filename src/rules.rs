@@ -0,0 +1,272 @@
+//! Coverage policy checks, configured in the `[rules]` section of
+//! `.ambits.toml` and evaluated against a finished session's ledger.
+
+use serde::{Deserialize, Serialize};
+
+use crate::coverage::{count_symbols_for_agent, CountMode, CoverageReport};
+use crate::symbols::ProjectTree;
+use crate::tracking::{ContextLedger, ReadDepth};
+
+/// Rules a session must satisfy. Unset rules are not checked.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    /// Minimum percentage of symbols seen at any depth.
+    pub min_seen_percent: Option<f64>,
+    /// Minimum percentage of symbols read at full body.
+    pub min_full_percent: Option<f64>,
+    /// Every edited symbol must have been read at full body before its first edit.
+    #[serde(default)]
+    pub read_before_edit: bool,
+    /// No symbol may be stale when the session ends.
+    #[serde(default)]
+    pub no_stale: bool,
+}
+
+/// Which reads count toward a file's declared requirement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequiredLevel {
+    /// Seen at any depth.
+    Seen,
+    /// Read at full body.
+    Full,
+}
+
+/// The coverage a file declares for itself in a leading comment, such as
+/// `// ambits: require full` (see `parser::required_coverage`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FileRequirement {
+    pub level: RequiredLevel,
+    pub percent: f64,
+}
+
+/// Pass/fail for one rule, with the reasons it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleOutcome {
+    pub rule: &'static str,
+    pub passed: bool,
+    pub details: Vec<String>,
+}
+
+impl Rules {
+    pub fn is_empty(&self) -> bool {
+        self.min_seen_percent.is_none() && self.min_full_percent.is_none() && !self.read_before_edit && !self.no_stale
+    }
+
+    /// Check the rules against a session replayed from its logs, as `--ci` does.
+    /// No file watcher ran, so symbols of files modified after their last read
+    /// are made Stale first.
+    pub fn evaluate_session(&self, project_tree: &ProjectTree, ledger: &mut ContextLedger, mode: CountMode) -> Vec<RuleOutcome> {
+        ledger.mark_modified_files_stale(project_tree);
+        self.evaluate(project_tree, ledger, mode)
+    }
+
+    /// Check every configured rule, in declaration order.
+    pub fn evaluate(&self, project_tree: &ProjectTree, ledger: &ContextLedger, mode: CountMode) -> Vec<RuleOutcome> {
+        let report = CoverageReport::from_project_with(project_tree, ledger, mode);
+        let mut outcomes = Vec::new();
+
+        let mut threshold = |rule, actual: f64, min: Option<f64>| {
+            if let Some(min) = min {
+                let passed = actual >= min;
+                let details = if passed { vec![] } else { vec![format!("{actual:.1}% is below {min}%")] };
+                outcomes.push(RuleOutcome { rule, passed, details });
+            }
+        };
+        threshold("min_seen_percent", report.total_seen_percent(), self.min_seen_percent);
+        threshold("min_full_percent", report.total_full_percent(), self.min_full_percent);
+
+        if self.read_before_edit {
+            let unread = edited_before_read(ledger);
+            outcomes.push(RuleOutcome {
+                rule: "read_before_edit",
+                passed: unread.is_empty(),
+                details: unread.into_iter().map(|id| format!("{id} was edited before being read")).collect(),
+            });
+        }
+
+        if self.no_stale {
+            let mut stale: Vec<&str> = ledger
+                .entries
+                .values()
+                .filter(|e| e.depth == ReadDepth::Stale)
+                .map(|e| e.symbol_id.as_str())
+                .collect();
+            stale.sort_unstable();
+            outcomes.push(RuleOutcome {
+                rule: "no_stale",
+                passed: stale.is_empty(),
+                details: stale.into_iter().map(|id| format!("{id} is stale")).collect(),
+            });
+        }
+
+        if project_tree.files.iter().any(|f| f.required.is_some()) {
+            let unmet = unmet_file_requirements(project_tree, ledger, mode);
+            outcomes.push(RuleOutcome { rule: "file_requirements", passed: unmet.is_empty(), details: unmet });
+        }
+
+        outcomes
+    }
+}
+
+/// One message per file whose coverage is below what its `ambits: require`
+/// comment asks for, in tree order.
+pub fn unmet_file_requirements(project_tree: &ProjectTree, ledger: &ContextLedger, mode: CountMode) -> Vec<String> {
+    project_tree
+        .files
+        .iter()
+        .filter_map(|file| {
+            let required = file.required?;
+            let (total, seen, full) = count_symbols_for_agent(&file.symbols, ledger, None, mode);
+            let (count, level) = match required.level {
+                RequiredLevel::Seen => (seen, "seen"),
+                RequiredLevel::Full => (full, "full"),
+            };
+            let actual = if total == 0 { 100.0 } else { count as f64 / total as f64 * 100.0 };
+            (actual < required.percent).then(|| {
+                format!(
+                    "{} requires {}% {level}, has {actual:.1}%",
+                    file.file_path.display(),
+                    required.percent
+                )
+            })
+        })
+        .collect()
+}
+
+/// Symbols that were edited without having been read at full body first, sorted.
+pub fn edited_before_read(ledger: &ContextLedger) -> Vec<&str> {
+    let mut unread: Vec<&str> = ledger
+        .edited
+        .iter()
+        .filter(|(_, read_first)| !**read_first)
+        .map(|(id, _)| id.as_str())
+        .collect();
+    unread.sort_unstable();
+    unread
+}
+
+/// One `PASS`/`FAIL` line per rule, failures followed by their details.
+pub fn format_outcomes(outcomes: &[RuleOutcome]) -> String {
+    let mut output = String::new();
+    for outcome in outcomes {
+        let status = if outcome.passed { "PASS" } else { "FAIL" };
+        output.push_str(&format!("{status}  {}\n", outcome.rule));
+        for detail in &outcome.details {
+            output.push_str(&format!("      {detail}\n"));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+#[path = "../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::helpers::*;
+
+    #[test]
+    fn file_requirements_are_checked_per_file() {
+        let mut strict = file("core.rs", vec![sym("core.rs::f", "f"), sym("core.rs::g", "g")]);
+        strict.required = Some(FileRequirement { level: RequiredLevel::Full, percent: 100.0 });
+        let mut loose = file("util.rs", vec![sym("util.rs::h", "h")]);
+        loose.required = Some(FileRequirement { level: RequiredLevel::Seen, percent: 50.0 });
+        let tree = project(vec![strict, loose, file("other.rs", vec![sym("other.rs::i", "i")])]);
+
+        let mut ledger = ContextLedger::new();
+        ledger.record("core.rs::f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record("core.rs::g".into(), ReadDepth::Signature, [0; 32], "ag".into(), 10);
+        ledger.record("util.rs::h".into(), ReadDepth::NameOnly, [0; 32], "ag".into(), 10);
+        assert_eq!(
+            unmet_file_requirements(&tree, &ledger, CountMode::ALL),
+            ["core.rs requires 100% full, has 50.0%"]
+        );
+
+        // Declared requirements are checked in --ci even without other rules.
+        let outcomes = Rules::default().evaluate(&tree, &ledger, CountMode::ALL);
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!((outcomes[0].rule, outcomes[0].passed), ("file_requirements", false));
+    }
+
+    #[test]
+    fn evaluates_thresholds_edits_and_staleness() {
+        let tree = project(vec![file("a.rs", vec![sym("a.rs::f", "f"), sym("a.rs::g", "g")])]);
+        let mut ledger = ContextLedger::new();
+        ledger.record("a.rs::f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record_edit("a.rs::f");
+        ledger.record_edit("a.rs::g");
+        ledger.record("a.rs::g".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record("a.rs::g".into(), ReadDepth::Stale, [1; 32], "ag".into(), 10);
+
+        let rules = Rules {
+            min_seen_percent: Some(100.0),
+            min_full_percent: Some(75.0),
+            read_before_edit: true,
+            no_stale: true,
+        };
+        let outcomes = rules.evaluate(&tree, &ledger, CountMode::ALL);
+        let passed: Vec<_> = outcomes.iter().map(|o| (o.rule, o.passed)).collect();
+        assert_eq!(
+            passed,
+            vec![
+                ("min_seen_percent", true),
+                ("min_full_percent", false),
+                ("read_before_edit", false),
+                ("no_stale", false),
+            ]
+        );
+        assert_eq!(outcomes[1].details, vec!["50.0% is below 75%"]);
+        assert_eq!(outcomes[2].details, vec!["a.rs::g was edited before being read"]);
+
+        let text = format_outcomes(&outcomes);
+        assert!(text.starts_with("PASS  min_seen_percent\nFAIL  min_full_percent\n      50.0% is below 75%\n"));
+    }
+
+    #[test]
+    fn session_fails_no_stale_when_a_file_changed_after_its_read() {
+        let mut tree = project(vec![file("a.rs", vec![sym("a.rs::f", "f")])]);
+        let mut ledger = ContextLedger::new();
+        let read_at = chrono::DateTime::parse_from_rfc3339("2026-01-01T10:00:00Z").unwrap().to_utc();
+        ledger.record("a.rs::f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record_file_read(std::path::Path::new("a.rs"), read_at);
+        let rules = Rules { no_stale: true, ..Rules::default() };
+
+        tree.files[0].modified = Some(read_at.into());
+        let outcomes = rules.evaluate_session(&tree, &mut ledger, CountMode::ALL);
+        assert_eq!((outcomes[0].rule, outcomes[0].passed), ("no_stale", true));
+
+        tree.files[0].modified = Some((read_at + chrono::Duration::minutes(5)).into());
+        let outcomes = rules.evaluate_session(&tree, &mut ledger, CountMode::ALL);
+        assert_eq!((outcomes[0].rule, outcomes[0].passed), ("no_stale", false));
+        assert_eq!(outcomes[0].details, vec!["a.rs::f is stale"]);
+    }
+
+    #[test]
+    fn edited_before_read_ignores_later_reads() {
+        let mut ledger = ContextLedger::new();
+        assert!(edited_before_read(&ledger).is_empty());
+
+        ledger.record("a.rs::f".into(), ReadDepth::Signature, [0; 32], "ag".into(), 10);
+        ledger.record_edit("a.rs::f");
+        ledger.record("a.rs::f".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record_edit("a.rs::f");
+        ledger.record_edit("a.rs::b");
+        ledger.record("a.rs::c".into(), ReadDepth::FullBody, [0; 32], "ag".into(), 10);
+        ledger.record_edit("a.rs::c");
+        assert_eq!(edited_before_read(&ledger), ["a.rs::b", "a.rs::f"]);
+    }
+
+    #[test]
+    fn rules_parse_from_config() {
+        let config = crate::config::Config::parse("[rules]\nmin_full_percent = 80\nno_stale = true\n").unwrap();
+        assert_eq!(config.rules.min_full_percent, Some(80.0));
+        assert!(config.rules.no_stale && !config.rules.read_before_edit);
+        assert!(crate::config::Config::parse("[rules]\nbogus = 1\n").is_err());
+        assert!(Rules::default().is_empty());
+    }
+}
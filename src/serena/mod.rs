@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -7,19 +7,35 @@ use serde_pickle::value::{HashableValue, Value};
 use sha2::{Digest, Sha256};
 
 use ambits::symbols::merkle::compute_merkle_hash;
-use ambits::symbols::{FileSymbols, ProjectTree, SymbolCategory, SymbolNode};
+use ambits::symbols::{id_path, FileSymbols, ProjectTree, SymbolCategory, SymbolNode};
 
-/// Scan a project using Serena's cached symbol data (.pkl files).
-pub fn scan_project_serena(project_root: &Path) -> Result<ProjectTree> {
-    let pkl_files = find_serena_caches(project_root);
+/// Where Serena keeps its cache unless told otherwise: `<project_root>/.serena/cache`.
+pub fn default_cache_dir(project_root: &Path) -> PathBuf {
+    project_root.join(".serena").join("cache")
+}
+
+/// A tree read from Serena's cache, and anything about the cache worth
+/// telling the user.
+pub struct SerenaScan {
+    pub tree: ProjectTree,
+    pub warnings: Vec<String>,
+}
+
+/// Scan a project using Serena's cached symbol data (.pkl files) in `cache_dir`.
+/// Files the cache doesn't cover are taken from `fallback` (a tree-sitter scan)
+/// when one is given; Serena wins for files it has.
+pub fn scan_project_serena(
+    project_root: &Path,
+    cache_dir: &Path,
+    fallback: Option<ProjectTree>,
+) -> Result<SerenaScan> {
+    let pkl_files = find_serena_caches(cache_dir);
     if pkl_files.is_empty() {
-        bail!(
-            "No Serena cache found at {}/.serena/cache/",
-            project_root.display()
-        );
+        bail!("No Serena cache found at {}", cache_dir.display());
     }
 
     let mut all_files = Vec::new();
+    let mut warnings = Vec::new();
     for pkl_path in &pkl_files {
         let data = fs::read(pkl_path)?;
         let value = serde_pickle::value_from_slice(&data, Default::default())
@@ -30,27 +46,30 @@ pub fn scan_project_serena(project_root: &Path) -> Result<ProjectTree> {
             .map(|n| n == "raw_document_symbols.pkl")
             .unwrap_or(false);
 
-        let files = if is_raw {
-            parse_raw_pickle(&value)?
-        } else {
-            parse_document_pickle(&value)?
-        };
+        let format = if is_raw { CacheFormat::Raw } else { CacheFormat::Document };
+        let (files, warning) = parse_cache(&value, format, pkl_path, project_root)?;
         all_files.extend(files);
+        warnings.extend(warning);
+    }
+    if let Some(fallback) = fallback {
+        merge_fallback(&mut all_files, fallback);
     }
 
     all_files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
 
-    Ok(ProjectTree {
-        root: project_root.to_path_buf(),
-        files: all_files,
+    Ok(SerenaScan {
+        tree: ProjectTree {
+            root: project_root.to_path_buf(),
+            files: all_files,
+        },
+        warnings,
     })
 }
 
-/// Find all Serena cache pickle files for a project.
+/// Find all Serena cache pickle files in a cache directory.
 /// Prefers raw_document_symbols.pkl over document_symbols.pkl per language.
-pub fn find_serena_caches(project_root: &Path) -> Vec<PathBuf> {
-    let cache_dir = project_root.join(".serena").join("cache");
-    let entries = match fs::read_dir(&cache_dir) {
+pub fn find_serena_caches(cache_dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(cache_dir) {
         Ok(e) => e,
         Err(_) => return Vec::new(),
     };
@@ -74,16 +93,143 @@ pub fn find_serena_caches(project_root: &Path) -> Vec<PathBuf> {
     results
 }
 
+/// Add the files from `fallback` that Serena has no entry for.
+fn merge_fallback(files: &mut Vec<FileSymbols>, fallback: ProjectTree) {
+    let indexed: HashSet<PathBuf> = files.iter().map(|f| f.file_path.clone()).collect();
+    files.extend(
+        fallback
+            .files
+            .into_iter()
+            .filter(|f| !indexed.contains(&f.file_path)),
+    );
+}
+
+/// A cache path relative to `project_root`, which symbol ids are built from.
+/// Absolute paths under the root just lose the prefix; others (a cache written
+/// for another checkout) keep the longest suffix that exists in the project.
+/// Paths that match nothing are left alone.
+fn project_relative(path: &Path, project_root: &Path) -> PathBuf {
+    if !path.is_absolute() {
+        return path.to_path_buf();
+    }
+    if let Ok(rel) = path.strip_prefix(project_root) {
+        return rel.to_path_buf();
+    }
+    let components: Vec<_> = path.components().collect();
+    (1..components.len())
+        .map(|i| components[i..].iter().collect::<PathBuf>())
+        .find(|suffix| project_root.join(suffix).is_file())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// The two pickle layouts Serena writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheFormat {
+    /// raw_document_symbols.pkl
+    Raw,
+    /// document_symbols.pkl
+    Document,
+}
+
+impl CacheFormat {
+    /// `__cache_version` values this layout's parser was written against.
+    fn known_versions(self) -> &'static [&'static [i64]] {
+        match self {
+            CacheFormat::Raw => &[&[1, 1]],
+            CacheFormat::Document => &[&[3]],
+        }
+    }
+
+    fn parse(self, value: &Value, project_root: &Path) -> Result<Vec<FileSymbols>> {
+        match self {
+            CacheFormat::Raw => parse_raw_pickle(value, project_root),
+            CacheFormat::Document => parse_document_pickle(value, project_root),
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            CacheFormat::Raw => CacheFormat::Document,
+            CacheFormat::Document => CacheFormat::Raw,
+        }
+    }
+}
+
+/// Parse a cache pickle, checking `__cache_version` first. An unknown version
+/// comes back with a warning naming it and the parser being attempted; if that
+/// parser fails, the other layout's parser is tried before giving up.
+fn parse_cache(
+    value: &Value,
+    format: CacheFormat,
+    pkl_path: &Path,
+    project_root: &Path,
+) -> Result<(Vec<FileSymbols>, Option<String>)> {
+    let version = cache_version(value);
+    let known = format.known_versions();
+    if version.as_deref().is_some_and(|v| known.contains(&v)) {
+        return Ok((format.parse(value, project_root)?, None));
+    }
+
+    let shown = version
+        .as_deref()
+        .map(format_version)
+        .unwrap_or_else(|| "(missing)".to_string());
+    let attempt = version
+        .as_deref()
+        .map(|v| closest_version(v, known))
+        .unwrap_or(known[known.len() - 1]);
+    let warning = format!(
+        "Unsupported Serena cache version {shown} in {}, attempting v{} parser",
+        pkl_path.display(),
+        format_version(attempt),
+    );
+
+    let files = format.parse(value, project_root).or_else(|first_err| {
+        format.other().parse(value, project_root).map_err(|_| {
+            eyre!(
+                "Failed to read Serena cache {} (version {shown}): {first_err}",
+                pkl_path.display()
+            )
+        })
+    })?;
+    Ok((files, Some(warning)))
+}
+
+/// Read `__cache_version` as a list of integers: `3` → [3], `(1, 1)` → [1, 1].
+fn cache_version(value: &Value) -> Option<Vec<i64>> {
+    let v = dict_get(value, "__cache_version")?;
+    match v {
+        Value::I64(n) => Some(vec![*n]),
+        Value::Tuple(items) | Value::List(items) => items.iter().map(as_i64).collect(),
+        _ => None,
+    }
+}
+
+fn format_version(version: &[i64]) -> String {
+    version.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(".")
+}
+
+/// The newest known version not newer than `version`, else the oldest known one.
+fn closest_version<'a>(version: &[i64], known: &[&'a [i64]]) -> &'a [i64] {
+    known
+        .iter()
+        .rev()
+        .find(|k| **k <= version)
+        .or_else(|| known.first())
+        .copied()
+        .unwrap_or(&[])
+}
+
 /// Parse raw_document_symbols.pkl format.
 /// Structure: {"__cache_version": (1,1), "obj": {path: (hash, [symbols])}}
-fn parse_raw_pickle(value: &Value) -> Result<Vec<FileSymbols>> {
+fn parse_raw_pickle(value: &Value, project_root: &Path) -> Result<Vec<FileSymbols>> {
     let obj = dict_get(value, "obj").ok_or_else(|| eyre!("Missing 'obj' key in pickle"))?;
     let entries = as_dict(obj).ok_or_else(|| eyre!("'obj' is not a dict"))?;
 
     let mut files = Vec::new();
     for (key, val) in entries {
         let file_path_str = hashable_as_str(key).ok_or_else(|| eyre!("File key not a string"))?;
-        let file_path = PathBuf::from(file_path_str);
+        let file_path = project_relative(Path::new(file_path_str), project_root);
 
         // Value is a tuple: (content_hash_str, [symbol_dicts])
         let items = as_tuple(val).ok_or_else(|| eyre!("File entry not a tuple"))?;
@@ -93,7 +239,7 @@ fn parse_raw_pickle(value: &Value) -> Result<Vec<FileSymbols>> {
         let symbol_list =
             as_list(&items[1]).ok_or_else(|| eyre!("Symbol list not an array for {file_path_str}"))?;
 
-        let path_prefix = file_path.to_string_lossy();
+        let path_prefix = id_path(&file_path);
         let mut symbols = Vec::new();
         for sym_val in symbol_list {
             if let Ok(node) = convert_symbol(sym_val, &file_path, &path_prefix, "") {
@@ -106,6 +252,8 @@ fn parse_raw_pickle(value: &Value) -> Result<Vec<FileSymbols>> {
             file_path,
             symbols,
             total_lines,
+            modified: None,
+            required: None,
         });
     }
     Ok(files)
@@ -114,14 +262,14 @@ fn parse_raw_pickle(value: &Value) -> Result<Vec<FileSymbols>> {
 /// Parse document_symbols.pkl format.
 /// Structure: {"__cache_version": 3, "obj": {path: (hash, DocumentSymbols_state)}}
 /// serde-pickle extracts the class instance as its __getstate__ dict.
-fn parse_document_pickle(value: &Value) -> Result<Vec<FileSymbols>> {
+fn parse_document_pickle(value: &Value, project_root: &Path) -> Result<Vec<FileSymbols>> {
     let obj = dict_get(value, "obj").ok_or_else(|| eyre!("Missing 'obj' key in pickle"))?;
     let entries = as_dict(obj).ok_or_else(|| eyre!("'obj' is not a dict"))?;
 
     let mut files = Vec::new();
     for (key, val) in entries {
         let file_path_str = hashable_as_str(key).ok_or_else(|| eyre!("File key not a string"))?;
-        let file_path = PathBuf::from(file_path_str);
+        let file_path = project_relative(Path::new(file_path_str), project_root);
 
         let items = as_tuple(val).ok_or_else(|| eyre!("File entry not a tuple"))?;
         if items.len() < 2 {
@@ -135,7 +283,7 @@ fn parse_document_pickle(value: &Value) -> Result<Vec<FileSymbols>> {
             .or_else(|| as_list(state)) // fallback: might be a plain list
             .ok_or_else(|| eyre!("Cannot find symbols for {file_path_str}"))?;
 
-        let path_prefix = file_path.to_string_lossy();
+        let path_prefix = id_path(&file_path);
         let mut symbols = Vec::new();
         for sym_val in symbol_list {
             if let Ok(node) = convert_symbol(sym_val, &file_path, &path_prefix, "") {
@@ -148,6 +296,8 @@ fn parse_document_pickle(value: &Value) -> Result<Vec<FileSymbols>> {
             file_path,
             symbols,
             total_lines,
+            modified: None,
+            required: None,
         });
     }
     Ok(files)
@@ -216,6 +366,10 @@ fn convert_symbol(
         merkle_hash: [0u8; 32],
         children,
         estimated_tokens: line_count * 15,
+        derives: Vec::new(),
+        decorators: Vec::new(),
+        test_only: false,
+        name_range: None,
     };
     compute_merkle_hash(&mut node);
     Ok(node)
@@ -330,3 +484,112 @@ fn as_dict(val: &Value) -> Option<&BTreeMap<HashableValue, Value>> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pickle_with_version(version: Value) -> Value {
+        let mut dict = BTreeMap::new();
+        dict.insert(HashableValue::String("__cache_version".into()), version);
+        dict.insert(HashableValue::String("obj".into()), Value::Dict(BTreeMap::new()));
+        Value::Dict(dict)
+    }
+
+    #[test]
+    fn reads_int_and_tuple_versions() {
+        assert_eq!(cache_version(&pickle_with_version(Value::I64(3))), Some(vec![3]));
+        let tuple = Value::Tuple(vec![Value::I64(1), Value::I64(1)]);
+        assert_eq!(cache_version(&pickle_with_version(tuple)), Some(vec![1, 1]));
+    }
+
+    #[test]
+    fn closest_version_prefers_older_known() {
+        let known: &[&[i64]] = &[&[2], &[3]];
+        assert_eq!(closest_version(&[4], known), &[3]);
+        assert_eq!(closest_version(&[2], known), &[2]);
+        assert_eq!(closest_version(&[1], known), &[2]);
+    }
+
+    #[test]
+    fn cache_paths_become_project_relative() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+
+        // Under the project root.
+        assert_eq!(project_relative(&root.join("src/lib.rs"), root), PathBuf::from("src/lib.rs"));
+        // Written for another checkout: the existing suffix wins.
+        assert_eq!(
+            project_relative(Path::new("/home/ci/checkout/src/lib.rs"), root),
+            PathBuf::from("src/lib.rs")
+        );
+        // Already relative, or matching nothing: unchanged.
+        assert_eq!(project_relative(Path::new("src/main.rs"), root), PathBuf::from("src/main.rs"));
+        assert_eq!(
+            project_relative(Path::new("/elsewhere/other.rs"), root),
+            PathBuf::from("/elsewhere/other.rs")
+        );
+    }
+
+    #[test]
+    fn ids_match_tree_sitter_for_absolute_cache_paths() {
+        let mut sym = BTreeMap::new();
+        sym.insert(HashableValue::String("name".into()), Value::String("foo".into()));
+        let entry = Value::Tuple(vec![Value::String("hash".into()), Value::List(vec![Value::Dict(sym)])]);
+        let mut obj = BTreeMap::new();
+        obj.insert(HashableValue::String("/proj/src/app.py".into()), entry);
+        let mut dict = BTreeMap::new();
+        dict.insert(
+            HashableValue::String("__cache_version".into()),
+            Value::Tuple(vec![Value::I64(1), Value::I64(1)]),
+        );
+        dict.insert(HashableValue::String("obj".into()), Value::Dict(obj));
+
+        let (files, warning) = parse_cache(&Value::Dict(dict), CacheFormat::Raw, Path::new("raw.pkl"), Path::new("/proj")).unwrap();
+        assert_eq!(warning, None);
+        assert_eq!(files[0].file_path, PathBuf::from("src/app.py"));
+        assert_eq!(files[0].symbols[0].id, "src/app.py::foo");
+        assert_eq!(files[0].symbols[0].file_path, PathBuf::from("src/app.py"));
+    }
+
+    #[test]
+    fn fallback_fills_files_missing_from_cache() {
+        let file = |path: &str, tag: &str| FileSymbols {
+            file_path: path.into(),
+            symbols: Vec::new(),
+            total_lines: tag.len(),
+            modified: None,
+            required: None,
+        };
+        let mut files = vec![file("src/a.rs", "serena")];
+        let fallback = ProjectTree {
+            root: PathBuf::from("/p"),
+            files: vec![file("src/a.rs", "ts"), file("src/b.rs", "ts")],
+        };
+        merge_fallback(&mut files, fallback);
+
+        let paths: Vec<_> = files.iter().map(|f| f.file_path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["src/a.rs", "src/b.rs"]);
+        // Serena's entry for a.rs is the one kept.
+        assert_eq!(files[0].total_lines, "serena".len());
+    }
+
+    #[test]
+    fn finds_caches_in_any_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tmp.path().join("rust")).unwrap();
+        std::fs::write(tmp.path().join("rust/document_symbols.pkl"), "").unwrap();
+        assert_eq!(find_serena_caches(tmp.path()), vec![tmp.path().join("rust/document_symbols.pkl")]);
+        assert!(find_serena_caches(&tmp.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn unknown_version_still_parses() {
+        let value = pickle_with_version(Value::I64(4));
+        let (files, warning) = parse_cache(&value, CacheFormat::Document, Path::new("document_symbols.pkl"), Path::new("/p")).unwrap();
+        assert!(files.is_empty());
+        assert!(warning.unwrap().starts_with("Unsupported Serena cache version 4 in document_symbols.pkl"));
+    }
+}
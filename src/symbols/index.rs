@@ -0,0 +1,236 @@
+//! Per-file lookup tables built once per scan, so applying a targeted tool
+//! call only visits the symbols it can touch instead of every symbol in the file.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::{uncovered_lines, ProjectTree, SymbolId, SymbolNode};
+
+/// A symbol flattened out of the tree, with the fields marking needs.
+#[derive(Debug, Clone)]
+pub struct IndexedSymbol {
+    pub id: SymbolId,
+    pub name: String,
+    pub line_range: Range<usize>,
+    pub content_hash: [u8; 32],
+    pub estimated_tokens: usize,
+    /// The symbol's descendants are the entries after it, up to this index.
+    pub subtree_end: usize,
+    /// Levels below the file's top level (0 for top-level symbols).
+    pub depth: usize,
+    /// Lines covered by the symbol together with all its descendants.
+    span: Range<usize>,
+    children: Level,
+}
+
+/// Sibling entries sorted by span start, with the running max of span ends
+/// so overlap queries can stop early.
+#[derive(Debug, Clone, Default)]
+struct Level {
+    members: Vec<usize>,
+    max_end: Vec<usize>,
+}
+
+/// Lookup tables for one file's symbols.
+#[derive(Debug, Clone, Default)]
+pub struct FileIndex {
+    /// Every symbol in pre-order.
+    pub symbols: Vec<IndexedSymbol>,
+    roots: Level,
+    /// Entries keyed by symbol name and by the last segment of their name path.
+    by_name: HashMap<String, Vec<usize>>,
+    /// The module pseudo-symbol, with the module-level lines it stands for.
+    module: Option<(usize, Vec<usize>)>,
+}
+
+impl FileIndex {
+    pub fn new(symbols: &[SymbolNode]) -> Self {
+        let mut index = Self::default();
+        index.roots = index.add_level(symbols.iter().filter(|s| !s.is_module_symbol()), 0);
+
+        if let Some(module) = symbols.iter().find(|s| s.is_module_symbol()) {
+            let lines = uncovered_lines(symbols, module.line_range.end)
+                .into_iter()
+                .filter(|&line| line >= module.line_range.start)
+                .collect();
+            let idx = index.push(module, 0);
+            index.module = Some((idx, lines));
+        }
+        index
+    }
+
+    fn push(&mut self, sym: &SymbolNode, depth: usize) -> usize {
+        let idx = self.symbols.len();
+        self.symbols.push(IndexedSymbol {
+            id: sym.id.clone(),
+            name: sym.name.clone(),
+            line_range: sym.line_range.clone(),
+            content_hash: sym.content_hash,
+            estimated_tokens: sym.estimated_tokens,
+            subtree_end: idx + 1,
+            depth,
+            span: sym.line_range.clone(),
+            children: Level::default(),
+        });
+        idx
+    }
+
+    fn add_level<'a>(&mut self, symbols: impl Iterator<Item = &'a SymbolNode>, depth: usize) -> Level {
+        let mut members = Vec::new();
+        for sym in symbols {
+            let idx = self.push(sym, depth);
+            if !sym.is_module_symbol() {
+                let name_part = sym.id.split("::").last().unwrap_or(&sym.id);
+                let segment = name_part.rsplit('/').next().unwrap_or(name_part);
+                self.by_name.entry(sym.name.clone()).or_default().push(idx);
+                if segment != sym.name {
+                    self.by_name.entry(segment.to_string()).or_default().push(idx);
+                }
+            }
+
+            let children = self.add_level(sym.children.iter(), depth + 1);
+            let mut span = sym.line_range.clone();
+            for &child in &children.members {
+                let child_span = &self.symbols[child].span;
+                span.start = span.start.min(child_span.start);
+                span.end = span.end.max(child_span.end);
+            }
+            let subtree_end = self.symbols.len();
+            let entry = &mut self.symbols[idx];
+            entry.subtree_end = subtree_end;
+            entry.span = span;
+            entry.children = children;
+            members.push(idx);
+        }
+
+        members.sort_by_key(|&i| self.symbols[i].span.start);
+        let mut max_end = Vec::with_capacity(members.len());
+        let mut running = 0;
+        for &i in &members {
+            running = running.max(self.symbols[i].span.end);
+            max_end.push(running);
+        }
+        Level { members, max_end }
+    }
+
+    /// The highest entries whose own line range overlaps `lines`; symbols
+    /// nested inside a returned entry are left to its subtree. The module
+    /// pseudo-symbol is never returned (see `module_targeted`).
+    pub fn overlapping(&self, lines: &Range<usize>) -> Vec<usize> {
+        let mut out = Vec::new();
+        self.collect_overlapping(&self.roots, lines, &mut out);
+        out
+    }
+
+    fn collect_overlapping(&self, level: &Level, lines: &Range<usize>, out: &mut Vec<usize>) {
+        let k = level.members.partition_point(|&i| self.symbols[i].span.start < lines.end);
+        for j in (0..k).rev() {
+            if level.max_end[j] <= lines.start {
+                break;
+            }
+            let sym = &self.symbols[level.members[j]];
+            if sym.span.end <= lines.start {
+                continue;
+            }
+            if sym.line_range.start < lines.end && lines.start < sym.line_range.end {
+                out.push(level.members[j]);
+            } else {
+                self.collect_overlapping(&sym.children, lines, out);
+            }
+        }
+    }
+
+    /// Entries that may match a symbol-name target: those named `target` or
+    /// whose name path ends in its last segment. Callers apply the exact rule.
+    pub fn name_candidates(&self, target: &str) -> Vec<usize> {
+        let segment = target.rsplit('/').next().unwrap_or(target);
+        let mut out: Vec<usize> = self.by_name.get(target).into_iter().flatten().copied().collect();
+        if segment != target {
+            out.extend(self.by_name.get(segment).into_iter().flatten().copied());
+        }
+        out
+    }
+
+    /// The module pseudo-symbol, if `lines` touches any module-level line.
+    pub fn module_targeted(&self, lines: &Range<usize>) -> Option<usize> {
+        let (idx, module_lines) = self.module.as_ref()?;
+        let first = module_lines.partition_point(|&line| line < lines.start);
+        module_lines.get(first).is_some_and(|&line| line < lines.end).then_some(*idx)
+    }
+}
+
+/// A `FileIndex` for every file in a project tree, keyed by relative path.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    files: HashMap<PathBuf, FileIndex>,
+}
+
+impl SymbolIndex {
+    pub fn new(tree: &ProjectTree) -> Self {
+        Self {
+            files: tree
+                .files
+                .iter()
+                .map(|f| (f.file_path.clone(), FileIndex::new(&f.symbols)))
+                .collect(),
+        }
+    }
+
+    pub fn file(&self, file_path: &Path) -> Option<&FileIndex> {
+        self.files.get(file_path)
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use super::*;
+
+    fn names(index: &FileIndex, hits: &[usize]) -> Vec<String> {
+        let mut out: Vec<String> = hits.iter().map(|&i| index.symbols[i].name.clone()).collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn overlapping_returns_outermost_matches() {
+        let mut imp = sym_with_lines("a.rs::impl A", "impl A", 10, 40);
+        imp.children = vec![
+            sym_with_lines("a.rs::impl A/new", "new", 11, 20),
+            sym_with_lines("a.rs::impl A/run", "run", 21, 39),
+        ];
+        let index = FileIndex::new(&[sym_with_lines("a.rs::f", "f", 1, 5), imp, sym_with_lines("a.rs::g", "g", 45, 50)]);
+
+        assert_eq!(names(&index, &index.overlapping(&(3..12))), ["f", "impl A"]);
+        assert_eq!(names(&index, &index.overlapping(&(41..44))), Vec::<String>::new());
+        assert_eq!(names(&index, &index.overlapping(&(46..47))), ["g"]);
+
+        // The impl's subtree follows it in pre-order.
+        let imp = index.overlapping(&(30..31))[0];
+        assert_eq!(index.symbols[imp].subtree_end - imp, 3);
+    }
+
+    #[test]
+    fn children_outside_parent_range_are_still_found() {
+        let mut parent = sym_with_lines("a.rs::P", "P", 1, 3);
+        parent.children = vec![sym_with_lines("a.rs::P/c", "c", 20, 25)];
+        let index = FileIndex::new(&[parent]);
+        assert_eq!(names(&index, &index.overlapping(&(21..22))), ["c"]);
+    }
+
+    #[test]
+    fn name_candidates_cover_name_paths() {
+        let mut imp = sym("a.rs::impl App", "impl App");
+        imp.children = vec![sym("a.rs::impl App/handle_key", "handle_key")];
+        let index = FileIndex::new(&[imp]);
+        assert_eq!(names(&index, &index.name_candidates("handle_key")), ["handle_key"]);
+        assert_eq!(names(&index, &index.name_candidates("App/handle_key")), ["handle_key"]);
+        assert!(index.name_candidates("missing").is_empty());
+    }
+}
@@ -3,9 +3,10 @@ use sha2::{Digest, Sha256};
 use super::SymbolNode;
 
 /// Compute content hash from the raw source text of a symbol.
-/// Normalizes whitespace to make hashing resilient to formatting changes.
+/// Normalizes whitespace to make hashing resilient to formatting changes,
+/// and ignores a leading byte-order mark.
 pub fn content_hash(source: &str) -> [u8; 32] {
-    let normalized = normalize_source(source);
+    let normalized = normalize_source(source.strip_prefix('\u{feff}').unwrap_or(source));
     let mut hasher = Sha256::new();
     hasher.update(normalized.as_bytes());
     hasher.finalize().into()
@@ -50,6 +51,11 @@ fn normalize_source(source: &str) -> String {
     result.trim().to_string()
 }
 
+/// Lowercase hex encoding of a hash.
+pub fn to_hex(hash: &[u8; 32]) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
 /// Estimate the number of tokens a source string would consume.
 /// Rough approximation: ~3.5 characters per token for code.
 pub fn estimate_tokens(source: &str) -> usize {
@@ -81,6 +87,13 @@ mod tests {
         assert_eq!(h1, h2);
     }
 
+    #[test]
+    fn test_content_hash_ignores_bom_and_crlf() {
+        let h1 = content_hash("fn foo() {\n}\n");
+        assert_eq!(content_hash("\u{feff}fn foo() {\n}\n"), h1);
+        assert_eq!(content_hash("fn foo() {\r\n}\r\n"), h1);
+    }
+
     #[test]
     fn test_content_hash_detects_changes() {
         let h1 = content_hash("fn foo() {}");
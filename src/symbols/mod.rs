@@ -1,14 +1,25 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::ops::Range;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub mod index;
 pub mod merkle;
+pub mod snapshot;
 
 pub type SymbolId = String;
 
+/// Name of the optional per-file pseudo-symbol standing for module-level code
+/// (imports, top-level statements) outside every top-level symbol.
+pub const MODULE_SYMBOL: &str = "(module)";
+
 /// Universal symbol categories for cross-language operations.
 /// These represent broad semantic categories, not language-specific constructs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SymbolCategory {
     /// Modules, packages, namespaces
     Module,
@@ -40,7 +51,7 @@ impl fmt::Display for SymbolCategory {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolNode {
     pub id: SymbolId,
     pub name: String,
@@ -53,6 +64,19 @@ pub struct SymbolNode {
     pub merkle_hash: [u8; 32],
     pub children: Vec<SymbolNode>,
     pub estimated_tokens: usize,
+    /// Traits listed in `#[derive(...)]` attributes on the item (Rust only).
+    pub derives: Vec<String>,
+    /// Decorators applied to the item, e.g. `staticmethod` or `app.route` (Python only).
+    #[serde(default)]
+    pub decorators: Vec<String>,
+    /// Compiled only for tests, e.g. a Rust `#[cfg(test)] mod tests`; the
+    /// symbol's children are test-only too.
+    #[serde(default)]
+    pub test_only: bool,
+    /// Byte range of the name in the declaration, which content hashes leave
+    /// out when following renames. None for symbols without one (impl blocks).
+    #[serde(default)]
+    pub name_range: Option<Range<usize>>,
 }
 
 impl SymbolNode {
@@ -63,14 +87,93 @@ impl SymbolNode {
     pub fn total_tokens(&self) -> usize {
         self.estimated_tokens + self.children.iter().map(|c| c.total_tokens()).sum::<usize>()
     }
+
+    /// Whether this is the synthesized module pseudo-symbol rather than a parsed item.
+    pub fn is_module_symbol(&self) -> bool {
+        self.category == SymbolCategory::Module && self.name == MODULE_SYMBOL
+    }
+}
+
+/// A pre-order walk over a symbol tree, driven by `walk_symbols`.
+///
+/// Closures `FnMut(&SymbolNode, usize) -> &[SymbolNode]` implement it too;
+/// use `visit_symbols` when every symbol should be visited.
+pub trait SymbolVisitor<'a> {
+    /// Handle `sym`, nested `depth` levels below where the walk started, and
+    /// return the symbols to walk next at `depth + 1`: usually `&sym.children`,
+    /// or an empty slice to skip the subtree.
+    fn visit(&mut self, sym: &'a SymbolNode, depth: usize) -> &'a [SymbolNode];
+}
+
+impl<'a, F> SymbolVisitor<'a> for F
+where
+    F: FnMut(&'a SymbolNode, usize) -> &'a [SymbolNode],
+{
+    fn visit(&mut self, sym: &'a SymbolNode, depth: usize) -> &'a [SymbolNode] {
+        self(sym, depth)
+    }
+}
+
+/// Walk `symbols` and whatever the visitor returns for each, parents first.
+pub fn walk_symbols<'a, V: SymbolVisitor<'a> + ?Sized>(symbols: &'a [SymbolNode], depth: usize, visitor: &mut V) {
+    for sym in symbols {
+        let next = visitor.visit(sym, depth);
+        walk_symbols(next, depth + 1, visitor);
+    }
+}
+
+/// Call `f` on every symbol in `symbols` and all their descendants, parents first.
+pub fn visit_symbols<'a>(symbols: &'a [SymbolNode], mut f: impl FnMut(&'a SymbolNode)) {
+    walk_symbols(symbols, 0, &mut |sym: &'a SymbolNode, _| {
+        f(sym);
+        sym.children.as_slice()
+    });
+}
+
+/// A project-relative path as it appears in symbol ids: components joined
+/// with `/` and any `./` dropped, so every parser and symbol source agrees.
+pub fn id_path(path: &std::path::Path) -> String {
+    use std::path::Component;
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| match c {
+            Component::RootDir => "".into(),
+            other => other.as_os_str().to_string_lossy(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A file's mtime, or None if it can't be read.
+pub fn modified_time(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Line numbers up to `last_line` (inclusive) that no symbol in `symbols`
+/// spans, ignoring the module pseudo-symbol itself.
+pub fn uncovered_lines(symbols: &[SymbolNode], last_line: usize) -> Vec<usize> {
+    let mut covered = vec![false; last_line + 1];
+    for sym in symbols.iter().filter(|s| !s.is_module_symbol()) {
+        let end = sym.line_range.end.min(last_line);
+        if sym.line_range.start <= end {
+            covered[sym.line_range.start..=end].fill(true);
+        }
+    }
+    (1..=last_line).filter(|&line| !covered[line]).collect()
 }
 
 /// A file's worth of symbols, organized hierarchically.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSymbols {
     pub file_path: PathBuf,
     pub symbols: Vec<SymbolNode>,
     pub total_lines: usize,
+    /// The file's mtime when it was scanned; None when it couldn't be read.
+    #[serde(default)]
+    pub modified: Option<SystemTime>,
+    /// Coverage the file asks for in an `ambits: require` comment.
+    #[serde(default)]
+    pub required: Option<crate::rules::FileRequirement>,
 }
 
 impl FileSymbols {
@@ -79,8 +182,28 @@ impl FileSymbols {
     }
 }
 
+/// How many files and symbols share one file extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageStats {
+    /// Extension without the dot; empty for files without one.
+    pub extension: String,
+    pub files: usize,
+    pub symbols: usize,
+}
+
+impl LanguageStats {
+    /// `.rs`, or `(none)` for files without an extension.
+    pub fn label(&self) -> String {
+        if self.extension.is_empty() {
+            "(none)".to_string()
+        } else {
+            format!(".{}", self.extension)
+        }
+    }
+}
+
 /// The full project symbol tree, organized by directory structure.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectTree {
     pub root: PathBuf,
     pub files: Vec<FileSymbols>,
@@ -94,4 +217,173 @@ impl ProjectTree {
     pub fn total_files(&self) -> usize {
         self.files.len()
     }
+
+    /// File and symbol counts per extension, most symbols first.
+    pub fn language_stats(&self) -> Vec<LanguageStats> {
+        let mut by_ext: BTreeMap<String, LanguageStats> = BTreeMap::new();
+        for file in &self.files {
+            let ext = file.file_path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+            let stats = by_ext
+                .entry(ext.clone())
+                .or_insert_with(|| LanguageStats { extension: ext, files: 0, symbols: 0 });
+            stats.files += 1;
+            stats.symbols += file.total_symbols();
+        }
+        let mut stats: Vec<LanguageStats> = by_ext.into_values().collect();
+        stats.sort_by(|a, b| b.symbols.cmp(&a.symbols).then_with(|| a.extension.cmp(&b.extension)));
+        stats
+    }
+
+    /// The file at `path`, however it's spelled (`./src/a.rs` finds `src/a.rs`).
+    pub fn file(&self, path: &std::path::Path) -> Option<&FileSymbols> {
+        let key = id_path(path);
+        self.files.iter().find(|f| id_path(&f.file_path) == key)
+    }
+
+    /// Put `file` in place of every entry with the same normalized path,
+    /// keeping files sorted. Dropping all matches, not just the first, also
+    /// clears duplicates an earlier mismatch may have left behind.
+    pub fn replace_file(&mut self, file: FileSymbols) {
+        let key = id_path(&file.file_path);
+        self.files.retain(|f| id_path(&f.file_path) != key);
+        let at = self.files.partition_point(|f| f.file_path < file.file_path);
+        self.files.insert(at, file);
+    }
+
+    /// Fill in each file's `modified` from the filesystem under `root`.
+    pub fn stamp_modified(&mut self) {
+        for file in &mut self.files {
+            file.modified = modified_time(&self.root.join(&file.file_path));
+        }
+    }
+
+    /// Fold every file path and top-level symbol merkle hash into a single
+    /// project fingerprint. Files are visited in path order, so the result
+    /// doesn't depend on scan order.
+    pub fn merkle_root(&self) -> [u8; 32] {
+        let mut files: Vec<&FileSymbols> = self.files.iter().collect();
+        files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        let mut hasher = Sha256::new();
+        for file in files {
+            hasher.update(file.file_path.to_string_lossy().as_bytes());
+            hasher.update([0u8]);
+            for sym in &file.symbols {
+                hasher.update(sym.merkle_hash);
+            }
+        }
+        hasher.finalize().into()
+    }
+
+    /// The merkle hash of every symbol (nested ones included), keyed by id.
+    pub fn symbol_hashes(&self) -> BTreeMap<SymbolId, [u8; 32]> {
+        let mut out = BTreeMap::new();
+        for file in &self.files {
+            visit_symbols(&file.symbols, |sym| {
+                out.insert(sym.id.clone(), sym.merkle_hash);
+            });
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use super::{id_path, visit_symbols, walk_symbols, SymbolNode, SymbolVisitor};
+
+    fn nested() -> Vec<SymbolNode> {
+        vec![
+            sym_with_children("A", "A", vec![sym_with_children("A/b", "b", vec![sym("A/b/c", "c")])]),
+            sym("D", "D"),
+        ]
+    }
+
+    #[test]
+    fn id_path_normalizes_separators_and_dots() {
+        use std::path::Path;
+        assert_eq!(id_path(Path::new("src/app.rs")), "src/app.rs");
+        assert_eq!(id_path(Path::new("./src/./app.rs")), "src/app.rs");
+        assert_eq!(id_path(Path::new("/abs/app.rs")), "/abs/app.rs");
+    }
+
+    #[test]
+    fn replacing_a_file_twice_keeps_one_entry() {
+        let mut tree = project(vec![
+            file("src/a.rs", vec![sym("src/a.rs::alpha", "alpha")]),
+            file("src/b.rs", vec![sym("src/b.rs::beta", "beta")]),
+        ]);
+        tree.files.push(file("./src/a.rs", vec![]));
+
+        tree.replace_file(file("src/a.rs", vec![sym("src/a.rs::alpha", "alpha")]));
+        tree.replace_file(file("./src/a.rs", vec![sym("src/a.rs::alpha", "alpha"), sym("src/a.rs::gamma", "gamma")]));
+
+        assert_eq!(tree.total_files(), 2);
+        assert_eq!(tree.total_symbols(), 3);
+        assert!(tree.file(std::path::Path::new("src/./a.rs")).is_some());
+    }
+
+    #[test]
+    fn language_stats_tally_by_extension() {
+        let tree = project(vec![
+            file("src/a.rs", vec![sym("src/a.rs::alpha", "alpha")]),
+            file("lua/init.lua", vec![sym("lua/init.lua::f", "f"), sym("lua/init.lua::g", "g")]),
+            file("src/b.RS", vec![sym("src/b.RS::beta", "beta")]),
+            file("Makefile", vec![]),
+        ]);
+        let stats = tree.language_stats();
+        let tally: Vec<(&str, usize, usize)> = stats.iter().map(|s| (s.extension.as_str(), s.files, s.symbols)).collect();
+        assert_eq!(tally, [("lua", 1, 2), ("rs", 2, 2), ("", 1, 0)]);
+    }
+
+    #[test]
+    fn visit_symbols_is_pre_order() {
+        let symbols = nested();
+        let mut seen = Vec::new();
+        visit_symbols(&symbols, |sym| seen.push(sym.id.as_str()));
+        assert_eq!(seen, ["A", "A/b", "A/b/c", "D"]);
+    }
+
+    #[test]
+    fn walk_symbols_tracks_depth_and_prunes() {
+        struct SkipB(Vec<(String, usize)>);
+        impl<'a> SymbolVisitor<'a> for SkipB {
+            fn visit(&mut self, sym: &'a SymbolNode, depth: usize) -> &'a [SymbolNode] {
+                self.0.push((sym.id.clone(), depth));
+                if sym.name == "b" { &[] } else { &sym.children }
+            }
+        }
+
+        let symbols = nested();
+        let mut visitor = SkipB(Vec::new());
+        walk_symbols(&symbols, 1, &mut visitor);
+        assert_eq!(visitor.0, [("A".to_string(), 1), ("A/b".to_string(), 2), ("D".to_string(), 1)]);
+    }
+
+    #[test]
+    fn merkle_root_ignores_file_order_and_tracks_changes() {
+        let a = || file("src/a.rs", vec![sym("src/a.rs::a", "a")]);
+        let b = || file("src/b.rs", vec![sym("src/b.rs::b", "b")]);
+        let root = project(vec![a(), b()]).merkle_root();
+        assert_eq!(root, project(vec![b(), a()]).merkle_root());
+
+        let mut changed = project(vec![a(), b()]);
+        changed.files[1].symbols[0].merkle_hash = [7u8; 32];
+        assert_ne!(root, changed.merkle_root());
+    }
+
+    #[test]
+    fn symbol_hashes_include_children() {
+        let tree = project(vec![file(
+            "src/a.rs",
+            vec![sym_with_children("src/a.rs::A", "A", vec![sym("src/a.rs::A/f", "f")])],
+        )]);
+        let hashes = tree.symbol_hashes();
+        assert_eq!(hashes.keys().collect::<Vec<_>>(), vec!["src/a.rs::A", "src/a.rs::A/f"]);
+    }
 }
@@ -0,0 +1,203 @@
+//! Saving a parsed `ProjectTree` to disk and loading it back, so unchanged
+//! projects don't have to be re-parsed on every launch.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use color_eyre::eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use super::ProjectTree;
+use crate::app::SymbolSource;
+use crate::parser::{looks_generated, ParseOptions};
+
+/// Bumped whenever the snapshot layout, symbol ids or content hashes change.
+pub const SNAPSHOT_VERSION: u32 = 5;
+
+/// What a tree was scanned with. A snapshot only stands in for a scan with
+/// the same settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanSettings {
+    pub source: SymbolSource,
+    /// The Serena cache read, when `source` uses one.
+    pub serena_cache: Option<PathBuf>,
+    pub parse: ParseOptions,
+    pub max_depth: Option<usize>,
+    pub respect_ignore: bool,
+    pub generated_markers: Vec<String>,
+}
+
+/// A parsed tree plus the settings it was scanned with and the modification
+/// times of the files it came from.
+#[derive(Debug, Deserialize)]
+pub struct TreeSnapshot {
+    pub version: u32,
+    pub settings: ScanSettings,
+    pub tree: ProjectTree,
+    /// Modification time of each parsed file when the snapshot was taken.
+    pub mtimes: BTreeMap<PathBuf, SystemTime>,
+}
+
+/// Borrowing twin of `TreeSnapshot`, so saving doesn't clone the tree.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    version: u32,
+    settings: &'a ScanSettings,
+    tree: &'a ProjectTree,
+    mtimes: BTreeMap<&'a Path, SystemTime>,
+}
+
+/// Write `tree`, the settings it was scanned with and its files' current
+/// modification times to `path`.
+pub fn save(tree: &ProjectTree, settings: &ScanSettings, path: &Path) -> Result<()> {
+    let mtimes = tree
+        .files
+        .iter()
+        .filter_map(|f| Some((f.file_path.as_path(), file_mtime(&tree.root, &f.file_path)?)))
+        .collect();
+    let snapshot = SnapshotRef { version: SNAPSHOT_VERSION, settings, tree, mtimes };
+    let file = std::fs::File::create(path)
+        .wrap_err_with(|| format!("Failed to create {}", path.display()))?;
+    serde_json::to_writer(std::io::BufWriter::new(file), &snapshot)
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+impl TreeSnapshot {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .wrap_err_with(|| format!("Failed to open {}", path.display()))?;
+        let snapshot: Self = serde_json::from_reader(std::io::BufReader::new(file))
+            .wrap_err_with(|| format!("Invalid tree snapshot in {}", path.display()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            bail!(
+                "Tree snapshot {} has version {}, expected {}",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// Files that were modified or removed since the snapshot, then files in
+    /// `scanned` (what a scan would parse now, relative paths) that were added
+    /// since. Added files that look generated don't count, as the scan skips them.
+    pub fn changed_files<'a>(&'a self, scanned: &'a [PathBuf]) -> Vec<&'a Path> {
+        let root = &self.tree.root;
+        let changed = self
+            .tree
+            .files
+            .iter()
+            .map(|f| f.file_path.as_path())
+            .filter(|p| file_mtime(root, p) != self.mtimes.get(*p).copied());
+        let added = scanned
+            .iter()
+            .map(PathBuf::as_path)
+            .filter(|p| self.tree.file(p).is_none())
+            .filter(|p| {
+                std::fs::read_to_string(root.join(p))
+                    .is_ok_and(|source| !looks_generated(&source, &self.settings.generated_markers))
+            });
+        changed.chain(added).collect()
+    }
+}
+
+fn file_mtime(root: &Path, rel: &Path) -> Option<SystemTime> {
+    std::fs::metadata(root.join(rel)).ok()?.modified().ok()
+}
+
+#[cfg(test)]
+#[path = "../../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use super::*;
+
+    fn settings() -> ScanSettings {
+        ScanSettings {
+            source: SymbolSource::TreeSitter,
+            serena_cache: None,
+            parse: ParseOptions::default(),
+            max_depth: None,
+            respect_ignore: true,
+            generated_markers: vec!["@generated".into()],
+        }
+    }
+
+    #[test]
+    fn round_trip_and_detect_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("a.rs"), "fn a() {}").unwrap();
+        std::fs::write(tmp.path().join("b.rs"), "fn b() {}").unwrap();
+        let mut tree = project(vec![
+            file("a.rs", vec![sym_with_children("a.rs::A", "A", vec![sym("a.rs::A/f", "f")])]),
+            file("b.rs", vec![sym("b.rs::b", "b")]),
+        ]);
+        tree.root = tmp.path().to_path_buf();
+
+        let path = tmp.path().join("tree.json");
+        save(&tree, &settings(), &path).unwrap();
+        let loaded = TreeSnapshot::load(&path).unwrap();
+        assert_eq!(loaded.settings, settings());
+        let scanned = [PathBuf::from("a.rs"), PathBuf::from("b.rs")];
+        assert!(loaded.changed_files(&scanned).is_empty());
+        assert_eq!(loaded.tree.merkle_root(), tree.merkle_root());
+        assert_eq!(loaded.tree.symbol_hashes(), tree.symbol_hashes());
+
+        std::fs::remove_file(tmp.path().join("b.rs")).unwrap();
+        std::fs::write(tmp.path().join("c.rs"), "fn c() {}").unwrap();
+        std::fs::write(tmp.path().join("gen.rs"), "// @generated\nfn g() {}").unwrap();
+        let scanned = [PathBuf::from("a.rs"), PathBuf::from("c.rs"), PathBuf::from("gen.rs")];
+        assert_eq!(loaded.changed_files(&scanned), vec![Path::new("b.rs"), Path::new("c.rs")]);
+    }
+
+    /// Ids and hashes of a few parsed symbols, for the version they belong to.
+    /// When this fails, symbols parse differently than snapshots saved by
+    /// this version do: bump `SNAPSHOT_VERSION` and update the fingerprint.
+    #[test]
+    fn version_matches_symbol_format() {
+        use sha2::{Digest, Sha256};
+
+        let registry = crate::parser::ParserRegistry::new();
+        let sources = [
+            ("src/a.rs", "struct S;\nimpl S {\n    fn f(&self) -> u8 { 1 }\n}\nfn g() {}\n"),
+            ("pkg/b.py", "class A:\n    def f(self):\n        return 1\n"),
+            ("c.lua", "local M = {}\nfunction M.setup() end\n"),
+        ];
+        let files = sources
+            .iter()
+            .map(|(path, source)| {
+                let path = Path::new(path);
+                registry.parser_for(path).unwrap().parse_file(path, source).unwrap()
+            })
+            .collect();
+        let tree = ProjectTree { root: PathBuf::new(), files };
+
+        let mut hasher = Sha256::new();
+        for (id, hash) in tree.symbol_hashes() {
+            hasher.update(id.as_bytes());
+            hasher.update(hash);
+        }
+        let fingerprint = crate::symbols::merkle::to_hex(&hasher.finalize().into());
+        assert_eq!((SNAPSHOT_VERSION, &fingerprint[..16]), (5, "39430739b3353447"));
+    }
+
+    #[test]
+    fn load_rejects_other_versions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("tree.json");
+        save(&project(vec![]), &settings(), &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let bumped = text.replacen(
+            &format!("\"version\":{SNAPSHOT_VERSION}"),
+            &format!("\"version\":{}", SNAPSHOT_VERSION + 1),
+            1,
+        );
+        std::fs::write(&path, bumped).unwrap();
+        assert!(TreeSnapshot::load(&path).is_err());
+    }
+}
@@ -17,6 +17,12 @@ pub struct AgentTree {
     pub root_id: Option<String>,
 }
 
+impl Default for AgentTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AgentTree {
     pub fn new() -> Self {
         Self {
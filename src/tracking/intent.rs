@@ -0,0 +1,122 @@
+//! What an agent plans to touch, read from the items of its todo list.
+//!
+//! Todo items are prose, so only code-looking tokens count: text in
+//! backticks, and words with `_`, `::`, `()`, `/` or a `.`. Tokens that look
+//! like paths name files whose path ends with them; the rest name symbols by
+//! their last segment (`App::render` and `app.render()` both name `render`).
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::symbols::{visit_symbols, ProjectTree, SymbolId};
+
+/// Shortest symbol name a todo item can name; shorter words are too likely
+/// to be ordinary prose.
+const MIN_NAME_LEN: usize = 3;
+
+/// The file ids (paths) and symbol ids the todo items name.
+pub fn planned_targets(tree: &ProjectTree, todos: &[String]) -> HashSet<SymbolId> {
+    let mut planned = HashSet::new();
+    for token in todos.iter().flat_map(|todo| code_tokens(todo)) {
+        let files: Vec<_> = tree
+            .files
+            .iter()
+            .filter(|f| looks_like_path(token) && f.file_path.ends_with(Path::new(token)))
+            .collect();
+        if !files.is_empty() {
+            planned.extend(files.iter().map(|f| f.file_path.to_string_lossy().to_string()));
+            continue;
+        }
+
+        let name = symbol_name(token);
+        if name.chars().count() < MIN_NAME_LEN {
+            continue;
+        }
+        for file in &tree.files {
+            visit_symbols(&file.symbols, |sym| {
+                if sym.name == name {
+                    planned.insert(sym.id.clone());
+                }
+            });
+        }
+    }
+    planned
+}
+
+/// The code-looking tokens of one todo item.
+fn code_tokens(todo: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    // Odd pieces are inside backticks; even ones are prose to pick words from.
+    for (i, piece) in todo.split('`').enumerate() {
+        if i % 2 == 1 {
+            tokens.push(piece.trim());
+            continue;
+        }
+        for word in piece.split_whitespace() {
+            let word = word
+                .trim_start_matches(['(', '"', '\''])
+                .trim_end_matches([',', ';', ':', '"', '\'', '.', '!', '?']);
+            let word = match word.strip_suffix(')') {
+                Some(w) if !w.ends_with('(') => w,
+                _ => word,
+            };
+            if ["_", "::", "()", "/", "."].iter().any(|mark| word.contains(mark)) {
+                tokens.push(word);
+            }
+        }
+    }
+    tokens.retain(|t| !t.is_empty());
+    tokens
+}
+
+/// Whether a token names a file: it has a directory or an extension.
+fn looks_like_path(token: &str) -> bool {
+    token.contains('/')
+        || token
+            .rsplit_once('.')
+            .is_some_and(|(stem, ext)| !stem.is_empty() && !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// The symbol name a token ends in: `App::render()` -> `render`.
+fn symbol_name(token: &str) -> &str {
+    let token = token.split('(').next().unwrap_or(token);
+    let last = token.rsplit("::").next().unwrap_or(token);
+    last.rsplit('.').next().unwrap_or(last)
+}
+
+#[cfg(test)]
+#[path = "../../tests/helpers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
+mod helpers;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::*;
+    use super::*;
+
+    #[test]
+    fn tokens_come_from_backticks_and_code_looking_words() {
+        assert_eq!(
+            code_tokens("Fix `Replay` in src/replay.rs, then call App::render() and parse_line."),
+            ["Replay", "src/replay.rs", "App::render()", "parse_line"],
+        );
+        assert!(code_tokens("Run the tests and commit").is_empty());
+    }
+
+    #[test]
+    fn todo_items_name_files_and_symbols() {
+        let tree = project(vec![
+            file("src/app.rs", vec![sym_with_children("app.rs::App", "App", vec![sym("app.rs::App::render", "render")])]),
+            file("src/replay.rs", vec![sym("replay.rs::advance", "advance")]),
+        ]);
+        let todos = vec![
+            "Update App::render() for the new icon".to_string(),
+            "Look at replay.rs".to_string(),
+            "Make it go".to_string(),
+        ];
+        let planned = planned_targets(&tree, &todos);
+        let mut planned: Vec<_> = planned.into_iter().collect();
+        planned.sort();
+        assert_eq!(planned, ["app.rs::App::render", "src/replay.rs"]);
+    }
+}
@@ -1,11 +1,20 @@
 pub mod agents;
+pub mod intent;
+pub mod notes;
 
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use crate::symbols::SymbolId;
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+use crate::symbols::{ProjectTree, SymbolId, SymbolNode};
+
+use notes::Notes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ReadDepth {
     Unseen,
     NameOnly,
@@ -22,6 +31,19 @@ impl ReadDepth {
     }
 }
 
+/// How a read was earned: by the code being shown to the agent, or only
+/// implied by what the agent did with it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadSource {
+    /// An edit (which needs an earlier read) or a search (which shows only
+    /// the matching lines).
+    Inferred,
+    /// A Read, a Write, or a Serena symbol lookup.
+    #[default]
+    Explicit,
+}
+
 impl std::fmt::Display for ReadDepth {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -35,25 +57,144 @@ impl std::fmt::Display for ReadDepth {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContextEntry {
     pub symbol_id: SymbolId,
     pub depth: ReadDepth,
     pub content_hash_at_read: [u8; 32],
-    pub timestamp: Instant,
+    /// When `depth` was recorded.
+    pub timestamp: DateTime<Utc>,
     pub agent_id: String,
     pub token_count: usize,
+    /// How the read behind `depth` was earned.
+    #[serde(default)]
+    pub source: ReadSource,
+    /// Deepest read of this symbol by each individual agent.
+    #[serde(default)]
+    pub agent_depths: HashMap<String, ReadDepth>,
+}
+
+/// What `save_to_path` writes: everything but the notes, in key order.
+#[derive(Serialize, Deserialize)]
+struct SavedLedger {
+    entries: Vec<ContextEntry>,
+    #[serde(default)]
+    edited: BTreeMap<SymbolId, bool>,
+    #[serde(default)]
+    file_reads: BTreeMap<PathBuf, DateTime<Utc>>,
+    #[serde(default)]
+    file_depths: BTreeMap<PathBuf, ReadDepth>,
+    #[serde(default)]
+    plans: BTreeMap<String, BTreeSet<SymbolId>>,
+    #[serde(default)]
+    applied_until: Option<DateTime<Utc>>,
+}
+
+/// What changed between two parses of a project, by symbol id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    /// Symbols whose own content hash changed.
+    pub changed: usize,
+    pub added: usize,
+    pub removed: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct ContextLedger {
     pub entries: HashMap<SymbolId, ContextEntry>,
+    /// Review notes left on symbols; exported with coverage reports.
+    pub notes: Notes,
+    /// Symbols an edit touched, with whether they had been read at full body
+    /// before the first edit.
+    pub edited: HashMap<SymbolId, bool>,
+    /// Latest time an agent read or edited each file, from event timestamps.
+    pub file_reads: HashMap<PathBuf, DateTime<Utc>>,
+    /// Deepest read of each file as a whole. What a read of a file without
+    /// symbols leaves behind.
+    pub file_depths: HashMap<PathBuf, ReadDepth>,
+    /// File and symbol ids each agent's latest todo list names. Intent only:
+    /// planned symbols are not counted as covered.
+    pub plans: HashMap<String, HashSet<SymbolId>>,
+    /// Timestamp of the latest log event applied, so a ledger loaded from a
+    /// cache knows which events it already covers.
+    pub applied_until: Option<DateTime<Utc>>,
+    /// Bumped whenever coverage changes, so callers can cache derived counts.
+    version: u64,
+}
+
+impl Default for ContextLedger {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ContextLedger {
     pub fn new() -> Self {
         Self {
             entries: HashMap::new(),
+            notes: Notes::default(),
+            edited: HashMap::new(),
+            file_reads: HashMap::new(),
+            file_depths: HashMap::new(),
+            plans: HashMap::new(),
+            applied_until: None,
+            version: 0,
+        }
+    }
+
+    /// Counter that increases every time a recorded depth changes. Writes made
+    /// directly to `entries` don't bump it.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Write everything but the notes to `path` as JSON, creating its directory.
+    pub fn save_to_path(&self, path: &Path) -> Result<()> {
+        let mut entries: Vec<ContextEntry> = self.entries.values().cloned().collect();
+        entries.sort_by(|a, b| a.symbol_id.cmp(&b.symbol_id));
+        let saved = SavedLedger {
+            entries,
+            edited: self.edited.clone().into_iter().collect(),
+            file_reads: self.file_reads.clone().into_iter().collect(),
+            file_depths: self.file_depths.clone().into_iter().collect(),
+            plans: self.plans.iter().map(|(agent, ids)| (agent.clone(), ids.iter().cloned().collect())).collect(),
+            applied_until: self.applied_until,
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&saved)?)
+            .wrap_err_with(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Replace everything but the notes with what `save_to_path` wrote to `path`.
+    pub fn load_from_path(&mut self, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let saved: SavedLedger =
+            serde_json::from_str(&text).wrap_err_with(|| format!("Invalid ledger in {}", path.display()))?;
+        self.entries = saved.entries.into_iter().map(|e| (e.symbol_id.clone(), e)).collect();
+        self.edited = saved.edited.into_iter().collect();
+        self.file_reads = saved.file_reads.into_iter().collect();
+        self.file_depths = saved.file_depths.into_iter().collect();
+        self.plans = saved.plans.into_iter().map(|(agent, ids)| (agent, ids.into_iter().collect())).collect();
+        self.applied_until = saved.applied_until;
+        self.version += 1;
+        Ok(())
+    }
+
+    /// Note that the log event stamped `timestamp` was applied.
+    pub fn mark_applied(&mut self, timestamp: DateTime<Utc>) {
+        self.applied_until = self.applied_until.max(Some(timestamp));
+    }
+
+    /// Whether the log event stamped `timestamp` (RFC 3339) is no later than
+    /// the latest one applied. Unstamped events never are.
+    pub fn has_applied(&self, timestamp: &str) -> bool {
+        match (DateTime::parse_from_rfc3339(timestamp), self.applied_until) {
+            (Ok(ts), Some(until)) => ts.with_timezone(&Utc) <= until,
+            _ => false,
         }
     }
 
@@ -66,26 +207,109 @@ impl ContextLedger {
         content_hash: [u8; 32],
         agent_id: String,
         token_count: usize,
+    ) {
+        self.record_with(symbol_id, depth, content_hash, agent_id, token_count, ReadSource::Explicit);
+    }
+
+    /// `record`, noting how the read was earned. An explicit read at the same
+    /// depth firms up an inferred one.
+    pub fn record_with(
+        &mut self,
+        symbol_id: SymbolId,
+        depth: ReadDepth,
+        content_hash: [u8; 32],
+        agent_id: String,
+        token_count: usize,
+        source: ReadSource,
     ) {
         let entry = self.entries.entry(symbol_id.clone()).or_insert_with(|| ContextEntry {
             symbol_id: symbol_id.clone(),
             depth: ReadDepth::Unseen,
             content_hash_at_read: [0u8; 32],
-            timestamp: Instant::now(),
+            timestamp: Utc::now(),
             agent_id: String::new(),
             token_count: 0,
+            source: ReadSource::Inferred,
+            agent_depths: HashMap::new(),
         });
 
+        let agent_depth = entry.agent_depths.entry(agent_id.clone()).or_insert(ReadDepth::Unseen);
+        if depth == ReadDepth::Stale || depth > *agent_depth {
+            if *agent_depth != depth {
+                self.version += 1;
+            }
+            *agent_depth = depth;
+        }
+
         // Only upgrade, never downgrade (except Stale overrides everything).
         if depth == ReadDepth::Stale || depth > entry.depth {
+            if entry.depth != depth {
+                self.version += 1;
+            }
             entry.depth = depth;
             entry.content_hash_at_read = content_hash;
-            entry.timestamp = Instant::now();
+            entry.timestamp = Utc::now();
             entry.agent_id = agent_id;
             entry.token_count = token_count;
+            entry.source = source;
+        } else if depth == entry.depth && source > entry.source {
+            self.version += 1;
+            entry.source = source;
         }
     }
 
+    /// Note that a symbol is being edited. Call before recording the edit's read,
+    /// so the first edit captures whether the symbol had been read beforehand.
+    pub fn record_edit(&mut self, symbol_id: &str) {
+        let read_first = self.depth_of(symbol_id) == ReadDepth::FullBody;
+        self.edited.entry(symbol_id.to_string()).or_insert(read_first);
+    }
+
+    /// Replace what `agent_id` plans to touch with `targets`.
+    pub fn record_plan(&mut self, agent_id: &str, targets: HashSet<SymbolId>) {
+        if targets.is_empty() {
+            self.plans.remove(agent_id);
+        } else {
+            self.plans.insert(agent_id.to_string(), targets);
+        }
+    }
+
+    /// Whether any agent's todo list names this file or symbol id.
+    pub fn is_planned(&self, id: &str) -> bool {
+        self.plans.values().any(|targets| targets.contains(id))
+    }
+
+    /// Note that an agent looked at `file_path` at `at`, keeping the latest time.
+    pub fn record_file_read(&mut self, file_path: &Path, at: DateTime<Utc>) {
+        let last = self.file_reads.entry(file_path.to_path_buf()).or_insert(at);
+        if at > *last {
+            *last = at;
+        }
+    }
+
+    /// Note that `file_path` was read at `depth`, keeping the deepest read.
+    pub fn record_file_depth(&mut self, file_path: &Path, depth: ReadDepth) {
+        let deepest = self.file_depths.entry(file_path.to_path_buf()).or_insert(ReadDepth::Unseen);
+        if depth > *deepest {
+            *deepest = depth;
+            self.version += 1;
+        }
+    }
+
+    /// The deepest read of `file_path` as a whole, defaulting to Unseen.
+    pub fn file_depth(&self, file_path: &Path) -> ReadDepth {
+        self.file_depths.get(file_path).copied().unwrap_or(ReadDepth::Unseen)
+    }
+
+    /// Whether a file modified at `modified` changed after the last recorded read.
+    /// Files never read don't count. Allows a couple of seconds of slack, since an
+    /// agent's own edit lands just after the event timestamp.
+    pub fn modified_since_read(&self, file_path: &Path, modified: std::time::SystemTime) -> bool {
+        self.file_reads.get(file_path).is_some_and(|read| {
+            DateTime::<Utc>::from(modified) > *read + chrono::Duration::seconds(2)
+        })
+    }
+
     /// Get the read depth for a symbol, defaulting to Unseen.
     pub fn depth_of(&self, symbol_id: &str) -> ReadDepth {
         self.entries
@@ -94,19 +318,181 @@ impl ContextLedger {
             .unwrap_or(ReadDepth::Unseen)
     }
 
+    /// Whether the symbol's coverage was only inferred, never shown to an agent.
+    pub fn is_inferred(&self, symbol_id: &str) -> bool {
+        self.entries
+            .get(symbol_id)
+            .is_some_and(|e| e.depth.is_seen() && e.source == ReadSource::Inferred)
+    }
+
+    /// Get the read depth a single agent reached for a symbol, defaulting to Unseen.
+    pub fn depth_for_agent(&self, symbol_id: &str, agent_id: &str) -> ReadDepth {
+        self.entries
+            .get(symbol_id)
+            .and_then(|e| e.agent_depths.get(agent_id))
+            .copied()
+            .unwrap_or(ReadDepth::Unseen)
+    }
+
     /// Mark all entries whose content hash no longer matches as Stale.
     pub fn mark_stale_if_changed(&mut self, symbol_id: &str, current_hash: [u8; 32]) {
+        let changed = self
+            .entries
+            .get(symbol_id)
+            .is_some_and(|e| e.content_hash_at_read != current_hash);
+        if changed {
+            self.mark_stale(symbol_id);
+        }
+    }
+
+    /// Mark the seen symbols of every file modified after its last recorded
+    /// read as Stale. For ledgers replayed offline, where the file changes
+    /// weren't watched as they happened.
+    pub fn mark_modified_files_stale(&mut self, tree: &ProjectTree) {
+        for file in &tree.files {
+            let Some(modified) = file.modified else { continue };
+            if !self.modified_since_read(&file.file_path, modified) {
+                continue;
+            }
+            crate::symbols::visit_symbols(&file.symbols, |sym| self.mark_stale(&sym.id));
+        }
+    }
+
+    fn mark_stale(&mut self, symbol_id: &str) {
         if let Some(entry) = self.entries.get_mut(symbol_id) {
-            if entry.depth != ReadDepth::Unseen && entry.content_hash_at_read != current_hash {
+            if entry.depth != ReadDepth::Unseen {
+                if entry.depth != ReadDepth::Stale {
+                    self.version += 1;
+                }
                 entry.depth = ReadDepth::Stale;
+                for depth in entry.agent_depths.values_mut() {
+                    if depth.is_seen() {
+                        *depth = ReadDepth::Stale;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Carry coverage over from `old` to `new`, matching symbols by id.
+    /// Subtrees whose merkle hash is unchanged are skipped; symbols whose
+    /// content changed go Stale, and entries for removed symbols are dropped.
+    pub fn reconcile(&mut self, old: &ProjectTree, new: &ProjectTree) -> TreeDiff {
+        fn index<'a>(symbols: &'a [SymbolNode], out: &mut HashMap<&'a str, &'a SymbolNode>) {
+            for sym in symbols {
+                out.insert(&sym.id, sym);
+                index(&sym.children, out);
+            }
+        }
+        fn ids<'a>(sym: &'a SymbolNode, out: &mut HashSet<&'a str>) {
+            out.insert(&sym.id);
+            for child in &sym.children {
+                ids(child, out);
             }
         }
+        fn walk<'a>(
+            symbols: &'a [SymbolNode],
+            old: &HashMap<&str, &SymbolNode>,
+            present: &mut HashSet<&'a str>,
+            ledger: &mut ContextLedger,
+            diff: &mut TreeDiff,
+        ) {
+            for sym in symbols {
+                match old.get(sym.id.as_str()) {
+                    Some(prev) if prev.merkle_hash == sym.merkle_hash => {
+                        ids(sym, present);
+                        continue;
+                    }
+                    Some(prev) if prev.content_hash != sym.content_hash => {
+                        diff.changed += 1;
+                        ledger.mark_stale_if_changed(&sym.id, sym.content_hash);
+                    }
+                    Some(_) => {}
+                    None => diff.added += 1,
+                }
+                present.insert(&sym.id);
+                walk(&sym.children, old, present, ledger, diff);
+            }
+        }
+
+        let mut old_index = HashMap::new();
+        for file in &old.files {
+            index(&file.symbols, &mut old_index);
+        }
+        let mut present = HashSet::new();
+        let mut diff = TreeDiff::default();
+        for file in &new.files {
+            walk(&file.symbols, &old_index, &mut present, self, &mut diff);
+        }
+        for id in old_index.keys().filter(|id| !present.contains(*id)) {
+            diff.removed += 1;
+            if self.entries.remove(*id).is_some() {
+                self.version += 1;
+            }
+            self.edited.remove(*id);
+        }
+        diff
+    }
+
+    /// Move coverage from symbols missing from `new` to symbols new in it
+    /// with the same content hash, so a rename that leaves the body alone
+    /// keeps what was read. Only one-to-one matches move; a hash shared by
+    /// several removed or added symbols (identical stubs, say) is left alone.
+    /// Returns how many entries moved.
+    pub fn remap_renamed(&mut self, old: &[SymbolNode], new: &[SymbolNode]) -> usize {
+        let mut old_ids = HashMap::new();
+        crate::symbols::visit_symbols(old, |sym| {
+            old_ids.insert(sym.id.as_str(), sym.content_hash);
+        });
+        let mut new_ids = HashMap::new();
+        crate::symbols::visit_symbols(new, |sym| {
+            new_ids.insert(sym.id.as_str(), sym.content_hash);
+        });
+
+        let mut by_hash: HashMap<[u8; 32], (Vec<&str>, Vec<&str>)> = HashMap::new();
+        for (id, hash) in old_ids.iter().filter(|(id, _)| !new_ids.contains_key(*id)) {
+            by_hash.entry(*hash).or_default().0.push(id);
+        }
+        for (id, hash) in new_ids.iter().filter(|(id, _)| !old_ids.contains_key(*id)) {
+            by_hash.entry(*hash).or_default().1.push(id);
+        }
+
+        let mut moved = 0;
+        for (removed, added) in by_hash.values() {
+            let ([from], [to]) = (removed.as_slice(), added.as_slice()) else { continue };
+            if self.entries.contains_key(*to) {
+                continue;
+            }
+            if let Some(mut entry) = self.entries.remove(*from) {
+                entry.symbol_id = to.to_string();
+                self.entries.insert(to.to_string(), entry);
+                self.version += 1;
+                moved += 1;
+            }
+            if let Some(read_first) = self.edited.remove(*from) {
+                self.edited.insert(to.to_string(), read_first);
+            }
+        }
+        moved
     }
 
     pub fn total_seen(&self) -> usize {
         self.entries.values().filter(|e| e.depth.is_seen()).count()
     }
 
+    /// Number of symbols each agent has seen at any depth.
+    pub fn seen_by_agent(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for entry in self.entries.values() {
+            for (agent, depth) in &entry.agent_depths {
+                if depth.is_seen() {
+                    *counts.entry(agent.clone()).or_insert(0) += 1;
+                }
+            }
+        }
+        counts
+    }
+
     pub fn count_by_depth(&self) -> HashMap<ReadDepth, usize> {
         let mut counts = HashMap::new();
         for entry in self.entries.values() {
@@ -124,6 +510,55 @@ mod tests {
         crate::symbols::merkle::content_hash(s)
     }
 
+    #[test]
+    fn seen_by_agent_counts_each_agent() {
+        let mut ledger = ContextLedger::new();
+        ledger.record("s1".into(), ReadDepth::NameOnly, hash("a"), "a1".into(), 10);
+        ledger.record("s2".into(), ReadDepth::FullBody, hash("b"), "a1".into(), 10);
+        ledger.record("s2".into(), ReadDepth::Overview, hash("b"), "a2".into(), 10);
+        let counts = ledger.seen_by_agent();
+        assert_eq!(counts["a1"], 2);
+        assert_eq!(counts["a2"], 1);
+    }
+
+    #[test]
+    fn reconcile_keeps_unchanged_coverage_and_drops_removed() {
+        use crate::symbols::{FileSymbols, SymbolCategory};
+
+        let node = |id: &str, body: &str, children: Vec<SymbolNode>| {
+            let mut sym = SymbolNode {
+                id: id.into(), name: id.into(), category: SymbolCategory::Function,
+                label: "fn".into(), file_path: "a.rs".into(), byte_range: 0..1, line_range: 1..2,
+                content_hash: hash(body), merkle_hash: [0; 32],
+                children, estimated_tokens: 1, derives: Vec::new(), decorators: Vec::new(), test_only: false, name_range: None,
+            };
+            crate::symbols::merkle::compute_merkle_hash(&mut sym);
+            sym
+        };
+        let tree = |symbols| ProjectTree {
+            root: "/p".into(),
+            files: vec![FileSymbols { file_path: "a.rs".into(), symbols, total_lines: 10, modified: None, required: None }],
+        };
+        let old = tree(vec![
+            node("T", "T", vec![node("T/f", "f1", vec![]), node("T/g", "g", vec![])]),
+            node("gone", "gone", vec![]),
+        ]);
+        let new = tree(vec![
+            node("T", "T", vec![node("T/f", "f2", vec![]), node("T/g", "g", vec![]), node("T/h", "h", vec![])]),
+        ]);
+
+        let mut ledger = ContextLedger::new();
+        for (id, body) in [("T", "T"), ("T/f", "f1"), ("T/g", "g"), ("gone", "gone")] {
+            ledger.record(id.into(), ReadDepth::FullBody, hash(body), "ag".into(), 1);
+        }
+        let diff = ledger.reconcile(&old, &new);
+        assert_eq!(diff, TreeDiff { changed: 1, added: 1, removed: 1 });
+        assert_eq!(ledger.depth_of("T"), ReadDepth::FullBody);
+        assert_eq!(ledger.depth_of("T/f"), ReadDepth::Stale);
+        assert_eq!(ledger.depth_of("T/g"), ReadDepth::FullBody);
+        assert!(!ledger.entries.contains_key("gone"));
+    }
+
     #[test]
     fn record_upgrades_depth() {
         let mut ledger = ContextLedger::new();
@@ -132,6 +567,23 @@ mod tests {
         assert_eq!(ledger.depth_of("s1"), ReadDepth::FullBody);
     }
 
+    #[test]
+    fn explicit_reads_firm_up_inferred_ones() {
+        let mut ledger = ContextLedger::new();
+        ledger.record_with("s1".into(), ReadDepth::FullBody, hash("a"), "ag".into(), 10, ReadSource::Inferred);
+        assert!(ledger.is_inferred("s1"));
+
+        // A shallower explicit read doesn't vouch for the full body.
+        ledger.record("s1".into(), ReadDepth::Overview, hash("a"), "ag".into(), 10);
+        assert!(ledger.is_inferred("s1"));
+
+        ledger.record("s1".into(), ReadDepth::FullBody, hash("a"), "ag".into(), 10);
+        assert!(!ledger.is_inferred("s1"));
+        ledger.record_with("s1".into(), ReadDepth::FullBody, hash("a"), "ag".into(), 10, ReadSource::Inferred);
+        assert!(!ledger.is_inferred("s1"));
+        assert!(!ledger.is_inferred("never-read"));
+    }
+
     #[test]
     fn record_never_downgrades() {
         let mut ledger = ContextLedger::new();
@@ -164,6 +616,63 @@ mod tests {
         assert_eq!(ledger.depth_of("s1"), ReadDepth::Stale);
     }
 
+    #[test]
+    fn modified_since_read_compares_against_latest_read() {
+        let mut ledger = ContextLedger::new();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let path = Path::new("src/a.rs");
+        let mtime = |s: &str| std::time::SystemTime::from(at(s));
+
+        assert!(!ledger.modified_since_read(path, mtime("2026-01-01T12:00:00Z")));
+        ledger.record_file_read(path, at("2026-01-01T10:00:00Z"));
+        ledger.record_file_read(path, at("2026-01-01T09:00:00Z"));
+        assert!(ledger.modified_since_read(path, mtime("2026-01-01T10:05:00Z")));
+        // Within the slack of the read, e.g. the agent's own edit.
+        assert!(!ledger.modified_since_read(path, mtime("2026-01-01T10:00:01Z")));
+
+        ledger.record_file_read(path, at("2026-01-01T11:00:00Z"));
+        assert!(!ledger.modified_since_read(path, mtime("2026-01-01T10:05:00Z")));
+    }
+
+    #[test]
+    fn version_bumps_only_on_change() {
+        let mut ledger = ContextLedger::new();
+        ledger.record("s1".into(), ReadDepth::Overview, hash("a"), "ag".into(), 10);
+        let v = ledger.version();
+        assert!(v > 0);
+
+        // Re-reading at the same or a shallower depth changes nothing.
+        ledger.record("s1".into(), ReadDepth::Overview, hash("a"), "ag".into(), 10);
+        ledger.record("s1".into(), ReadDepth::NameOnly, hash("a"), "ag".into(), 10);
+        ledger.mark_stale_if_changed("s1", hash("a"));
+        assert_eq!(ledger.version(), v);
+
+        ledger.record("s1".into(), ReadDepth::FullBody, hash("a"), "ag".into(), 10);
+        assert!(ledger.version() > v);
+        let v = ledger.version();
+        ledger.mark_stale_if_changed("s1", hash("b"));
+        assert!(ledger.version() > v);
+    }
+
+    #[test]
+    fn renamed_function_keeps_its_coverage() {
+        use crate::parser::{rust::RustParser, LanguageParser, ParseOptions};
+        let parser = RustParser::with_options(ParseOptions { follow_renames: true, ..ParseOptions::default() });
+        let parse = |src: &str| parser.parse_file(Path::new("src/a.rs"), src).unwrap().symbols;
+        let old = parse("fn foo() -> u32 {\n    1\n}\n\nfn bar() {}\n");
+        let new = parse("fn foo2() -> u32 {\n    1\n}\n\nfn baz() { 2 }\n");
+
+        let mut ledger = ContextLedger::new();
+        ledger.record("src/a.rs::foo".into(), ReadDepth::FullBody, old[0].content_hash, "ag".into(), 10);
+        ledger.record("src/a.rs::bar".into(), ReadDepth::FullBody, old[1].content_hash, "ag".into(), 10);
+
+        assert_eq!(ledger.remap_renamed(&old, &new), 1);
+        assert_eq!(ledger.depth_of("src/a.rs::foo2"), ReadDepth::FullBody);
+        assert_eq!(ledger.depth_of("src/a.rs::foo"), ReadDepth::Unseen);
+        // `bar` became `baz` with a new body: that's a different symbol.
+        assert_eq!(ledger.depth_of("src/a.rs::baz"), ReadDepth::Unseen);
+    }
+
     #[test]
     fn unseen_not_marked_stale() {
         let mut ledger = ContextLedger::new();
@@ -171,6 +680,51 @@ mod tests {
         assert_eq!(ledger.depth_of("never_seen"), ReadDepth::Unseen);
     }
 
+    #[test]
+    fn depth_for_agent_tracks_each_agent() {
+        let mut ledger = ContextLedger::new();
+        ledger.record("s1".into(), ReadDepth::FullBody, hash("a"), "main".into(), 10);
+        ledger.record("s1".into(), ReadDepth::NameOnly, hash("a"), "agent-b".into(), 10);
+
+        assert_eq!(ledger.depth_of("s1"), ReadDepth::FullBody);
+        assert_eq!(ledger.depth_for_agent("s1", "main"), ReadDepth::FullBody);
+        assert_eq!(ledger.depth_for_agent("s1", "agent-b"), ReadDepth::NameOnly);
+        assert_eq!(ledger.depth_for_agent("s1", "agent-c"), ReadDepth::Unseen);
+
+        ledger.mark_stale_if_changed("s1", hash("b"));
+        assert_eq!(ledger.depth_for_agent("s1", "agent-b"), ReadDepth::Stale);
+    }
+
+    #[test]
+    fn saved_ledger_loads_back() {
+        let mut ledger = ContextLedger::new();
+        ledger.record("s1".into(), ReadDepth::FullBody, hash("a"), "main".into(), 10);
+        ledger.record("s1".into(), ReadDepth::NameOnly, hash("a"), "agent-b".into(), 10);
+        ledger.record_with("s2".into(), ReadDepth::Overview, hash("b"), "main".into(), 4, ReadSource::Inferred);
+        ledger.edited.insert("s1".into(), true);
+        ledger.plans.insert("main".into(), HashSet::from(["s2".to_string()]));
+        let at = DateTime::parse_from_rfc3339("2025-01-01T10:00:00.500Z").unwrap().with_timezone(&Utc);
+        ledger.mark_applied(at);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(".ambits/ledger-s.json");
+        ledger.save_to_path(&path).unwrap();
+
+        let mut loaded = ContextLedger::new();
+        let v = loaded.version();
+        loaded.load_from_path(&path).unwrap();
+        assert_eq!(loaded.entries, ledger.entries);
+        assert!(loaded.version() > v);
+        assert_eq!(loaded.depth_for_agent("s1", "agent-b"), ReadDepth::NameOnly);
+        assert!(loaded.is_inferred("s2"));
+        assert_eq!(loaded.edited, ledger.edited);
+        assert_eq!(loaded.plans, ledger.plans);
+        assert!(loaded.has_applied("2025-01-01T10:00:00.500Z"));
+        assert!(loaded.has_applied("2025-01-01T09:00:00Z"));
+        assert!(!loaded.has_applied("2025-01-01T10:00:01Z"));
+        assert!(!loaded.has_applied(""));
+    }
+
     #[test]
     fn depth_of_defaults_unseen() {
         let ledger = ContextLedger::new();
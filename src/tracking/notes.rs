@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::symbols::SymbolId;
+
+/// Where review notes are saved, relative to the project root.
+pub const NOTES_FILE: &str = ".ambits/notes.json";
+
+/// Free-form review notes keyed by symbol id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Notes {
+    pub by_symbol: BTreeMap<SymbolId, String>,
+}
+
+impl Notes {
+    /// Load `<project_root>/.ambits/notes.json`, or no notes if there is none.
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = project_root.join(NOTES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).wrap_err_with(|| format!("Invalid notes in {}", path.display()))
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let path = project_root.join(NOTES_FILE);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn get(&self, symbol_id: &str) -> Option<&str> {
+        self.by_symbol.get(symbol_id).map(String::as_str)
+    }
+
+    /// Set the note for a symbol. A blank note removes it.
+    pub fn set(&mut self, symbol_id: &str, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.by_symbol.remove(symbol_id);
+        } else {
+            self.by_symbol.insert(symbol_id.to_string(), text.to_string());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_symbol.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_note_removes_entry() {
+        let mut notes = Notes::default();
+        notes.set("a.rs::f", "  didn't read the error path ");
+        assert_eq!(notes.get("a.rs::f"), Some("didn't read the error path"));
+        notes.set("a.rs::f", "   ");
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        assert!(Notes::load(dir).unwrap().is_empty());
+
+        let mut notes = Notes::default();
+        notes.set("src/a.rs::f", "check this");
+        notes.save(dir).unwrap();
+        assert_eq!(Notes::load(dir).unwrap(), notes);
+    }
+}
@@ -5,8 +5,10 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 
 use ambits::app::{App, FocusPanel};
+use ambits::ingest::AgentToolCall;
 
 use super::colors;
+use super::truncate_chars;
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
     let border_style = if app.focus == FocusPanel::Activity {
@@ -22,22 +24,19 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
 
     // Show the most recent events that fit in the area.
     let max_lines = area.height.saturating_sub(2) as usize;
-    let start = app.activity.len().saturating_sub(max_lines);
-    let visible = &app.activity[start..];
+    let runs = collapse_repeats(&app.activity);
+    let start = runs.len().saturating_sub(max_lines);
+    let visible = &runs[start..];
 
     let lines: Vec<Line> = visible
         .iter()
-        .map(|event| {
-            let agent_short = if event.agent_id.len() > 8 {
-                &event.agent_id[..8]
-            } else {
-                &event.agent_id
-            };
-
-            Line::from(vec![
+        .map(|&(event, count)| {
+            let agent_short = truncate_chars(&event.agent_id, 8);
+
+            let mut spans = vec![
                 Span::styled(
                     format!(" [{}] ", agent_short),
-                    Style::default().fg(colors::ACCENT_MUTED),
+                    Style::default().fg(colors::palette().accent_muted),
                 ),
                 Span::styled(
                     &event.description,
@@ -47,7 +46,11 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
                     format!("  ({})", event.read_depth),
                     Style::default().fg(Color::DarkGray),
                 ),
-            ])
+            ];
+            if count > 1 {
+                spans.push(Span::styled(format!(" \u{d7}{count}"), Style::default().fg(Color::Yellow)));
+            }
+            Line::from(spans)
         })
         .collect();
 
@@ -66,21 +69,42 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
+/// Runs of consecutive events by the same agent with the same tool, path and
+/// depth, as the latest event of each run and the run's length. Re-reading a
+/// file after every edit then takes one line instead of dozens.
+fn collapse_repeats(events: &[AgentToolCall]) -> Vec<(&AgentToolCall, usize)> {
+    let mut runs: Vec<(&AgentToolCall, usize)> = Vec::new();
+    for event in events {
+        match runs.last_mut() {
+            Some((last, count))
+                if last.agent_id == event.agent_id
+                    && last.tool_name == event.tool_name
+                    && last.file_path == event.file_path
+                    && last.read_depth == event.read_depth =>
+            {
+                *last = event;
+                *count += 1;
+            }
+            _ => runs.push((event, 1)),
+        }
+    }
+    runs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::PathBuf;
     use ratatui::backend::TestBackend;
     use ratatui::Terminal;
-    use ambits::ingest::AgentToolCall;
     use ambits::symbols::{ProjectTree, FileSymbols};
-    use ambits::tracking::ReadDepth;
+    use ambits::tracking::{ReadDepth, ReadSource};
 
     fn test_app() -> App {
         let tree = ProjectTree {
             root: PathBuf::from("/test"),
             files: vec![
-                FileSymbols { file_path: "mock/a.rs".into(), symbols: Vec::new(), total_lines: 10 },
+                FileSymbols { file_path: "mock/a.rs".into(), symbols: Vec::new(), total_lines: 10, modified: None, required: None },
             ],
         };
         App::new(tree, PathBuf::from("/test"), None)
@@ -108,6 +132,11 @@ mod tests {
             timestamp_str: "2025-01-01T00:00:00Z".into(),
             target_symbol: None,
             target_lines: None,
+            path_glob: None,
+            symbol_depth_limit: None,
+            todos: Vec::new(),
+            source: ReadSource::Explicit,
+            sub_index: 0,
         });
 
         let backend = TestBackend::new(60, 10);
@@ -115,6 +144,64 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), 1, "agent-ab").unwrap();
-        assert_eq!(color, colors::ACCENT_MUTED);
+        assert_eq!(color, colors::palette().accent_muted);
+    }
+
+    #[test]
+    fn repeated_reads_collapse_into_one_counted_line() {
+        let mut app = test_app();
+        let read = |path: &str| AgentToolCall {
+            agent_id: "agent-1".into(),
+            tool_name: "Read".into(),
+            file_path: Some(PathBuf::from(path)),
+            read_depth: ReadDepth::FullBody,
+            description: format!("Read {path}"),
+            timestamp_str: "2025-01-01T00:00:00Z".into(),
+            target_symbol: None,
+            target_lines: None,
+            path_glob: None,
+            symbol_depth_limit: None,
+            todos: Vec::new(),
+            source: ReadSource::Explicit,
+            sub_index: 0,
+        };
+        app.activity.extend([read("mock/a.rs"), read("mock/a.rs"), read("mock/a.rs"), read("mock/b.rs"), read("mock/a.rs")]);
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| render(f, &app, f.area())).unwrap();
+        let buf = terminal.backend().buffer();
+        let rows: Vec<String> = (1..4)
+            .map(|y| (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect::<String>().trim_end().to_string())
+            .collect();
+        assert!(rows[0].contains("Read mock/a.rs  (full) \u{d7}3"), "{rows:?}");
+        assert!(rows[1].contains("Read mock/b.rs") && !rows[1].contains('\u{d7}'), "{rows:?}");
+        assert!(rows[2].contains("Read mock/a.rs") && !rows[2].contains('\u{d7}'), "{rows:?}");
+    }
+
+    #[test]
+    fn render_multibyte_agent_id_does_not_panic() {
+        let mut app = test_app();
+        app.activity.push(AgentToolCall {
+            agent_id: "агент-абвгд".into(),
+            tool_name: "Read".into(),
+            file_path: Some(PathBuf::from("mock/a.rs")),
+            read_depth: ReadDepth::FullBody,
+            description: "Read a.rs".into(),
+            timestamp_str: "2025-01-01T00:00:00Z".into(),
+            target_symbol: None,
+            target_lines: None,
+            path_glob: None,
+            symbol_depth_limit: None,
+            todos: Vec::new(),
+            source: ReadSource::Explicit,
+            sub_index: 0,
+        });
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| render(f, &app, f.area())).unwrap();
+
+        assert!(fg_color_of(terminal.backend(), 1, "[").is_some());
     }
 }
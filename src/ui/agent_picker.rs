@@ -0,0 +1,64 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+
+use ambits::app::App;
+
+use super::colors;
+use super::stats::{bar, coverage_color};
+use super::truncate_chars;
+
+/// Popup listing every agent with its coverage, for choosing the agent filter.
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let Some(cursor) = app.agent_picker else {
+        return;
+    };
+
+    let total = app.project_tree.total_symbols();
+    let seen_by_agent = app.ledger.seen_by_agent();
+    let mut items = vec![ListItem::new(Line::from(vec![
+        Span::raw("  All agents   "),
+        Span::styled(
+            format!("{} seen", app.ledger.total_seen()),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]))];
+    for agent_id in &app.agents_seen {
+        let seen = *seen_by_agent.get(agent_id).unwrap_or(&0);
+        let pct = if total > 0 { (seen as f64 / total as f64 * 100.0) as u32 } else { 0 };
+        let marker = if app.agent_filter.as_deref() == Some(agent_id.as_str()) { "\u{25cf} " } else { "  " };
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(marker, Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:<24} ", truncate_chars(agent_id, 24)), Style::default().fg(colors::palette().accent_muted)),
+            Span::styled(bar(pct, 10), Style::default().fg(coverage_color(pct))),
+            Span::styled(format!(" {pct:>3}% {seen:>5} seen"), Style::default().fg(Color::DarkGray)),
+        ])));
+    }
+
+    let width = 60.min(area.width);
+    let height = (items.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let block = Block::default()
+        .title(" Agents [enter] select [esc] ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let list = List::new(items).block(block).highlight_style(
+        Style::default()
+            .bg(colors::palette().highlight_bg)
+            .fg(colors::palette().highlight_fg)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut state = ListState::default();
+    state.select(Some(cursor));
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut state);
+}
@@ -1,28 +1,158 @@
 //! Shared color palette for the TUI.
+//!
+//! Rendering code reads colors from [`palette()`], the theme chosen at startup
+//! with `--theme`; it defaults to [`DEFAULT`].
+
+use std::cell::Cell;
 
 use ratatui::style::Color;
 
-// ── Read-depth colors (symbol level) ────────────────────────────────
-pub const DEPTH_UNSEEN: Color = Color::Rgb(100, 100, 100);
-pub const DEPTH_NAME_ONLY: Color = Color::Rgb(160, 160, 160);
-pub const DEPTH_OVERVIEW: Color = Color::Rgb(120, 160, 220);
-pub const DEPTH_SIGNATURE: Color = Color::Rgb(80, 140, 255);
-pub const DEPTH_FULL_BODY: Color = Color::Rgb(80, 220, 120);
-pub const DEPTH_STALE: Color = Color::Rgb(230, 160, 60);
-
-// ── File coverage colors (file header level) ────────────────────────
-pub const FILE_FULLY_COVERED: Color = Color::Rgb(80, 220, 120);
-pub const FILE_ALL_SEEN: Color = Color::Rgb(180, 220, 80);
-pub const FILE_PARTIALLY_COVERED: Color = Color::Rgb(255, 180, 50);
-pub const FILE_NOT_COVERED: Color = Color::White;
-
-// ── Coverage percentage gradient ────────────────────────────────────
-pub const PCT_LOW: Color = Color::Rgb(180, 60, 60);
-pub const PCT_MID_LOW: Color = Color::Rgb(230, 160, 60);
-pub const PCT_MID_HIGH: Color = Color::Rgb(200, 200, 80);
-pub const PCT_HIGH: Color = Color::Rgb(80, 220, 120);
-
-// ── Accent / chrome ─────────────────────────────────────────────────
-pub const ACCENT_MUTED: Color = Color::Rgb(120, 120, 180);
-pub const HIGHLIGHT_BG: Color = Color::Rgb(60, 55, 50);
-pub const HIGHLIGHT_FG: Color = Color::Rgb(255, 220, 150);
+/// One theme's colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    // ── Read-depth colors (symbol level) ────────────────────────────
+    pub depth_unseen: Color,
+    pub depth_name_only: Color,
+    pub depth_overview: Color,
+    pub depth_signature: Color,
+    pub depth_full_body: Color,
+    pub depth_stale: Color,
+
+    // ── File coverage colors (file header level) ────────────────────
+    pub file_fully_covered: Color,
+    pub file_all_seen: Color,
+    pub file_partially_covered: Color,
+    pub file_not_covered: Color,
+
+    // ── Coverage percentage gradient ────────────────────────────────
+    pub pct_low: Color,
+    pub pct_mid_low: Color,
+    pub pct_mid_high: Color,
+    pub pct_high: Color,
+
+    // ── Accent / chrome ─────────────────────────────────────────────
+    pub accent_muted: Color,
+    pub highlight_bg: Color,
+    pub highlight_fg: Color,
+}
+
+pub const DEFAULT: Palette = Palette {
+    depth_unseen: Color::Rgb(100, 100, 100),
+    depth_name_only: Color::Rgb(160, 160, 160),
+    depth_overview: Color::Rgb(120, 160, 220),
+    depth_signature: Color::Rgb(80, 140, 255),
+    depth_full_body: Color::Rgb(80, 220, 120),
+    depth_stale: Color::Rgb(230, 160, 60),
+
+    file_fully_covered: Color::Rgb(80, 220, 120),
+    file_all_seen: Color::Rgb(180, 220, 80),
+    file_partially_covered: Color::Rgb(255, 180, 50),
+    file_not_covered: Color::White,
+
+    pct_low: Color::Rgb(180, 60, 60),
+    pct_mid_low: Color::Rgb(230, 160, 60),
+    pct_mid_high: Color::Rgb(200, 200, 80),
+    pct_high: Color::Rgb(80, 220, 120),
+
+    accent_muted: Color::Rgb(120, 120, 180),
+    highlight_bg: Color::Rgb(60, 55, 50),
+    highlight_fg: Color::Rgb(255, 220, 150),
+};
+
+/// Darker shades that stay readable on a light background.
+pub const LIGHT: Palette = Palette {
+    depth_unseen: Color::Rgb(150, 150, 150),
+    depth_name_only: Color::Rgb(100, 100, 100),
+    depth_overview: Color::Rgb(40, 90, 170),
+    depth_signature: Color::Rgb(20, 60, 200),
+    depth_full_body: Color::Rgb(20, 130, 50),
+    depth_stale: Color::Rgb(190, 100, 0),
+
+    file_fully_covered: Color::Rgb(20, 130, 50),
+    file_all_seen: Color::Rgb(100, 130, 0),
+    file_partially_covered: Color::Rgb(200, 110, 0),
+    file_not_covered: Color::Black,
+
+    pct_low: Color::Rgb(180, 30, 30),
+    pct_mid_low: Color::Rgb(200, 100, 0),
+    pct_mid_high: Color::Rgb(140, 130, 0),
+    pct_high: Color::Rgb(20, 130, 50),
+
+    accent_muted: Color::Rgb(70, 70, 150),
+    highlight_bg: Color::Rgb(215, 220, 235),
+    highlight_fg: Color::Rgb(20, 20, 60),
+};
+
+/// The 16 standard terminal colors, bright variants where they help.
+pub const HIGH_CONTRAST: Palette = Palette {
+    depth_unseen: Color::DarkGray,
+    depth_name_only: Color::White,
+    depth_overview: Color::LightCyan,
+    depth_signature: Color::LightBlue,
+    depth_full_body: Color::LightGreen,
+    depth_stale: Color::LightRed,
+
+    file_fully_covered: Color::LightGreen,
+    file_all_seen: Color::Green,
+    file_partially_covered: Color::Yellow,
+    file_not_covered: Color::White,
+
+    pct_low: Color::LightRed,
+    pct_mid_low: Color::Yellow,
+    pct_mid_high: Color::LightYellow,
+    pct_high: Color::LightGreen,
+
+    accent_muted: Color::LightMagenta,
+    highlight_bg: Color::White,
+    highlight_fg: Color::Black,
+};
+
+/// Shades of gray only; depth reads as brightness.
+pub const MONOCHROME: Palette = Palette {
+    depth_unseen: Color::DarkGray,
+    depth_name_only: Color::Gray,
+    depth_overview: Color::Gray,
+    depth_signature: Color::White,
+    depth_full_body: Color::White,
+    depth_stale: Color::DarkGray,
+
+    file_fully_covered: Color::White,
+    file_all_seen: Color::White,
+    file_partially_covered: Color::Gray,
+    file_not_covered: Color::DarkGray,
+
+    pct_low: Color::DarkGray,
+    pct_mid_low: Color::Gray,
+    pct_mid_high: Color::Gray,
+    pct_high: Color::White,
+
+    accent_muted: Color::Gray,
+    highlight_bg: Color::Gray,
+    highlight_fg: Color::Black,
+};
+
+/// Built-in themes by name, for `--theme` and `--list-themes`.
+pub const THEMES: [(&str, &Palette); 4] = [
+    ("default", &DEFAULT),
+    ("light", &LIGHT),
+    ("high-contrast", &HIGH_CONTRAST),
+    ("monochrome", &MONOCHROME),
+];
+
+thread_local! {
+    static ACTIVE: Cell<&'static Palette> = const { Cell::new(&DEFAULT) };
+}
+
+/// The built-in theme called `name`.
+pub fn theme(name: &str) -> Option<&'static Palette> {
+    THEMES.iter().find(|(n, _)| *n == name).map(|(_, p)| *p)
+}
+
+/// The palette rendering uses on this thread.
+pub fn palette() -> &'static Palette {
+    ACTIVE.with(Cell::get)
+}
+
+pub fn set_palette(palette: &'static Palette) {
+    ACTIVE.with(|active| active.set(palette));
+}
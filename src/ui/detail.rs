@@ -0,0 +1,64 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use ambits::app::App;
+
+/// Popup with metadata for the selected symbol, drawn over the tree.
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let Some(sym) = app.selected_symbol() else {
+        return;
+    };
+
+    let field = |name: &'static str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("  {name:<11}"), Style::default().fg(Color::DarkGray)),
+            Span::raw(value),
+        ])
+    };
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(format!("  {} ", sym.label), Style::default().fg(Color::DarkGray)),
+            Span::styled(sym.name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(""),
+        field("file", sym.file_path.display().to_string()),
+        field("lines", format!("{}-{}", sym.line_range.start, sym.line_range.end)),
+        field("tokens", format!("~{}", sym.estimated_tokens)),
+        field("seen", format!("{:?}", app.ledger.depth_of(&sym.id))),
+    ];
+    if !sym.derives.is_empty() {
+        lines.push(field("derives", sym.derives.join(", ")));
+    }
+    if !sym.decorators.is_empty() {
+        let names: Vec<String> = sym.decorators.iter().map(|d| format!("@{d}")).collect();
+        lines.push(field("decorators", names.join(", ")));
+    }
+    if let Some(note) = app.ledger.notes.get(&sym.id) {
+        lines.push(field("note", note.to_string()));
+    }
+
+    let width = (area.width * 3 / 5).max(40).min(area.width);
+    let height = (lines.len() as u16 + 3).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let block = Block::default()
+        .title(" Details [esc] ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).block(block).wrap(Wrap { trim: false }),
+        popup,
+    );
+}
@@ -2,19 +2,63 @@ pub mod colors;
 pub mod tree_view;
 pub mod stats;
 pub mod activity;
+pub mod detail;
+pub mod unseen;
+pub mod agent_picker;
+pub mod session_picker;
+pub mod warnings;
 
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 
-use ambits::app::{App, SortMode};
+use ambits::app::{AgentFilterMode, App, FocusPanel, LayoutMode, SortMode};
+
+/// Smallest terminal the three-panel layout fits in.
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 20;
+
+/// Below this width `LayoutMode::Auto` stacks the panels.
+pub const STACKED_BELOW_WIDTH: u16 = 100;
 
 pub fn render(f: &mut Frame, app: &App) {
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        render_too_small(f, area);
+        return;
+    }
+
+    let stacked = match app.layout {
+        LayoutMode::Auto => area.width < STACKED_BELOW_WIDTH,
+        LayoutMode::Split => false,
+        LayoutMode::Stacked => true,
+    };
+    let (tree_area, detail_area) = if stacked {
+        render_stacked(f, app)
+    } else {
+        render_split(f, app)
+    };
+
+    unseen::render(f, app, tree_area);
+
+    if app.show_detail {
+        detail::render(f, app, detail_area);
+    }
+    agent_picker::render(f, app, detail_area);
+    session_picker::render(f, app, detail_area);
+    warnings::render(f, app, detail_area);
+}
+
+/// Tree and stats side by side over the activity feed, when it's shown.
+/// Returns the tree area and the area popups are centred in.
+fn render_split(f: &mut Frame, app: &App) -> (Rect, Rect) {
+    let activity_height = if app.show_activity { 8 } else { 0 };
     let outer = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(10),       // top: tree + stats
-            Constraint::Length(8),     // bottom: activity feed
-            Constraint::Length(1),     // status bar
+            Constraint::Length(1),                // header
+            Constraint::Min(10),                  // top: tree + stats
+            Constraint::Length(activity_height),  // bottom: activity feed
+            Constraint::Length(1),                // status bar
         ])
         .split(f.area());
 
@@ -24,27 +68,194 @@ pub fn render(f: &mut Frame, app: &App) {
             Constraint::Percentage(62),  // tree
             Constraint::Percentage(38),  // stats
         ])
-        .split(outer[0]);
+        .split(outer[1]);
 
+    render_header(f, app, outer[0]);
     tree_view::render(f, app, top[0]);
     stats::render(f, app, top[1]);
-    activity::render(f, app, outer[1]);
-    render_status_bar(f, app, outer[2]);
+    if app.show_activity {
+        activity::render(f, app, outer[2]);
+    }
+    render_status_bar(f, app, outer[3]);
+    (top[0], outer[1])
 }
 
-fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+/// Full-width tree over a single panel: the activity feed when it has focus,
+/// the stats otherwise.
+fn render_stacked(f: &mut Frame, app: &App) -> (Rect, Rect) {
+    let outer = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),     // header
+            Constraint::Min(6),        // tree
+            Constraint::Length(10),    // stats or activity
+            Constraint::Length(1),     // status bar
+        ])
+        .split(f.area());
+
+    render_header(f, app, outer[0]);
+    tree_view::render(f, app, outer[1]);
+    if app.focus == FocusPanel::Activity {
+        activity::render(f, app, outer[2]);
+    } else {
+        stats::render(f, app, outer[2]);
+    }
+    render_status_bar(f, app, outer[3]);
+    (outer[1], outer[1])
+}
+
+/// One line naming the project, session and symbol source, with overall coverage.
+fn render_header(f: &mut Frame, app: &App, area: Rect) {
+    use ratatui::style::{Modifier, Style};
+    use ratatui::text::{Line, Span};
+    use ratatui::widgets::Paragraph;
+
+    let palette = colors::palette();
+    let base = Style::default().bg(palette.highlight_bg).fg(palette.highlight_fg);
+    let pct = app.seen_percent();
+    let session = match &app.session_id {
+        Some(id) => format!("session {}", truncate_chars(id, 8)),
+        None => "no session".to_string(),
+    };
+    let mut spans = vec![
+        Span::styled(" ambits ", base.add_modifier(Modifier::BOLD)),
+        Span::styled(format!(" {} ", abbreviate_path(&app.project_root, 40)), base),
+        Span::styled(format!("\u{2502} {session} \u{2502} {} \u{2502} ", app.symbol_source.label()), base),
+        Span::styled(format!("{pct}% seen "), base.fg(stats::coverage_color(pct)).add_modifier(Modifier::BOLD)),
+        Span::styled(format!("\u{2502} {} tok unseen ", format_tokens(app.unseen_tokens())), base),
+    ];
+    if let Some(replay) = &app.replay {
+        let state = if replay.is_finished() {
+            "replay done".to_string()
+        } else if replay.paused {
+            format!("\u{23f8} paused, {} left", replay.remaining())
+        } else {
+            format!("\u{25b6} replay {}x, {} left", replay.speed, replay.remaining())
+        };
+        spans.push(Span::styled(format!("\u{2502} {state} "), base.add_modifier(Modifier::BOLD)));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)).style(base), area);
+}
+
+/// `path` with the home directory shown as `~`, keeping only its last
+/// components (after `…/`) when longer than `max_chars`.
+pub fn abbreviate_path(path: &std::path::Path, max_chars: usize) -> String {
+    let mut text = path.display().to_string();
+    if let Some(home) = std::env::var_os("HOME").filter(|h| !h.is_empty()) {
+        if let Ok(rest) = path.strip_prefix(&home) {
+            text = if rest.as_os_str().is_empty() { "~".into() } else { format!("~/{}", rest.display()) };
+        }
+    }
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut tail = String::new();
+    for part in text.rsplit('/') {
+        let candidate = if tail.is_empty() { part.to_string() } else { format!("{part}/{tail}") };
+        if candidate.chars().count() + 2 > max_chars && !tail.is_empty() {
+            break;
+        }
+        tail = candidate;
+    }
+    format!("\u{2026}/{tail}")
+}
+
+fn render_too_small(f: &mut Frame, area: Rect) {
+    use ratatui::layout::Alignment;
+    use ratatui::text::Line;
+    use ratatui::widgets::{Paragraph, Wrap};
+
+    let lines = vec![
+        Line::from("Terminal too small"),
+        Line::from(format!("{}x{}, need {}x{}", area.width, area.height, MIN_WIDTH, MIN_HEIGHT)),
+    ];
+    // Vertically centre when there's room.
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let inner = Rect {
+        y: area.y + top,
+        height: area.height - top,
+        ..area
+    };
+    f.render_widget(
+        Paragraph::new(lines).alignment(Alignment::Center).wrap(Wrap { trim: true }),
+        inner,
+    );
+}
+
+/// Token counts as "950", "12.3k" or "1.2M".
+pub fn format_tokens(tokens: usize) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Return at most the first `max_chars` characters of `s`.
+/// Slices on a char boundary, so multi-byte ids never panic.
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+/// Compact age like "45s", "12m", "3h07m" or "2d04h".
+pub fn format_age(age: chrono::Duration) -> String {
+    let secs = age.num_seconds().max(0);
+    match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h{:02}m", s / 3600, (s % 3600) / 60),
+        s => format!("{}d{:02}h", s / 86_400, (s % 86_400) / 3600),
+    }
+}
+
+fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     use ratatui::style::{Color, Style};
     use ratatui::text::{Line, Span};
     use ratatui::widgets::Paragraph;
 
+    // Right-hand side: session age and wall clock.
+    let mut clock = String::new();
+    if let Some(age) = app.session_age() {
+        clock.push_str(&format!("session {} ", format_age(age)));
+    }
+    if let Some(now) = app.now {
+        clock.push_str(&now.format("%H:%M ").to_string());
+    }
+    let parts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(clock.chars().count() as u16)])
+        .split(area);
+
     let status = if app.search_mode {
         Line::from(vec![
             Span::styled(" /", Style::default().fg(Color::Yellow)),
             Span::raw(&app.search_query),
             Span::styled("_", Style::default().fg(Color::Yellow)),
         ])
-    } else {
+    } else if let Some((_, text)) = &app.note_input {
+        Line::from(vec![
+            Span::styled(" note: ", Style::default().fg(Color::Magenta)),
+            Span::raw(text),
+            Span::styled("_", Style::default().fg(Color::Magenta)),
+        ])
+    } else if app.quit_pending {
         Line::from(vec![
+            Span::styled(" Quit? ", Style::default().fg(Color::Yellow)),
+            Span::raw("[y/q] yes, any other key cancels"),
+        ])
+    } else if let Some(msg) = app.active_flash() {
+        Line::from(Span::styled(format!(" {msg}"), Style::default().fg(Color::Yellow)))
+    } else {
+        let mut spans = Vec::new();
+        // With the feed hidden, keep the latest event in view.
+        if let Some(event) = app.activity.last().filter(|_| !app.show_activity) {
+            spans.push(Span::styled(format!(" [{}] ", truncate_chars(&event.agent_id, 8)), Style::default().fg(Color::Cyan)));
+            spans.push(Span::raw(format!("{} \u{2502}", event.description)));
+        }
+        spans.extend([
             Span::styled(" [q]", Style::default().fg(Color::DarkGray)),
             Span::raw("uit "),
             Span::styled("[j/k]", Style::default().fg(Color::DarkGray)),
@@ -60,13 +271,341 @@ fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
             }),
             Span::styled("[a]", Style::default().fg(Color::DarkGray)),
             Span::raw("gents "),
+            Span::styled("[u]", Style::default().fg(Color::DarkGray)),
+            Span::raw(match app.agent_filter_mode {
+                AgentFilterMode::CoveredBy => "nseen:off ",
+                AgentFilterMode::NotCoveredBy => "nseen:on ",
+            }),
+            Span::styled("[i]", Style::default().fg(Color::DarkGray)),
+            Span::raw("nfo "),
+            Span::styled("[n]", Style::default().fg(Color::DarkGray)),
+            Span::raw("ote "),
             Span::styled("[tab]", Style::default().fg(Color::DarkGray)),
             Span::raw("focus "),
-        ])
+        ]);
+        Line::from(spans)
     };
 
-    f.render_widget(
-        Paragraph::new(status).style(Style::default().bg(Color::DarkGray).fg(Color::White)),
-        area,
-    );
+    let style = Style::default().bg(Color::DarkGray).fg(Color::White);
+    f.render_widget(Paragraph::new(status).style(style), parts[0]);
+    f.render_widget(Paragraph::new(clock).style(style), parts[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+    use ratatui::Terminal;
+    use ambits::ingest::AgentToolCall;
+    use ambits::symbols::{FileSymbols, ProjectTree, SymbolCategory, SymbolNode};
+    use ambits::tracking::{ReadDepth, ReadSource};
+
+    fn sym(id: &str, name: &str, lines: std::ops::Range<usize>) -> SymbolNode {
+        let hash = ambits::symbols::merkle::content_hash(name);
+        SymbolNode {
+            id: id.into(), name: name.into(), category: SymbolCategory::Function,
+            label: "fn".into(), file_path: PathBuf::new(),
+            byte_range: 0..100, line_range: lines, content_hash: hash,
+            merkle_hash: hash, children: Vec::new(), estimated_tokens: 30,
+            derives: Vec::new(),
+            decorators: Vec::new(),
+            test_only: false,
+            name_range: None,
+        }
+    }
+
+    fn snapshot_app() -> App {
+        let tree = ProjectTree {
+            root: PathBuf::from("/test"),
+            files: vec![
+                FileSymbols {
+                    file_path: "src/a.rs".into(),
+                    symbols: vec![sym("src/a.rs::alpha", "alpha", 1..10), sym("src/a.rs::beta", "beta", 12..30)],
+                    total_lines: 30,
+                    modified: None,
+                    required: None,
+                },
+                FileSymbols {
+                    file_path: "src/b.rs".into(),
+                    symbols: vec![sym("src/b.rs::gamma", "gamma", 1..20)],
+                    total_lines: 20,
+                    modified: None,
+                    required: None,
+                },
+            ],
+        };
+        let mut app = App::new(tree, PathBuf::from("/test"), None);
+        app.session_id = Some("11111111-2222-3333-4444-555555555555".into());
+        app.collapsed.remove("src/a.rs");
+        app.rebuild_tree_rows();
+        app
+    }
+
+    fn read_event(agent: &str, path: &str) -> AgentToolCall {
+        AgentToolCall {
+            agent_id: agent.into(),
+            tool_name: "Read".into(),
+            file_path: Some(PathBuf::from(path)),
+            read_depth: ReadDepth::FullBody,
+            description: format!("Read {path}"),
+            timestamp_str: "2025-01-01T00:00:00Z".into(),
+            target_symbol: None,
+            target_lines: None,
+            path_glob: None,
+            symbol_depth_limit: None,
+            todos: Vec::new(),
+            source: ReadSource::Explicit,
+            sub_index: 0,
+        }
+    }
+
+    /// Dump a buffer as plain text, one line per row, trailing spaces trimmed.
+    fn buffer_to_string(buf: &Buffer) -> String {
+        let mut out = String::new();
+        for y in 0..buf.area.height {
+            let row: String = (0..buf.area.width)
+                .map(|x| buf[(x, y)].symbol().to_string())
+                .collect();
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Compare `actual` against `tests/snapshots/<name>.txt`.
+    /// Run with `UPDATE_SNAPSHOTS=1` to (re)write the golden file.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(format!("{name}.txt"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+            panic!("missing snapshot {}; run with UPDATE_SNAPSHOTS=1", path.display())
+        });
+        assert!(
+            expected == actual,
+            "snapshot {name} differs (run with UPDATE_SNAPSHOTS=1 to accept)\n--- expected\n{expected}\n--- actual\n{actual}"
+        );
+    }
+
+    fn render_to_string(app: &App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| render(f, app)).unwrap();
+        buffer_to_string(terminal.backend().buffer())
+    }
+
+    #[test]
+    fn snapshot_empty_session() {
+        let app = snapshot_app();
+        assert_snapshot("render_empty_session", &render_to_string(&app, 100, 30));
+    }
+
+    #[test]
+    fn snapshot_with_coverage_and_activity() {
+        let mut app = snapshot_app();
+        app.process_agent_event(read_event("agent-a1b2c3d4", "/test/src/a.rs"));
+        app.process_agent_event(read_event("11111111-2222-3333-4444-555555555555", "/test/src/b.rs"));
+        assert_snapshot("render_with_coverage", &render_to_string(&app, 100, 30));
+    }
+
+    #[test]
+    fn detail_popup_shows_derives() {
+        let mut app = snapshot_app();
+        app.project_tree.files[0].symbols[1].derives = vec!["Debug".into(), "Clone".into()];
+        app.selected_index = 2;
+        app.show_detail = true;
+        let out = render_to_string(&app, 100, 30);
+        assert!(out.contains("beta"));
+        assert!(out.contains("derives    Debug, Clone"));
+    }
+
+    #[test]
+    fn detail_popup_shows_decorators() {
+        let mut app = snapshot_app();
+        app.project_tree.files[0].symbols[1].decorators = vec!["Injectable".into(), "app.route".into()];
+        app.selected_index = 2;
+        app.show_detail = true;
+        let out = render_to_string(&app, 100, 30);
+        assert!(out.contains("decorators @Injectable, @app.route"));
+    }
+
+    #[test]
+    fn unseen_alert_banner() {
+        let mut app = snapshot_app();
+        app.note_file_changed(std::path::Path::new("src/a.rs"));
+        let out = render_to_string(&app, 100, 30);
+        assert!(out.contains("Edited src/a.rs — 2 unseen"));
+        assert!(out.contains("alpha  L1-10"));
+    }
+
+    #[test]
+    fn status_bar_clock_and_session_age() {
+        use chrono::{Local, TimeZone};
+
+        let mut app = snapshot_app();
+        app.process_agent_event(read_event("agent-a1b2c3d4", "/test/src/a.rs"));
+        app.now = Some(Local.with_ymd_and_hms(2030, 6, 1, 9, 41, 0).unwrap());
+        let out = render_to_string(&app, 140, 30);
+        let status = out.lines().nth(29).unwrap();
+        assert!(status.ends_with("09:41"), "{status}");
+        assert!(status.contains("session "), "{status}");
+    }
+
+    #[test]
+    fn hidden_activity_feed_gives_its_rows_to_the_tree() {
+        let mut app = snapshot_app();
+        app.process_agent_event(read_event("agent-a1b2c3d4", "/test/src/a.rs"));
+        let shown = render_to_string(&app, 140, 30);
+        assert!(shown.contains("Activity Feed"));
+
+        app.show_activity = false;
+        let hidden = render_to_string(&app, 140, 30);
+        assert!(!hidden.contains("Activity Feed"));
+        let status = hidden.lines().nth(29).unwrap();
+        assert!(status.contains("[agent-a1] Read /test/src/a.rs"), "{status}");
+        // The tree panel now runs down to the status bar.
+        assert!(hidden.lines().nth(28).unwrap().starts_with('\u{2514}'), "{hidden}");
+    }
+
+    #[test]
+    fn format_age_units() {
+        assert_eq!(format_age(chrono::Duration::seconds(42)), "42s");
+        assert_eq!(format_age(chrono::Duration::minutes(12)), "12m");
+        assert_eq!(format_age(chrono::Duration::minutes(187)), "3h07m");
+        assert_eq!(format_age(chrono::Duration::hours(52)), "2d04h");
+    }
+
+    #[test]
+    fn tiny_terminal_shows_message_instead_of_panels() {
+        let mut app = snapshot_app();
+        app.show_detail = true;
+        app.note_file_changed(std::path::Path::new("src/a.rs"));
+        for (w, h) in [(1, 1), (20, 5), (59, 40), (120, 19)] {
+            let out = render_to_string(&app, w, h);
+            assert!(!out.contains("Symbol Tree"), "{w}x{h}");
+        }
+        let out = render_to_string(&app, 40, 10);
+        assert!(out.contains("Terminal too small"));
+        assert!(out.contains("40x10, need 60x20"));
+        assert!(render_to_string(&app, MIN_WIDTH, MIN_HEIGHT).contains("Symbol Tree"));
+    }
+
+    #[test]
+    fn narrow_terminals_stack_the_panels() {
+        let mut app = snapshot_app();
+        let side_by_side = |out: &str| out.lines().any(|l| l.contains("Symbol Tree") && l.contains("Coverage Stats"));
+
+        assert!(side_by_side(&render_to_string(&app, 100, 30)));
+        let narrow = render_to_string(&app, 80, 30);
+        assert!(!side_by_side(&narrow));
+        assert!(narrow.contains("Coverage Stats"));
+        assert!(!narrow.contains("Activity Feed"));
+
+        // Focusing the activity feed swaps it in for the stats.
+        app.focus = FocusPanel::Activity;
+        let narrow = render_to_string(&app, 80, 30);
+        assert!(narrow.contains("Activity Feed"));
+        assert!(!narrow.contains("Coverage Stats"));
+
+        app.layout = LayoutMode::Split;
+        assert!(side_by_side(&render_to_string(&app, 80, 30)));
+        app.layout = LayoutMode::Stacked;
+        assert!(!side_by_side(&render_to_string(&app, 140, 30)));
+    }
+
+    #[test]
+    fn agent_picker_popup_lists_agents() {
+        let mut app = snapshot_app();
+        app.process_agent_event(read_event("main", "/test/src/a.rs"));
+        app.process_agent_event(read_event("agent-7", "/test/src/b.rs"));
+        app.agent_picker = Some(2);
+        let out = render_to_string(&app, 100, 30);
+        assert!(out.contains(" Agents [enter] select [esc] "));
+        assert!(out.contains("All agents"));
+        assert!(out.lines().any(|l| l.contains("agent-7") && l.contains("seen")));
+    }
+
+    #[test]
+    fn session_picker_popup_lists_watched_sessions() {
+        let mut app = snapshot_app();
+        app.watch_sessions(vec!["11111111-2222-3333-4444-555555555555".into(), "other-session".into()]);
+        app.process_session_event("other-session", read_event("main", "/test/src/b.rs"));
+        app.session_picker = Some(1);
+        let out = render_to_string(&app, 100, 30);
+        assert!(out.contains(" Sessions [enter] show [esc] "));
+        assert!(out.lines().any(|l| l.contains("\u{25cf} 11111111-2222-3333")));
+        assert!(out.lines().any(|l| l.contains("other-session") && l.contains("1 seen")));
+    }
+
+    #[test]
+    fn warnings_popup_lists_files_missing_from_the_tree() {
+        let mut app = snapshot_app();
+        app.process_agent_event(read_event("main", "/test/docs/guide.md"));
+        app.process_agent_event(read_event("main", "/test/docs/guide.md"));
+        app.process_agent_event(read_event("main", "/test/build.sh"));
+        app.process_agent_event(read_event("main", "/test/src/a.rs"));
+        assert_eq!(app.orphan_reads.len(), 2);
+
+        app.handle_key(crossterm::event::KeyEvent::new(
+            crossterm::event::KeyCode::Char('W'),
+            crossterm::event::KeyModifiers::SHIFT,
+        ));
+        let out = render_to_string(&app, 100, 30);
+        assert!(out.contains(" Warnings [esc] "));
+        assert!(out.contains("3 events on 2 files not in the tree"), "{out}");
+        let guide = out.lines().position(|l| l.contains("2  docs/guide.md")).unwrap();
+        let build = out.lines().position(|l| l.contains("1  build.sh")).unwrap();
+        assert!(guide < build);
+    }
+
+    #[test]
+    fn abbreviate_path_keeps_trailing_components() {
+        let path = std::path::Path::new("/srv/builds/workspace/org/project");
+        assert_eq!(abbreviate_path(path, 40), "/srv/builds/workspace/org/project");
+        assert_eq!(abbreviate_path(path, 16), "\u{2026}/org/project");
+        assert_eq!(abbreviate_path(path, 4), "\u{2026}/project");
+    }
+
+    #[test]
+    fn header_shows_source_and_coverage() {
+        let mut app = snapshot_app();
+        app.symbol_source = ambits::app::SymbolSource::SerenaWithFallback;
+        let out = render_to_string(&app, 100, 30);
+        let header = out.lines().next().unwrap();
+        assert!(header.contains("session 11111111"), "{header}");
+        assert!(header.contains("serena+tree-sitter"), "{header}");
+        assert!(header.contains("0% seen"), "{header}");
+        assert!(header.contains(&format!("{} tok unseen", format_tokens(app.unseen_tokens()))), "{header}");
+    }
+
+    #[test]
+    fn header_shows_replay_state() {
+        let mut app = snapshot_app();
+        app.replay = Some(ambits::replay::Replay::new(vec![read_event("agent-1", "/test/src/a.rs")], 2.0));
+        let header = |app: &App| render_to_string(app, 120, 30).lines().next().unwrap().to_string();
+        assert!(header(&app).contains("replay 2x, 1 left"), "{}", header(&app));
+
+        app.handle_key(crossterm::event::KeyEvent::from(crossterm::event::KeyCode::Char(' ')));
+        assert!(header(&app).contains("paused, 1 left"), "{}", header(&app));
+    }
+
+    #[test]
+    fn format_tokens_units() {
+        assert_eq!(format_tokens(950), "950");
+        assert_eq!(format_tokens(12_345), "12.3k");
+        assert_eq!(format_tokens(1_250_000), "1.2M");
+    }
+
+    #[test]
+    fn truncate_chars_respects_boundaries() {
+        assert_eq!(truncate_chars("abcdef", 3), "abc");
+        assert_eq!(truncate_chars("ab", 3), "ab");
+        assert_eq!(truncate_chars("äöüß", 2), "äö");
+    }
 }
@@ -0,0 +1,60 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+
+use ambits::app::App;
+
+use super::colors;
+use super::stats::{bar, coverage_color};
+use super::truncate_chars;
+
+/// Popup listing every watched session with its coverage, for choosing which one is shown.
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let Some(cursor) = app.session_picker else {
+        return;
+    };
+
+    let total = app.project_tree.total_symbols();
+    let items: Vec<ListItem> = app
+        .sessions
+        .iter()
+        .map(|id| {
+            let seen = app.session_ledger(id).map_or(0, |l| l.total_seen());
+            let pct = if total > 0 { (seen as f64 / total as f64 * 100.0) as u32 } else { 0 };
+            let marker = if app.session_id.as_deref() == Some(id.as_str()) { "\u{25cf} " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(Color::Yellow)),
+                Span::styled(format!("{:<24} ", truncate_chars(id, 24)), Style::default().fg(colors::palette().accent_muted)),
+                Span::styled(bar(pct, 10), Style::default().fg(coverage_color(pct))),
+                Span::styled(format!(" {pct:>3}% {seen:>5} seen"), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let width = 60.min(area.width);
+    let height = (items.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let block = Block::default()
+        .title(" Sessions [enter] show [esc] ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let list = List::new(items).block(block).highlight_style(
+        Style::default()
+            .bg(colors::palette().highlight_bg)
+            .fg(colors::palette().highlight_fg)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut state = ListState::default();
+    state.select(Some(cursor));
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut state);
+}
@@ -4,10 +4,12 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 
-use ambits::app::{App, FocusPanel};
+use ambits::app::{AgentFilterMode, App, FocusPanel};
+use ambits::coverage::{count_symbols_for_agent, CountMode};
 use ambits::tracking::ReadDepth;
 
 use super::colors;
+use super::truncate_chars;
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
     let border_style = if app.focus == FocusPanel::Stats {
@@ -21,9 +23,15 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
         .borders(Borders::ALL)
         .border_style(border_style);
 
-    let total = app.project_tree.total_symbols();
     let counts = app.ledger.count_by_depth();
-    let seen = app.ledger.total_seen();
+    let (total, seen) = if app.count_mode == CountMode::ALL {
+        (app.project_tree.total_symbols(), app.ledger.total_seen())
+    } else {
+        app.project_tree.files.iter().fold((0, 0), |(t, s), f| {
+            let (total, seen, _) = count_symbols_for_agent(&f.symbols, &app.ledger, None, app.count_mode);
+            (t + total, s + seen)
+        })
+    };
 
     let pct = if total > 0 {
         (seen as f64 / total as f64 * 100.0) as u32
@@ -48,16 +56,16 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(Color::DarkGray),
             ),
         ]),
-        Line::from(""),
-        stat_line("  Full Body", count_for(ReadDepth::FullBody), colors::DEPTH_FULL_BODY),
-        stat_line("  Signature", count_for(ReadDepth::Signature), colors::DEPTH_SIGNATURE),
-        stat_line("  Overview ", count_for(ReadDepth::Overview), colors::DEPTH_OVERVIEW),
-        stat_line("  Name Only", count_for(ReadDepth::NameOnly), colors::DEPTH_NAME_ONLY),
-        stat_line("  Stale    ", count_for(ReadDepth::Stale), colors::DEPTH_STALE),
+        trend_line(app),
+        stat_line("  Full Body", count_for(ReadDepth::FullBody), colors::palette().depth_full_body),
+        stat_line("  Signature", count_for(ReadDepth::Signature), colors::palette().depth_signature),
+        stat_line("  Overview ", count_for(ReadDepth::Overview), colors::palette().depth_overview),
+        stat_line("  Name Only", count_for(ReadDepth::NameOnly), colors::palette().depth_name_only),
+        stat_line("  Stale    ", count_for(ReadDepth::Stale), colors::palette().depth_stale),
         stat_line(
             "  Unseen   ",
             total.saturating_sub(seen),
-            colors::DEPTH_UNSEEN,
+            colors::palette().depth_unseen,
         ),
         Line::from(""),
         Line::from(vec![
@@ -74,12 +82,21 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
         ]),
     ];
 
+    // Composition by file extension.
+    for lang in app.project_tree.language_stats() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<7}", lang.label()), Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{:>5}", lang.symbols), Style::default().fg(Color::White)),
+            Span::styled(format!(" in {} files", lang.files), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+
     // Session info.
     if let Some(ref sid) = app.session_id {
-        let short = if sid.len() > 12 { &sid[..12] } else { sid };
+        let short = truncate_chars(sid, 12);
         lines.push(Line::from(vec![
             Span::raw("  Session: "),
-            Span::styled(short, Style::default().fg(colors::ACCENT_MUTED)),
+            Span::styled(short, Style::default().fg(colors::palette().accent_muted)),
         ]));
     }
 
@@ -92,16 +109,26 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(Color::White),
             ),
             Span::styled(
-                match &app.agent_filter {
-                    None => "[all]".to_string(),
-                    Some(id) => format!("[{}]", short_id(id)),
+                match (&app.agent_filter, app.agent_filter_mode) {
+                    (None, _) => "[all]".to_string(),
+                    (Some(id), AgentFilterMode::CoveredBy) => format!("[{}]", short_id(id)),
+                    (Some(id), AgentFilterMode::NotCoveredBy) => format!("[not {}]", short_id(id)),
                 },
                 Style::default().fg(Color::Yellow),
             ),
         ]));
 
-        for agent_id in &app.agents_seen {
-            let is_selected = app.agent_filter.as_deref() == Some(agent_id);
+        // One bar per agent, largest contribution first.
+        let seen_by_agent = app.ledger.seen_by_agent();
+        let mut agents: Vec<(&String, usize)> = app
+            .agents_seen
+            .iter()
+            .map(|id| (id, *seen_by_agent.get(id).unwrap_or(&0)))
+            .collect();
+        agents.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        for (agent_id, agent_seen) in agents {
+            let is_selected = app.agent_filter.as_deref() == Some(agent_id.as_str());
             let prefix = if agent_id.starts_with("agent-") {
                 "  \u{251c}\u{2500} "
             } else {
@@ -110,11 +137,18 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             let color = if is_selected {
                 Color::Yellow
             } else {
-                colors::ACCENT_MUTED
+                colors::palette().accent_muted
+            };
+            let agent_pct = if total > 0 {
+                (agent_seen as f64 / total as f64 * 100.0) as u32
+            } else {
+                0
             };
             lines.push(Line::from(vec![
                 Span::styled(prefix, Style::default().fg(Color::DarkGray)),
-                Span::styled(short_id(agent_id), Style::default().fg(color)),
+                Span::styled(format!("{:<12} ", short_id(agent_id)), Style::default().fg(color)),
+                Span::styled(bar(agent_pct, 10), Style::default().fg(coverage_color(agent_pct))),
+                Span::styled(format!(" {:>3}%", agent_pct), Style::default().fg(Color::DarkGray)),
             ]));
         }
     }
@@ -123,6 +157,44 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
+/// Sparkline of recent overall coverage, or a blank line until there are two samples.
+fn trend_line(app: &App) -> Line<'static> {
+    let samples: Vec<u32> = app.coverage_history.iter().copied().collect();
+    let (&first, &last) = match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) if samples.len() >= 2 => (first, last),
+        _ => return Line::from(""),
+    };
+    Line::from(vec![
+        Span::styled("  Trend: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(braille_sparkline(&samples), Style::default().fg(coverage_color(last))),
+        Span::styled(format!(" {first}\u{2192}{last}%"), Style::default().fg(Color::DarkGray)),
+    ])
+}
+
+/// Plot samples two per braille cell, four dot rows high, scaled to the samples'
+/// own range so small climbs still show. A flat series sits on the bottom row.
+fn braille_sparkline(samples: &[u32]) -> String {
+    // Dot bits per column, bottom row first.
+    const LEFT: [u32; 4] = [0x40, 0x04, 0x02, 0x01];
+    const RIGHT: [u32; 4] = [0x80, 0x20, 0x10, 0x08];
+
+    let lo = samples.iter().copied().min().unwrap_or(0);
+    let hi = samples.iter().copied().max().unwrap_or(0);
+    let height = |v: u32| if hi == lo { 1 } else { 1 + ((v - lo) * 3).div_ceil(hi - lo) as usize };
+    let column = |dots: &[u32; 4], v: u32| dots[..height(v)].iter().sum::<u32>();
+
+    samples
+        .chunks(2)
+        .map(|pair| {
+            let mut bits = column(&LEFT, pair[0]);
+            if let Some(&right) = pair.get(1) {
+                bits |= column(&RIGHT, right);
+            }
+            char::from_u32(0x2800 + bits).unwrap_or(' ')
+        })
+        .collect()
+}
+
 fn stat_line(label: &str, count: usize, color: Color) -> Line<'static> {
     Line::from(vec![
         Span::styled(
@@ -133,20 +205,22 @@ fn stat_line(label: &str, count: usize, color: Color) -> Line<'static> {
     ])
 }
 
+/// A fixed-width bar filled to `pct` percent.
+pub(super) fn bar(pct: u32, width: usize) -> String {
+    let filled = (pct as usize * width).div_ceil(100).min(width);
+    format!("{}{}", "\u{2588}".repeat(filled), "\u{2591}".repeat(width - filled))
+}
+
 fn short_id(id: &str) -> String {
-    if id.len() > 12 {
-        id[..12].to_string()
-    } else {
-        id.to_string()
-    }
+    truncate_chars(id, 12).to_string()
 }
 
-fn coverage_color(pct: u32) -> Color {
+pub(super) fn coverage_color(pct: u32) -> Color {
     match pct {
-        0..=20 => colors::PCT_LOW,
-        21..=50 => colors::PCT_MID_LOW,
-        51..=80 => colors::PCT_MID_HIGH,
-        _ => colors::PCT_HIGH,
+        0..=20 => colors::palette().pct_low,
+        21..=50 => colors::palette().pct_mid_low,
+        51..=80 => colors::palette().pct_mid_high,
+        _ => colors::palette().pct_high,
     }
 }
 
@@ -165,6 +239,10 @@ mod tests {
             label: "fn".into(), file_path: PathBuf::new(),
             byte_range: 0..100, line_range: 1..10, content_hash: hash,
             merkle_hash: hash, children: Vec::new(), estimated_tokens: 30,
+            derives: Vec::new(),
+            decorators: Vec::new(),
+            test_only: false,
+            name_range: None,
         }
     }
 
@@ -172,7 +250,7 @@ mod tests {
         let tree = ProjectTree {
             root: PathBuf::from("/test"),
             files: vec![
-                FileSymbols { file_path: "mock/a.rs".into(), symbols: vec![sym("a1", "alpha")], total_lines: 50 },
+                FileSymbols { file_path: "mock/a.rs".into(), symbols: vec![sym("a1", "alpha")], total_lines: 50, modified: None, required: None },
             ],
         };
         App::new(tree, PathBuf::from("/test"), None)
@@ -194,14 +272,14 @@ mod tests {
 
     #[test]
     fn coverage_color_gradient() {
-        assert_eq!(coverage_color(0), colors::PCT_LOW);
-        assert_eq!(coverage_color(20), colors::PCT_LOW);
-        assert_eq!(coverage_color(21), colors::PCT_MID_LOW);
-        assert_eq!(coverage_color(50), colors::PCT_MID_LOW);
-        assert_eq!(coverage_color(51), colors::PCT_MID_HIGH);
-        assert_eq!(coverage_color(80), colors::PCT_MID_HIGH);
-        assert_eq!(coverage_color(81), colors::PCT_HIGH);
-        assert_eq!(coverage_color(100), colors::PCT_HIGH);
+        assert_eq!(coverage_color(0), colors::palette().pct_low);
+        assert_eq!(coverage_color(20), colors::palette().pct_low);
+        assert_eq!(coverage_color(21), colors::palette().pct_mid_low);
+        assert_eq!(coverage_color(50), colors::palette().pct_mid_low);
+        assert_eq!(coverage_color(51), colors::palette().pct_mid_high);
+        assert_eq!(coverage_color(80), colors::palette().pct_mid_high);
+        assert_eq!(coverage_color(81), colors::palette().pct_high);
+        assert_eq!(coverage_color(100), colors::palette().pct_high);
     }
 
     #[test]
@@ -211,9 +289,16 @@ mod tests {
         assert_eq!(short_id("exactly12chr"), "exactly12chr");
     }
 
+    #[test]
+    fn short_id_multibyte_safe() {
+        // 'é' is two bytes; a byte slice at 12 would split a code point.
+        assert_eq!(short_id("agént-éééééééé"), "agént-éééééé");
+        assert_eq!(short_id("日本語"), "日本語");
+    }
+
     #[test]
     fn stat_line_format() {
-        let line = stat_line("  Full Body", 42, colors::DEPTH_FULL_BODY);
+        let line = stat_line("  Full Body", 42, colors::palette().depth_full_body);
         let spans: Vec<_> = line.spans.iter().map(|s| s.content.as_ref()).collect();
         assert_eq!(spans[0], "  Full Body: ");
         assert_eq!(spans[1], "   42");
@@ -232,7 +317,7 @@ mod tests {
             .flat_map(|y| (0..buf.area.width).map(move |x| &buf[(x, y)]))
             .find(|cell| cell.modifier.contains(Modifier::BOLD))
             .expect("bold percentage cell not found");
-        assert_eq!(bold_cell.fg, colors::PCT_LOW);
+        assert_eq!(bold_cell.fg, colors::palette().pct_low);
     }
 
     #[test]
@@ -246,7 +331,7 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), "100%").unwrap();
-        assert_eq!(color, colors::PCT_HIGH);
+        assert_eq!(color, colors::palette().pct_high);
     }
 
     #[test]
@@ -260,6 +345,75 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), "abcdef123456").unwrap();
-        assert_eq!(color, colors::ACCENT_MUTED);
+        assert_eq!(color, colors::palette().accent_muted);
+    }
+
+    #[test]
+    fn agent_bars_sorted_by_contribution() {
+        let mut app = test_app();
+        app.agents_seen.push("agent-small".into());
+        app.agents_seen.push("agent-big".into());
+        app.ledger.record("a1".into(), ReadDepth::FullBody, [0; 32], "agent-big".into(), 10);
+
+        let backend = TestBackend::new(48, 24);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| render(f, &app, f.area())).unwrap();
+
+        let buf = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buf.area.height)
+            .map(|y| (0..buf.area.width).map(|x| buf[(x, y)].symbol().to_string()).collect())
+            .collect();
+        let big = rows.iter().position(|r| r.contains("agent-big")).unwrap();
+        let small = rows.iter().position(|r| r.contains("agent-small")).unwrap();
+        assert!(big < small);
+        assert!(rows[big].contains("100%"), "{}", rows[big]);
+    }
+
+    #[test]
+    fn render_breaks_symbols_down_by_extension() {
+        let mut app = test_app();
+        app.project_tree.files.push(FileSymbols {
+            file_path: "mock/init.lua".into(),
+            symbols: vec![sym("l1", "f"), sym("l2", "g")],
+            total_lines: 5,
+            modified: None,
+            required: None,
+        });
+
+        let backend = TestBackend::new(40, 24);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| render(f, &app, f.area())).unwrap();
+
+        let buf = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buf.area.height)
+            .map(|y| (0..buf.area.width).map(|x| buf[(x, y)].symbol().to_string()).collect())
+            .collect();
+        let lua = rows.iter().position(|r| r.contains(".lua") && r.contains("2 in 1 files")).unwrap();
+        let rs = rows.iter().position(|r| r.contains(".rs ") && r.contains("1 in 1 files")).unwrap();
+        assert!(lua < rs);
+    }
+
+    #[test]
+    fn bar_fills_proportionally() {
+        assert_eq!(bar(0, 4), "░░░░");
+        assert_eq!(bar(50, 4), "██░░");
+        assert_eq!(bar(100, 4), "████");
+    }
+
+    #[test]
+    fn braille_sparkline_scales_to_range() {
+        assert_eq!(braille_sparkline(&[0, 100]), "\u{28f8}");
+        assert_eq!(braille_sparkline(&[50, 50, 50]), "\u{28c0}\u{2840}");
+        assert_eq!(braille_sparkline(&[10, 20, 30, 40]).chars().count(), 2);
+    }
+
+    #[test]
+    fn render_shows_trend_once_sampled() {
+        let mut app = test_app();
+        app.coverage_history.extend([0, 100]);
+        let backend = TestBackend::new(40, 20);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| render(f, &app, f.area())).unwrap();
+        assert!(fg_color_of(terminal.backend(), "0\u{2192}100%").is_some());
     }
 }
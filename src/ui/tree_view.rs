@@ -21,6 +21,9 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
         .borders(Borders::ALL)
         .border_style(border_style);
 
+    // Inside the borders.
+    let inner_width = area.width.saturating_sub(2) as usize;
+
     let items: Vec<ListItem> = app
         .tree_rows
         .iter()
@@ -40,13 +43,23 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
                 Span::raw(indent),
                 Span::styled(icon, Style::default().fg(Color::DarkGray)),
             ];
+            // Everything after the name, so the name can be cut to fit beside it.
+            let mut suffix = Vec::new();
+            let name_style;
 
             if row.is_file {
                 let file_color = file_coverage_color(row.coverage_status);
-                spans.push(Span::styled(
-                    &row.display_name,
-                    Style::default().fg(file_color).add_modifier(Modifier::BOLD),
-                ));
+                name_style = Style::default().fg(file_color).add_modifier(Modifier::BOLD);
+                let spans = &mut suffix;
+                if row.is_pinned {
+                    spans.push(Span::styled(" \u{2605}", Style::default().fg(Color::Yellow)));
+                }
+                if row.modified_since_read {
+                    spans.push(Span::styled(" \u{21bb}", Style::default().fg(colors::palette().depth_stale)));
+                }
+                if row.is_planned {
+                    spans.push(Span::styled(" \u{25c7}", Style::default().fg(Color::Cyan)));
+                }
                 if row.file_coverage_total > 0 {
                     spans.push(Span::styled(
                         format!("  {}/{}", row.file_coverage_seen, row.file_coverage_total),
@@ -54,7 +67,7 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
                     ));
                 }
                 spans.push(Span::styled(
-                    format!("  ({})", row.line_range),
+                    format!("  ({})", ambits::app::file_annotation(row, &row.size_label(app.show_lines))),
                     Style::default().fg(Color::DarkGray),
                 ));
             } else {
@@ -62,13 +75,36 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
                     format!("{} ", row.label),
                     Style::default().fg(Color::DarkGray),
                 ));
-                spans.push(Span::styled(&row.display_name, Style::default().fg(color)));
+                // Coverage an edit or search implied, rather than a read, is dimmed.
+                name_style = if row.is_inferred {
+                    Style::default().fg(color).add_modifier(Modifier::DIM | Modifier::ITALIC)
+                } else {
+                    Style::default().fg(color)
+                };
+                let spans = &mut suffix;
+                if row.file_coverage_total > 0 {
+                    spans.push(Span::styled(
+                        format!("  {}/{}", row.file_coverage_seen, row.file_coverage_total),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                if row.has_note {
+                    spans.push(Span::styled(" \u{270e}", Style::default().fg(Color::Magenta)));
+                }
+                if row.is_planned {
+                    spans.push(Span::styled(" \u{25c7}", Style::default().fg(Color::Cyan)));
+                }
                 spans.push(Span::styled(
-                    format!("  [{}] ~{} tok", row.line_range, row.token_count),
+                    format!("  [{}] {}", row.line_range, row.size_label(app.show_lines)),
                     Style::default().fg(Color::DarkGray),
                 ));
             }
 
+            let used: usize = spans.iter().chain(&suffix).map(Span::width).sum();
+            let name = truncate_name(&row.display_name, inner_width.saturating_sub(used));
+            spans.push(Span::styled(name, name_style));
+            spans.extend(suffix);
+
             ListItem::new(Line::from(spans))
         })
         .collect();
@@ -80,31 +116,42 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
         .block(block)
         .highlight_style(
             Style::default()
-                .bg(colors::HIGHLIGHT_BG)
-                .fg(colors::HIGHLIGHT_FG)
+                .bg(colors::palette().highlight_bg)
+                .fg(colors::palette().highlight_fg)
                 .add_modifier(Modifier::BOLD),
         );
 
     f.render_stateful_widget(list, area, &mut state);
 }
 
+/// `name` cut to at most `max` characters, ending in an ellipsis when cut.
+/// The full name stays on the row for the detail popup.
+fn truncate_name(name: &str, max: usize) -> String {
+    if name.chars().count() <= max {
+        return name.to_string();
+    }
+    let mut out: String = name.chars().take(max.saturating_sub(1)).collect();
+    out.push('\u{2026}');
+    out
+}
+
 fn depth_color(depth: ReadDepth) -> Color {
     match depth {
-        ReadDepth::Unseen => colors::DEPTH_UNSEEN,
-        ReadDepth::NameOnly => colors::DEPTH_NAME_ONLY,
-        ReadDepth::Overview => colors::DEPTH_OVERVIEW,
-        ReadDepth::Signature => colors::DEPTH_SIGNATURE,
-        ReadDepth::FullBody => colors::DEPTH_FULL_BODY,
-        ReadDepth::Stale => colors::DEPTH_STALE,
+        ReadDepth::Unseen => colors::palette().depth_unseen,
+        ReadDepth::NameOnly => colors::palette().depth_name_only,
+        ReadDepth::Overview => colors::palette().depth_overview,
+        ReadDepth::Signature => colors::palette().depth_signature,
+        ReadDepth::FullBody => colors::palette().depth_full_body,
+        ReadDepth::Stale => colors::palette().depth_stale,
     }
 }
 
 fn file_coverage_color(status: Option<FileCoverageStatus>) -> Color {
     match status {
-        Some(FileCoverageStatus::FullyCovered) => colors::FILE_FULLY_COVERED,
-        Some(FileCoverageStatus::AllSeen) => colors::FILE_ALL_SEEN,
-        Some(FileCoverageStatus::PartiallyCovered) => colors::FILE_PARTIALLY_COVERED,
-        _ => colors::FILE_NOT_COVERED,
+        Some(FileCoverageStatus::FullyCovered) => colors::palette().file_fully_covered,
+        Some(FileCoverageStatus::AllSeen) => colors::palette().file_all_seen,
+        Some(FileCoverageStatus::PartiallyCovered) => colors::palette().file_partially_covered,
+        _ => colors::palette().file_not_covered,
     }
 }
 
@@ -123,6 +170,10 @@ mod tests {
             label: "fn".into(), file_path: PathBuf::new(),
             byte_range: 0..100, line_range: 1..10, content_hash: hash,
             merkle_hash: hash, children: Vec::new(), estimated_tokens: 30,
+            derives: Vec::new(),
+            decorators: Vec::new(),
+            test_only: false,
+            name_range: None,
         }
     }
 
@@ -130,8 +181,8 @@ mod tests {
         let tree = ProjectTree {
             root: PathBuf::from("/test"),
             files: vec![
-                FileSymbols { file_path: "mock/a.rs".into(), symbols: vec![sym("a1", "alpha"), sym("a2", "beta")], total_lines: 50 },
-                FileSymbols { file_path: "mock/b.rs".into(), symbols: vec![sym("b1", "gamma")], total_lines: 30 },
+                FileSymbols { file_path: "mock/a.rs".into(), symbols: vec![sym("a1", "alpha"), sym("a2", "beta")], total_lines: 50, modified: None, required: None },
+                FileSymbols { file_path: "mock/b.rs".into(), symbols: vec![sym("b1", "gamma")], total_lines: 30, modified: None, required: None },
             ],
         };
         App::new(tree, PathBuf::from("/test"), None)
@@ -149,21 +200,51 @@ mod tests {
 
     #[test]
     fn file_coverage_color_variants() {
-        assert_eq!(file_coverage_color(Some(FileCoverageStatus::FullyCovered)), colors::FILE_FULLY_COVERED);
-        assert_eq!(file_coverage_color(Some(FileCoverageStatus::AllSeen)), colors::FILE_ALL_SEEN);
-        assert_eq!(file_coverage_color(Some(FileCoverageStatus::PartiallyCovered)), colors::FILE_PARTIALLY_COVERED);
-        assert_eq!(file_coverage_color(Some(FileCoverageStatus::NotCovered)), colors::FILE_NOT_COVERED);
-        assert_eq!(file_coverage_color(None), colors::FILE_NOT_COVERED);
+        assert_eq!(file_coverage_color(Some(FileCoverageStatus::FullyCovered)), colors::palette().file_fully_covered);
+        assert_eq!(file_coverage_color(Some(FileCoverageStatus::AllSeen)), colors::palette().file_all_seen);
+        assert_eq!(file_coverage_color(Some(FileCoverageStatus::PartiallyCovered)), colors::palette().file_partially_covered);
+        assert_eq!(file_coverage_color(Some(FileCoverageStatus::NotCovered)), colors::palette().file_not_covered);
+        assert_eq!(file_coverage_color(None), colors::palette().file_not_covered);
     }
 
     #[test]
     fn depth_color_variants() {
-        assert_eq!(depth_color(ReadDepth::Unseen), colors::DEPTH_UNSEEN);
-        assert_eq!(depth_color(ReadDepth::NameOnly), colors::DEPTH_NAME_ONLY);
-        assert_eq!(depth_color(ReadDepth::Overview), colors::DEPTH_OVERVIEW);
-        assert_eq!(depth_color(ReadDepth::Signature), colors::DEPTH_SIGNATURE);
-        assert_eq!(depth_color(ReadDepth::FullBody), colors::DEPTH_FULL_BODY);
-        assert_eq!(depth_color(ReadDepth::Stale), colors::DEPTH_STALE);
+        assert_eq!(depth_color(ReadDepth::Unseen), colors::palette().depth_unseen);
+        assert_eq!(depth_color(ReadDepth::NameOnly), colors::palette().depth_name_only);
+        assert_eq!(depth_color(ReadDepth::Overview), colors::palette().depth_overview);
+        assert_eq!(depth_color(ReadDepth::Signature), colors::palette().depth_signature);
+        assert_eq!(depth_color(ReadDepth::FullBody), colors::palette().depth_full_body);
+        assert_eq!(depth_color(ReadDepth::Stale), colors::palette().depth_stale);
+    }
+
+    #[test]
+    fn truncate_name_adds_ellipsis() {
+        assert_eq!(truncate_name("short", 10), "short");
+        assert_eq!(truncate_name("exactly", 7), "exactly");
+        assert_eq!(truncate_name("impl<T: Trait + Send> Foo<T>", 10), "impl<T: T\u{2026}");
+        assert_eq!(truncate_name("name", 0), "\u{2026}");
+    }
+
+    #[test]
+    fn render_keeps_annotation_visible_for_long_names() {
+        let long = "impl<T: Trait + Send + Sync + 'static> VeryLongGenericName<T>";
+        let tree = ProjectTree {
+            root: PathBuf::from("/test"),
+            files: vec![FileSymbols { file_path: "a.rs".into(), symbols: vec![sym("a1", long)], total_lines: 10, modified: None, required: None }],
+        };
+        let mut app = App::new(tree, PathBuf::from("/test"), None);
+        app.collapsed.remove("a.rs");
+        app.rebuild_tree_rows();
+
+        let backend = TestBackend::new(50, 6);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| render(f, &app, f.area())).unwrap();
+
+        let buf = terminal.backend().buffer();
+        let row: String = (0..buf.area.width).map(|x| buf[(x, 2)].symbol().to_string()).collect();
+        assert!(row.contains("\u{2026}"), "{row}");
+        assert!(row.contains("~30 tok"), "{row}");
+        assert_eq!(app.tree_rows[1].display_name, long);
     }
 
     #[test]
@@ -176,7 +257,22 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), 1, "mock/a.rs").unwrap();
-        assert_eq!(color, colors::FILE_NOT_COVERED);
+        assert_eq!(color, colors::palette().file_not_covered);
+    }
+
+    #[test]
+    fn render_uses_active_theme() {
+        let mut app = test_app();
+        app.selected_index = 1;
+        colors::set_palette(colors::theme("light").unwrap());
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| render(f, &app, f.area())).unwrap();
+        colors::set_palette(&colors::DEFAULT);
+
+        let color = fg_color_of(terminal.backend(), 1, "mock/a.rs").unwrap();
+        assert_eq!(color, colors::LIGHT.file_not_covered);
+        assert!(colors::theme("solarized").is_none());
     }
 
     #[test]
@@ -192,7 +288,7 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), 1, "mock/a.rs").unwrap();
-        assert_eq!(color, colors::FILE_FULLY_COVERED);
+        assert_eq!(color, colors::palette().file_fully_covered);
     }
 
     #[test]
@@ -206,7 +302,7 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), 2, "mock/b.rs").unwrap();
-        assert_eq!(color, colors::FILE_ALL_SEEN);
+        assert_eq!(color, colors::palette().file_all_seen);
     }
 
     #[test]
@@ -221,7 +317,7 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), 1, "mock/a.rs").unwrap();
-        assert_eq!(color, colors::FILE_PARTIALLY_COVERED);
+        assert_eq!(color, colors::palette().file_partially_covered);
     }
 
     #[test]
@@ -237,6 +333,6 @@ mod tests {
         terminal.draw(|f| render(f, &app, f.area())).unwrap();
 
         let color = fg_color_of(terminal.backend(), 2, "alpha").unwrap();
-        assert_eq!(color, colors::DEPTH_FULL_BODY);
+        assert_eq!(color, colors::palette().depth_full_body);
     }
 }
@@ -0,0 +1,59 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use ambits::app::App;
+
+use super::colors;
+
+/// Banner along the bottom of `area` listing unseen symbols in a just-edited file.
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let Some(ref alert) = app.unseen_alert else {
+        return;
+    };
+
+    let max_rows = 6usize;
+    let mut lines: Vec<Line> = alert
+        .symbols
+        .iter()
+        .take(max_rows)
+        .map(|s| {
+            Line::from(vec![
+                Span::styled(format!(" {} ", s.label), Style::default().fg(Color::DarkGray)),
+                Span::styled(s.name.clone(), Style::default().fg(colors::palette().depth_stale)),
+                Span::styled(
+                    format!("  L{}-{}", s.line_range.start, s.line_range.end),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        })
+        .collect();
+    if alert.symbols.len() > max_rows {
+        lines.push(Line::styled(
+            format!(" … and {} more", alert.symbols.len() - max_rows),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x,
+        y: area.y + area.height - height,
+        width: area.width,
+        height,
+    };
+
+    let block = Block::default()
+        .title(format!(
+            " Edited {} — {} unseen [esc] ",
+            alert.file_path.display(),
+            alert.symbols.len()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors::palette().depth_stale));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(lines).block(block), popup);
+}
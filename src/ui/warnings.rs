@@ -0,0 +1,63 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use ambits::app::App;
+
+/// Popup with the paths agents touched that aren't in the tree, busiest
+/// first, followed by the latest path-matching warnings.
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    if !app.show_warnings {
+        return;
+    }
+
+    let heading = |text: String| Line::from(Span::styled(text, Style::default().add_modifier(Modifier::BOLD)));
+    let mut lines = vec![Line::from("")];
+
+    let events: usize = app.orphan_reads.values().sum();
+    if app.orphan_reads.is_empty() {
+        lines.push(Line::from(Span::styled("  Every path agents touched is in the tree", Style::default().fg(Color::DarkGray))));
+    } else {
+        let files = app.orphan_reads.len();
+        lines.push(heading(format!(
+            "  {events} event{} on {files} file{} not in the tree",
+            if events == 1 { "" } else { "s" },
+            if files == 1 { "" } else { "s" },
+        )));
+        let mut orphans: Vec<_> = app.orphan_reads.iter().collect();
+        orphans.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (path, count) in orphans {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {count:>5}  "), Style::default().fg(Color::DarkGray)),
+                Span::raw(path.display().to_string()),
+            ]));
+        }
+    }
+
+    if !app.warnings.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(heading("  Path matches".to_string()));
+        for warning in app.warnings.iter().rev() {
+            lines.push(Line::from(Span::styled(format!("  {warning}"), Style::default().fg(Color::Yellow))));
+        }
+    }
+
+    let width = (area.width * 3 / 4).max(40).min(area.width);
+    let height = (lines.len() as u16 + 3).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let block = Block::default()
+        .title(" Warnings [esc] ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(lines).block(block), popup);
+}
@@ -32,6 +32,10 @@ fn sym(id: &str, name: &str) -> SymbolNode {
         merkle_hash: hash,
         children: Vec::new(),
         estimated_tokens: 30,
+        derives: Vec::new(),
+        decorators: Vec::new(),
+        test_only: false,
+        name_range: None,
     }
 }
 
@@ -48,6 +52,8 @@ fn file(path: &str, symbols: Vec<SymbolNode>) -> FileSymbols {
         file_path,
         symbols,
         total_lines: 100,
+        modified: None,
+        required: None,
     }
 }
 
@@ -278,3 +284,32 @@ fn text_formatter_structure() {
     assert!(output.contains("TOTAL"), "should contain total row");
     assert!(output.contains("100%"), "should show 100% for full coverage");
 }
+
+/// Reading a session's log after its edits landed: each edit is found by its
+/// new text, so `--check` flags only the symbol edited, not the whole file.
+#[test]
+fn offline_edit_touches_only_the_edited_symbol() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.rs");
+    // The file as it is after the edit below.
+    std::fs::write(&path, "fn a() {}\n\nfn b() {\n    3\n}\n").unwrap();
+    let mut a = sym("a.rs::a", "a");
+    a.line_range = 1..1;
+    let mut b = sym("a.rs::b", "b");
+    b.line_range = 3..5;
+    let mut app = App::new(
+        ProjectTree { root: dir.path().to_path_buf(), files: vec![file("a.rs", vec![a, b])] },
+        dir.path().to_path_buf(),
+        None,
+    );
+
+    let input = serde_json::json!({"file_path": path, "old_string": "    2", "new_string": "    3"});
+    let tmp = write_jsonl(&[format!(
+        r#"{{"type":"assistant","sessionId":"s1","timestamp":"2025-01-01T00:00:00Z","message":{{"role":"assistant","content":[{{"type":"tool_use","name":"Edit","input":{input}}}]}}}}"#
+    )]);
+    for event in parse_log_file(tmp.path()) {
+        app.process_agent_event(event);
+    }
+
+    assert_eq!(ambits::rules::edited_before_read(&app.ledger), ["a.rs::b"]);
+}
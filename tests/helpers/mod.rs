@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use crate::ingest::AgentToolCall;
 use crate::symbols::{FileSymbols, ProjectTree, SymbolCategory, SymbolNode};
-use crate::tracking::ReadDepth;
+use crate::tracking::{ReadDepth, ReadSource};
 
 /// Create a mock SymbolNode for testing.
 /// The `file_path` is left empty; `file()` sets it to match the parent `FileSymbols`.
@@ -20,6 +20,10 @@ pub fn sym(id: &str, name: &str) -> SymbolNode {
         merkle_hash: hash,
         children: Vec::new(),
         estimated_tokens: 30,
+        derives: Vec::new(),
+        decorators: Vec::new(),
+        test_only: false,
+        name_range: None,
     }
 }
 
@@ -51,6 +55,8 @@ pub fn file(path: &str, symbols: Vec<SymbolNode>) -> FileSymbols {
         file_path,
         symbols,
         total_lines: 100,
+        modified: None,
+        required: None,
     }
 }
 
@@ -73,6 +79,11 @@ pub fn tool_call(tool: &str, path: &str, depth: ReadDepth) -> AgentToolCall {
         timestamp_str: "2025-01-01T00:00:00Z".to_string(),
         target_symbol: None,
         target_lines: None,
+        path_glob: None,
+        symbol_depth_limit: None,
+        todos: Vec::new(),
+        source: ReadSource::Explicit,
+        sub_index: 0,
     }
 }
 